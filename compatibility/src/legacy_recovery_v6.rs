@@ -55,6 +55,13 @@ impl Default for AccountRole {
     }
 }
 
+/// The tower state of a legacy miner, only the fields read after the fork
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct LegacyMinerStateV6 {
+    /// the last epoch the account submitted a proof
+    pub latest_epoch_mining: u64,
+}
+
 #[derive(Debug, Default, Clone, Serialize, Deserialize)]
 pub struct LegacyRecoveryV6 {
     /// The account address associated with this recovery.
@@ -75,6 +82,9 @@ pub struct LegacyRecoveryV6 {
     /// Validator operator configuration resource.
     pub val_operator_cfg: Option<ValidatorOperatorConfigResource>,
 
+    /// Tower state of the account, if it mined.
+    pub miner_state: Option<LegacyMinerStateV6>,
+
     /// Community wallets associated with the account.
     pub comm_wallet: Option<CommunityWalletsResource>,
 
@@ -161,6 +171,7 @@ pub fn get_legacy_recovery(account_state: &AccountState) -> anyhow::Result<Legac
         balance: None,
         val_cfg: None,
         val_operator_cfg: None,
+        miner_state: None,
         comm_wallet: None,
         currency_info: None, // TODO: DO WE NEED THIS
         ancestry: None,
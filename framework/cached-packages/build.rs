@@ -1,10 +1,12 @@
-use libra_framework::release::ReleaseTarget;
+use libra_framework::{builder::view_bindings, release::ReleaseTarget};
 use std::{env::current_dir, path::PathBuf};
 
 fn main() {
+    let out_dir = PathBuf::from(std::env::var("OUT_DIR").expect("OUT_DIR defined"));
+
     // Set the below variable to skip the building step. This might be useful if the build
     // is broken so it can be debugged with the old outdated artifacts.
-    if std::env::var("LIBRA_BUILD_FRAMEWORK").is_ok() {
+    let bundle_path = if std::env::var("LIBRA_BUILD_FRAMEWORK").is_ok() {
         let current_dir = current_dir().expect("Should be able to get current dir");
         // Get the previous directory
         let mut prev_dir = current_dir;
@@ -19,14 +21,22 @@ fn main() {
         );
 
         // TODO: make this run the libra binary if it is found in users $PATH
+        let out = out_dir.join("head.mrb");
         ReleaseTarget::Head
-            .create_release(
-                false,
-                Some(
-                    PathBuf::from(std::env::var("OUT_DIR").expect("OUT_DIR defined"))
-                        .join("head.mrb"),
-                ),
-            )
+            .create_release(false, Some(out.clone()))
             .expect("release build failed");
-    }
+        out
+    } else {
+        // otherwise the view bindings always come from the tracked mainnet release,
+        // so they don't depend on untracked bundles in the checkout
+        ReleaseTarget::Mainnet
+            .find_bundle_path()
+            .expect("could not find release path")
+    };
+    println!("cargo:rerun-if-env-changed=LIBRA_BUILD_FRAMEWORK");
+    println!("cargo:rerun-if-changed={}", bundle_path.display());
+
+    let bundle = ReleaseTarget::load_bundle_from_file(bundle_path).expect("could not load release");
+    view_bindings::write_view_bindings(&bundle, &out_dir.join(view_bindings::VIEW_BINDINGS_FILE))
+        .expect("could not generate view bindings");
 }
@@ -1,3 +1,10 @@
 #![allow(clippy::doc_lazy_continuation)]
 pub mod libra_framework_sdk_builder;
 pub mod libra_stdlib;
+pub mod view_support;
+
+/// typed bindings for the #[view] functions, generated by the build script
+#[allow(unused_imports, clippy::too_many_arguments, clippy::redundant_closure)]
+pub mod libra_framework_views {
+    include!(concat!(env!("OUT_DIR"), "/libra_framework_views.rs"));
+}
@@ -234,6 +234,12 @@ pub enum EntryFunctionCall {
 
     FiloMigrationMaybeMigrate {},
 
+    /// Transaction script for user to pledge to infra escrow.
+    /// Only once the USER_PLEDGE_INFRA_ENABLED feature is enabled.
+    InfraEscrowUserPledgeInfra {
+        amount: u64,
+    },
+
     /// Only a Voucher of the validator can flip the unjail bit.
     /// This is a way to make sure the validator is ready to rejoin.
     JailUnjailByVoucher {
@@ -364,6 +370,13 @@ pub enum EntryFunctionCall {
         fullnode_addresses: Vec<u8>,
     },
 
+    /// Rotate the consensus key of the validator, it'll take effect in next epoch.
+    StakeRotateConsensusKey {
+        validator_address: AccountAddress,
+        new_consensus_pubkey: Vec<u8>,
+        proof_of_possession: Vec<u8>,
+    },
+
     /// Update the network and full node addresses of the validator. This only takes effect in the next epoch.
     /// Entry function necessary for validator configuration
     StakeUpdateNetworkAndFullnodeAddresses {
@@ -517,6 +530,7 @@ impl EntryFunctionCall {
             EpochBoundarySmokeEnableTrigger {} => epoch_boundary_smoke_enable_trigger(),
             EpochBoundarySmokeTriggerEpoch {} => epoch_boundary_smoke_trigger_epoch(),
             FiloMigrationMaybeMigrate {} => filo_migration_maybe_migrate(),
+            InfraEscrowUserPledgeInfra { amount } => infra_escrow_user_pledge_infra(amount),
             JailUnjailByVoucher { addr } => jail_unjail_by_voucher(addr),
             LibraCoinMintToImpl { dst_addr, amount } => libra_coin_mint_to_impl(dst_addr, amount),
             MultiActionClaimOffer { multisig_address } => {
@@ -565,6 +579,15 @@ impl EntryFunctionCall {
                 network_addresses,
                 fullnode_addresses,
             ),
+            StakeRotateConsensusKey {
+                validator_address,
+                new_consensus_pubkey,
+                proof_of_possession,
+            } => stake_rotate_consensus_key(
+                validator_address,
+                new_consensus_pubkey,
+                proof_of_possession,
+            ),
             StakeUpdateNetworkAndFullnodeAddresses {
                 validator_address,
                 new_network_addresses,
@@ -1155,6 +1178,23 @@ pub fn filo_migration_maybe_migrate() -> TransactionPayload {
     ))
 }
 
+/// Transaction script for user to pledge to infra escrow.
+/// Only once the USER_PLEDGE_INFRA_ENABLED feature is enabled.
+pub fn infra_escrow_user_pledge_infra(amount: u64) -> TransactionPayload {
+    TransactionPayload::EntryFunction(EntryFunction::new(
+        ModuleId::new(
+            AccountAddress::new([
+                0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+                0, 0, 0, 1,
+            ]),
+            ident_str!("infra_escrow").to_owned(),
+        ),
+        ident_str!("user_pledge_infra").to_owned(),
+        vec![],
+        vec![bcs::to_bytes(&amount).unwrap()],
+    ))
+}
+
 /// Only a Voucher of the validator can flip the unjail bit.
 /// This is a way to make sure the validator is ready to rejoin.
 pub fn jail_unjail_by_voucher(addr: AccountAddress) -> TransactionPayload {
@@ -1528,6 +1568,30 @@ pub fn stake_initialize_validator(
     ))
 }
 
+/// Rotate the consensus key of the validator, it'll take effect in next epoch.
+pub fn stake_rotate_consensus_key(
+    validator_address: AccountAddress,
+    new_consensus_pubkey: Vec<u8>,
+    proof_of_possession: Vec<u8>,
+) -> TransactionPayload {
+    TransactionPayload::EntryFunction(EntryFunction::new(
+        ModuleId::new(
+            AccountAddress::new([
+                0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+                0, 0, 0, 1,
+            ]),
+            ident_str!("stake").to_owned(),
+        ),
+        ident_str!("rotate_consensus_key").to_owned(),
+        vec![],
+        vec![
+            bcs::to_bytes(&validator_address).unwrap(),
+            bcs::to_bytes(&new_consensus_pubkey).unwrap(),
+            bcs::to_bytes(&proof_of_possession).unwrap(),
+        ],
+    ))
+}
+
 /// Update the network and full node addresses of the validator. This only takes effect in the next epoch.
 /// Entry function necessary for validator configuration
 pub fn stake_update_network_and_fullnode_addresses(
@@ -1924,6 +1988,18 @@ mod decoder {
         }
     }
 
+    pub fn infra_escrow_user_pledge_infra(
+        payload: &TransactionPayload,
+    ) -> Option<EntryFunctionCall> {
+        if let TransactionPayload::EntryFunction(script) = payload {
+            Some(EntryFunctionCall::InfraEscrowUserPledgeInfra {
+                amount: bcs::from_bytes(script.args().first()?).ok()?,
+            })
+        } else {
+            None
+        }
+    }
+
     pub fn jail_unjail_by_voucher(payload: &TransactionPayload) -> Option<EntryFunctionCall> {
         if let TransactionPayload::EntryFunction(script) = payload {
             Some(EntryFunctionCall::JailUnjailByVoucher {
@@ -2132,6 +2208,18 @@ mod decoder {
         }
     }
 
+    pub fn stake_rotate_consensus_key(payload: &TransactionPayload) -> Option<EntryFunctionCall> {
+        if let TransactionPayload::EntryFunction(script) = payload {
+            Some(EntryFunctionCall::StakeRotateConsensusKey {
+                validator_address: bcs::from_bytes(script.args().first()?).ok()?,
+                new_consensus_pubkey: bcs::from_bytes(script.args().get(1)?).ok()?,
+                proof_of_possession: bcs::from_bytes(script.args().get(2)?).ok()?,
+            })
+        } else {
+            None
+        }
+    }
+
     pub fn stake_update_network_and_fullnode_addresses(
         payload: &TransactionPayload,
     ) -> Option<EntryFunctionCall> {
@@ -2298,6 +2386,10 @@ static SCRIPT_FUNCTION_DECODER_MAP: once_cell::sync::Lazy<EntryFunctionDecoderMa
             "filo_migration_maybe_migrate".to_string(),
             Box::new(decoder::filo_migration_maybe_migrate),
         );
+        map.insert(
+            "infra_escrow_user_pledge_infra".to_string(),
+            Box::new(decoder::infra_escrow_user_pledge_infra),
+        );
         map.insert(
             "jail_unjail_by_voucher".to_string(),
             Box::new(decoder::jail_unjail_by_voucher),
@@ -2374,6 +2466,10 @@ static SCRIPT_FUNCTION_DECODER_MAP: once_cell::sync::Lazy<EntryFunctionDecoderMa
             "stake_initialize_validator".to_string(),
             Box::new(decoder::stake_initialize_validator),
         );
+        map.insert(
+            "stake_rotate_consensus_key".to_string(),
+            Box::new(decoder::stake_rotate_consensus_key),
+        );
         map.insert(
             "stake_update_network_and_fullnode_addresses".to_string(),
             Box::new(decoder::stake_update_network_and_fullnode_addresses),
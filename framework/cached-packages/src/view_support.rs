@@ -0,0 +1,229 @@
+//! Helpers for the generated view function bindings in `libra_framework_views`.
+//! The view API returns JSON, u64 and larger numbers come back as strings,
+//! vector<u8> as a hex string, and an Option as `{"vec": [..]}`.
+
+use anyhow::{bail, Context};
+use diem_api_types::{EntryFunctionId, MoveType, ViewRequest};
+use diem_rest_client::Client;
+use diem_types::account_address::AccountAddress;
+use move_core_types::u256::U256;
+use serde_json::Value;
+use std::str::FromStr;
+
+/// call a view function, returns the list of values
+pub async fn view(
+    client: &Client,
+    function: &str,
+    type_arguments: &[&str],
+    arguments: Vec<Value>,
+) -> anyhow::Result<Vec<Value>> {
+    let request = ViewRequest {
+        function: EntryFunctionId::from_str(function)?,
+        type_arguments: type_arguments
+            .iter()
+            .map(|t| MoveType::from_str(t))
+            .collect::<Result<_, _>>()?,
+        arguments,
+    };
+    let res = client
+        .view(&request, None)
+        .await
+        .context(format!("view function {} failed", function))?
+        .into_inner();
+    Ok(res)
+}
+
+/// the i-th returned value
+pub fn ret<'a>(res: &'a [Value], i: usize, function: &str) -> anyhow::Result<&'a Value> {
+    res.get(i).context(format!(
+        "{} returned {} values, expected more than {}",
+        function,
+        res.len(),
+        i
+    ))
+}
+
+/// a field of a returned struct
+pub fn field<'a>(v: &'a Value, name: &str) -> anyhow::Result<&'a Value> {
+    v.get(name)
+        .context(format!("field {} missing from {}", name, v))
+}
+
+fn number<T: FromStr>(v: &Value) -> anyhow::Result<T> {
+    let s = match v {
+        Value::String(s) => s.to_owned(),
+        Value::Number(n) => n.to_string(),
+        _ => bail!("expected a number, got {}", v),
+    };
+    s.parse()
+        .ok()
+        .context(format!("could not parse number {}", s))
+}
+
+pub fn decode_bool(v: &Value) -> anyhow::Result<bool> {
+    v.as_bool().context(format!("expected a bool, got {}", v))
+}
+
+pub fn decode_u8(v: &Value) -> anyhow::Result<u8> {
+    number(v)
+}
+
+pub fn decode_u16(v: &Value) -> anyhow::Result<u16> {
+    number(v)
+}
+
+pub fn decode_u32(v: &Value) -> anyhow::Result<u32> {
+    number(v)
+}
+
+pub fn decode_u64(v: &Value) -> anyhow::Result<u64> {
+    number(v)
+}
+
+pub fn decode_u128(v: &Value) -> anyhow::Result<u128> {
+    number(v)
+}
+
+pub fn decode_u256(v: &Value) -> anyhow::Result<U256> {
+    number(v)
+}
+
+pub fn decode_address(v: &Value) -> anyhow::Result<AccountAddress> {
+    let s = v
+        .as_str()
+        .context(format!("expected an address, got {}", v))?;
+    Ok(AccountAddress::from_hex_literal(s)?)
+}
+
+pub fn decode_bytes(v: &Value) -> anyhow::Result<Vec<u8>> {
+    let s = v
+        .as_str()
+        .context(format!("expected hex bytes, got {}", v))?;
+    Ok(hex::decode(s.trim_start_matches("0x"))?)
+}
+
+pub fn decode_string(v: &Value) -> anyhow::Result<String> {
+    v.as_str()
+        .map(|s| s.to_owned())
+        .context(format!("expected a string, got {}", v))
+}
+
+pub fn decode_json(v: &Value) -> anyhow::Result<Value> {
+    Ok(v.clone())
+}
+
+pub fn decode_vec<T>(v: &Value, f: impl Fn(&Value) -> anyhow::Result<T>) -> anyhow::Result<Vec<T>> {
+    v.as_array()
+        .context(format!("expected a vector, got {}", v))?
+        .iter()
+        .map(f)
+        .collect()
+}
+
+/// an Option is a struct with a vector of zero or one element
+pub fn decode_option<T>(
+    v: &Value,
+    f: impl Fn(&Value) -> anyhow::Result<T>,
+) -> anyhow::Result<Option<T>> {
+    let mut inner = decode_vec(field(v, "vec")?, f)?;
+    match inner.len() {
+        0 | 1 => Ok(inner.pop()),
+        n => bail!("option with {} elements", n),
+    }
+}
+
+pub fn encode_bool(v: &bool) -> Value {
+    Value::Bool(*v)
+}
+
+pub fn encode_u8(v: &u8) -> Value {
+    Value::from(*v)
+}
+
+pub fn encode_u16(v: &u16) -> Value {
+    Value::from(*v)
+}
+
+pub fn encode_u32(v: &u32) -> Value {
+    Value::from(*v)
+}
+
+pub fn encode_u64(v: &u64) -> Value {
+    Value::String(v.to_string())
+}
+
+pub fn encode_u128(v: &u128) -> Value {
+    Value::String(v.to_string())
+}
+
+pub fn encode_u256(v: &U256) -> Value {
+    Value::String(v.to_string())
+}
+
+pub fn encode_address(v: &AccountAddress) -> Value {
+    Value::String(v.to_hex_literal())
+}
+
+#[allow(clippy::ptr_arg)]
+pub fn encode_bytes(v: &Vec<u8>) -> Value {
+    Value::String(format!("0x{}", hex::encode(v)))
+}
+
+#[allow(clippy::ptr_arg)]
+pub fn encode_string(v: &String) -> Value {
+    Value::String(v.to_owned())
+}
+
+pub fn encode_json(v: &Value) -> Value {
+    v.clone()
+}
+
+pub fn encode_vec<T>(v: &[T], f: impl Fn(&T) -> Value) -> Value {
+    Value::Array(v.iter().map(f).collect())
+}
+
+pub fn encode_option<T>(v: &Option<T>, f: impl Fn(&T) -> Value) -> Value {
+    serde_json::json!({ "vec": v.iter().map(f).collect::<Vec<_>>() })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn decodes_view_json() {
+        assert_eq!(
+            decode_u64(&json!("18446744073709551615")).unwrap(),
+            u64::MAX
+        );
+        assert_eq!(decode_u8(&json!(7)).unwrap(), 7);
+        assert!(decode_u8(&json!("300")).is_err());
+        assert_eq!(decode_bytes(&json!("0x0aff")).unwrap(), vec![10, 255]);
+        assert_eq!(decode_address(&json!("0x1")).unwrap(), AccountAddress::ONE);
+        let nested = json!([["1", "2"], []]);
+        assert_eq!(
+            decode_vec(&nested, |x| decode_vec(x, decode_u64)).unwrap(),
+            vec![vec![1, 2], vec![]]
+        );
+        assert_eq!(
+            decode_option(&json!({ "vec": ["5"] }), decode_u128).unwrap(),
+            Some(5)
+        );
+        assert_eq!(
+            decode_option(&json!({ "vec": [] }), decode_u128).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn encodes_round_trip() {
+        let v = vec![AccountAddress::ONE];
+        let enc = encode_vec(&v, encode_address);
+        assert_eq!(decode_vec(&enc, decode_address).unwrap(), v);
+        let o = Some(u64::MAX);
+        let enc = encode_option(&o, encode_u64);
+        assert_eq!(decode_option(&enc, decode_u64).unwrap(), o);
+        assert_eq!(encode_bytes(&vec![1, 2]), json!("0x0102"));
+    }
+}
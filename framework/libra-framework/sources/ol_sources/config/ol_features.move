@@ -24,6 +24,26 @@ module ol_framework::ol_features_constants {
         features::is_enabled(GOVERNANCE_MODE_ENABLED)
     }
 
+    /// CONSENSUS KEY ROTATION
+    /// Validators can rotate their consensus key with
+    /// stake::rotate_consensus_key.
+    /// Lifetime: transient
+    const CONSENSUS_KEY_ROTATION_ENABLED: u64 = 26;
+    public fun get_consensus_key_rotation(): u64 { CONSENSUS_KEY_ROTATION_ENABLED }
+    public fun is_consensus_key_rotation_enabled(): bool {
+        features::is_enabled(CONSENSUS_KEY_ROTATION_ENABLED)
+    }
+
+    /// USER PLEDGE INFRA
+    /// Users can pledge coins to the infra escrow with
+    /// infra_escrow::user_pledge_infra.
+    /// Lifetime: transient
+    const USER_PLEDGE_INFRA_ENABLED: u64 = 27;
+    public fun get_user_pledge_infra(): u64 { USER_PLEDGE_INFRA_ENABLED }
+    public fun is_user_pledge_infra_enabled(): bool {
+        features::is_enabled(USER_PLEDGE_INFRA_ENABLED)
+    }
+
     //////// TEST HELPERS ////////
     #[test_only]
     const TEST_DUMMY_FLAG: u64 = 8675309;
@@ -18,6 +18,7 @@ module ol_framework::infra_escrow{
     use ol_framework::ol_account;
     use ol_framework::libra_coin::LibraCoin;
     use ol_framework::pledge_accounts;
+    use ol_framework::ol_features_constants;
 
 
 
@@ -28,6 +29,8 @@ module ol_framework::infra_escrow{
     friend ol_framework::mock;
 
     const EGENESIS_REWARD: u64 = 0;
+    /// Users can't pledge to the infra escrow until governance enables it
+    const EUSER_PLEDGE_DISABLED: u64 = 1;
     /// for use on genesis, creates the infra escrow pledge policy struct
     public(friend) fun initialize(framework: &signer) {
         // NOTE: THIS MUST BE THE 0x0 address, because on epoch boundary it is that address @vm_reserved which will be calling the functions.
@@ -72,8 +75,10 @@ module ol_framework::infra_escrow{
     }
 
 
-    // Transaction script for user to pledge to infra escrow.
-    fun user_pledge_infra(user_sig: &signer, amount: u64){
+    /// Transaction script for user to pledge to infra escrow.
+    /// Only once the USER_PLEDGE_INFRA_ENABLED feature is enabled.
+    public entry fun user_pledge_infra(user_sig: &signer, amount: u64){
+      assert!(ol_features_constants::is_user_pledge_infra_enabled(), error::invalid_state(EUSER_PLEDGE_DISABLED));
       pledge_accounts::user_pledge(user_sig, @ol_framework, amount);
     }
 
@@ -0,0 +1,40 @@
+#[test_only]
+module ol_framework::test_pledge_accounts {
+  use ol_framework::mock;
+  use ol_framework::ol_account;
+  use ol_framework::infra_escrow;
+  use ol_framework::ol_features_constants;
+  use ol_framework::pledge_accounts;
+  use std::features;
+  use std::signer;
+
+  #[test(root = @ol_framework, alice = @0x1000a)]
+  fun user_pledge_creates_and_tops_up(root: &signer, alice: &signer) {
+    mock::genesis_n_vals(root, 1);
+    mock::ol_initialize_coin_and_fund_vals(root, 10000, true);
+    features::change_feature_flags(root, vector[ol_features_constants::get_user_pledge_infra()], vector[]);
+    let alice_addr = signer::address_of(alice);
+    let escrow_before = infra_escrow::infra_escrow_balance();
+    let (_, balance_before) = ol_account::balance(alice_addr);
+
+    infra_escrow::user_pledge_infra(alice, 100);
+    assert!(infra_escrow::user_infra_pledge_balance(alice_addr) == 100, 7357001);
+
+    // top up the same pledge account
+    infra_escrow::user_pledge_infra(alice, 50);
+    assert!(pledge_accounts::get_user_pledge_amount(alice_addr, @ol_framework) == 150, 7357002);
+    assert!(infra_escrow::infra_escrow_balance() == escrow_before + 150, 7357003);
+
+    let (_, balance_after) = ol_account::balance(alice_addr);
+    assert!(balance_after == balance_before - 150, 7357004);
+  }
+
+  // Scenario: users can't pledge until governance enables it
+  #[test(root = @ol_framework, alice = @0x1000a)]
+  #[expected_failure(abort_code = 0x30001, location = ol_framework::infra_escrow)]
+  fun user_pledge_disabled(root: &signer, alice: &signer) {
+    mock::genesis_n_vals(root, 1);
+    mock::ol_initialize_coin_and_fund_vals(root, 10000, true);
+    infra_escrow::user_pledge_infra(alice, 100);
+  }
+}
@@ -6,6 +6,9 @@ module ol_framework::test_stake {
   use ol_framework::stake;
   use ol_framework::testnet;
   use ol_framework::grade;
+  use diem_std::bls12381;
+  use std::features;
+  use ol_framework::ol_features_constants;
 
 
 
@@ -255,5 +258,43 @@ module ol_framework::test_stake {
 
   }
 
+  // Scenario: a validator rotates its consensus key, the new key is in its
+  // config for the next epoch.
+  #[test(root = @ol_framework, alice = @0x1000a)]
+  fun rotate_consensus_key(root: signer, alice: signer) {
+    mock::genesis_n_vals(&root, 4);
+    features::change_feature_flags(&root, vector[ol_features_constants::get_consensus_key_rotation()], vector[]);
+    let (_sk, pk, pop) = stake::generate_identity();
+    let pk_bytes = bls12381::public_key_to_bytes(&pk);
+    let pop_bytes = bls12381::proof_of_possession_to_bytes(&pop);
+
+    stake::rotate_consensus_key(&alice, @0x1000a, pk_bytes, pop_bytes);
+    let (consensus_pubkey, _, _) = stake::get_validator_config(@0x1000a);
+    assert!(consensus_pubkey == pk_bytes, 73570004);
+  }
+
+  // Scenario: a validator can't rotate the key of another validator
+  #[test(root = @ol_framework, bob = @0x1000b)]
+  #[expected_failure(abort_code = 0x40009, location = ol_framework::stake)]
+  fun rotate_consensus_key_not_operator(root: signer, bob: signer) {
+    mock::genesis_n_vals(&root, 4);
+    features::change_feature_flags(&root, vector[ol_features_constants::get_consensus_key_rotation()], vector[]);
+    let (_sk, pk, pop) = stake::generate_identity();
+    let pk_bytes = bls12381::public_key_to_bytes(&pk);
+    let pop_bytes = bls12381::proof_of_possession_to_bytes(&pop);
+
+    stake::rotate_consensus_key(&bob, @0x1000a, pk_bytes, pop_bytes);
+  }
 
+  // Scenario: the consensus key can't be rotated until governance enables it
+  #[test(root = @ol_framework, alice = @0x1000a)]
+  #[expected_failure(abort_code = 0x30014, location = ol_framework::stake)]
+  fun rotate_consensus_key_disabled(root: signer, alice: signer) {
+    mock::genesis_n_vals(&root, 4);
+    let (_sk, pk, pop) = stake::generate_identity();
+    let pk_bytes = bls12381::public_key_to_bytes(&pk);
+    let pop_bytes = bls12381::proof_of_possession_to_bytes(&pop);
+
+    stake::rotate_consensus_key(&alice, @0x1000a, pk_bytes, pop_bytes);
+  }
 }
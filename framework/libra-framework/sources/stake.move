@@ -16,6 +16,7 @@ module diem_framework::stake {
     use ol_framework::slow_wallet;
     use ol_framework::testnet;
     use ol_framework::address_utils;
+    use ol_framework::ol_features_constants;
 
     friend diem_framework::block;
     friend diem_framework::genesis;
@@ -48,6 +49,8 @@ module diem_framework::stake {
     const EINELIGIBLE_VALIDATOR: u64 = 17;
     /// Table to store collected transaction fees for each validator already exists.
     const EFEES_TABLE_ALREADY_EXISTS: u64 = 19;
+    /// Consensus key rotation is not enabled by governance yet
+    const EKEY_ROTATION_DISABLED: u64 = 20;
 
 
     /// ALL VALIDATORS HAVE UNIFORM VOTING POWER.
@@ -368,12 +371,14 @@ module diem_framework::stake {
     // Commit note: in 0L the validator owner and operator are the same.
 
     /// Rotate the consensus key of the validator, it'll take effect in next epoch.
-    fun rotate_consensus_key(
+    /// Only once the CONSENSUS_KEY_ROTATION_ENABLED feature is enabled.
+    public entry fun rotate_consensus_key(
         operator: &signer,
         validator_address: address,
         new_consensus_pubkey: vector<u8>,
         proof_of_possession: vector<u8>,
     ) acquires ValidatorState, ValidatorConfig {
+        assert!(ol_features_constants::is_consensus_key_rotation_enabled(), error::invalid_state(EKEY_ROTATION_DISABLED));
         assert_stake_pool_exists(validator_address);
         let stake_pool = borrow_global_mut<ValidatorState>(validator_address);
         assert!(signer::address_of(operator) == stake_pool.operator_address, error::unauthenticated(ENOT_OPERATOR));
@@ -0,0 +1,497 @@
+//! Generate typed Rust bindings for the #[view] functions of a release.
+//! The entry functions get their bindings from the vendor sdk builder, which
+//! skips view functions. Here every view function becomes an async fn which
+//! encodes its arguments, calls the node, and decodes the returned values.
+//! Structs returned by view functions become Rust structs.
+//! The helpers the generated code uses are in
+//! `libra_cached_packages::view_support`.
+
+use anyhow::Context;
+use diem_framework::ReleaseBundle;
+use diem_types::vm::module_metadata::get_metadata_from_compiled_module;
+use move_binary_format::{
+    access::ModuleAccess,
+    file_format::{SignatureToken, StructFieldInformation, StructHandleIndex},
+    CompiledModule,
+};
+use move_core_types::{account_address::AccountAddress, language_storage::ModuleId};
+use move_model::{code_writer::CodeWriter, emitln, model::Loc};
+use std::{collections::BTreeMap, path::Path};
+
+/// file name of the generated bindings
+pub const VIEW_BINDINGS_FILE: &str = "libra_framework_views.rs";
+
+/// A Move type, as it's represented in the bindings
+#[derive(Debug, Clone, PartialEq)]
+enum Ty {
+    Bool,
+    U8,
+    U16,
+    U32,
+    U64,
+    U128,
+    U256,
+    Address,
+    Bytes,
+    String,
+    Vec(Box<Ty>),
+    Option(Box<Ty>),
+    /// a generated struct, by its Rust name
+    Struct(String),
+    /// anything without a Rust type, kept as JSON
+    Json,
+}
+
+impl Ty {
+    fn rust(&self) -> String {
+        match self {
+            Ty::Bool => "bool".into(),
+            Ty::U8 => "u8".into(),
+            Ty::U16 => "u16".into(),
+            Ty::U32 => "u32".into(),
+            Ty::U64 => "u64".into(),
+            Ty::U128 => "u128".into(),
+            Ty::U256 => "U256".into(),
+            Ty::Address => "AccountAddress".into(),
+            Ty::Bytes => "Vec<u8>".into(),
+            Ty::String => "String".into(),
+            Ty::Vec(t) => format!("Vec<{}>", t.rust()),
+            Ty::Option(t) => format!("Option<{}>", t.rust()),
+            Ty::Struct(name) => name.clone(),
+            Ty::Json => "Value".into(),
+        }
+    }
+
+    fn helper_suffix(&self) -> &'static str {
+        match self {
+            Ty::Bool => "bool",
+            Ty::U8 => "u8",
+            Ty::U16 => "u16",
+            Ty::U32 => "u32",
+            Ty::U64 => "u64",
+            Ty::U128 => "u128",
+            Ty::U256 => "u256",
+            Ty::Address => "address",
+            Ty::Bytes => "bytes",
+            Ty::String => "string",
+            _ => "json",
+        }
+    }
+
+    /// a callable which decodes a `&Value` into this type
+    fn decoder(&self) -> String {
+        match self {
+            Ty::Vec(t) => format!("|x: &Value| decode_vec(x, {})", t.decoder()),
+            Ty::Option(t) => format!("|x: &Value| decode_option(x, {})", t.decoder()),
+            Ty::Struct(name) => format!("{}::decode", name),
+            t => format!("decode_{}", t.helper_suffix()),
+        }
+    }
+
+    fn decode_expr(&self, v: &str) -> String {
+        match self {
+            Ty::Vec(t) => format!("decode_vec({}, {})", v, t.decoder()),
+            Ty::Option(t) => format!("decode_option({}, {})", v, t.decoder()),
+            t => format!("{}({})", t.decoder(), v),
+        }
+    }
+
+    /// a callable which encodes a reference to this type as a view argument
+    fn encoder(&self) -> String {
+        match self {
+            Ty::Vec(t) => format!("|x: &{}| encode_vec(x, {})", self.rust(), t.encoder()),
+            Ty::Option(t) => format!("|x: &{}| encode_option(x, {})", self.rust(), t.encoder()),
+            t => format!("encode_{}", t.helper_suffix()),
+        }
+    }
+
+    fn encode_expr(&self, v: &str) -> String {
+        match self {
+            Ty::Vec(t) => format!("encode_vec({}, {})", v, t.encoder()),
+            Ty::Option(t) => format!("encode_option({}, {})", v, t.encoder()),
+            t => format!("{}({})", t.encoder(), v),
+        }
+    }
+
+    /// structs are only decoded, arguments of struct types are passed as JSON
+    fn as_argument(&self) -> Ty {
+        match self {
+            Ty::Struct(_) => Ty::Json,
+            Ty::Vec(t) => Ty::Vec(Box::new(t.as_argument())),
+            Ty::Option(t) => Ty::Option(Box::new(t.as_argument())),
+            t => t.clone(),
+        }
+    }
+}
+
+struct GenStruct {
+    move_name: String,
+    fields: Vec<(String, Ty)>,
+}
+
+struct GenFunction {
+    move_name: String,
+    rust_name: String,
+    type_params: usize,
+    params: Vec<Ty>,
+    returns: Vec<Ty>,
+}
+
+/// Collects the view functions of all modules, and the structs they return
+struct Generator {
+    modules: BTreeMap<ModuleId, CompiledModule>,
+    /// by Rust name. None while being generated, to stop recursive structs
+    structs: BTreeMap<String, Option<GenStruct>>,
+    functions: Vec<GenFunction>,
+}
+
+fn camel(s: &str) -> String {
+    s.split('_')
+        .map(|w| {
+            let mut c = w.chars();
+            match c.next() {
+                Some(f) => f.to_uppercase().chain(c).collect(),
+                None => String::new(),
+            }
+        })
+        .collect()
+}
+
+fn field_name(name: &str) -> String {
+    const KEYWORDS: &[&str] = &[
+        "as", "async", "await", "box", "crate", "dyn", "enum", "extern", "fn", "impl", "in",
+        "loop", "match", "mod", "move", "mut", "pub", "ref", "self", "static", "super", "trait",
+        "type", "unsafe", "use", "where", "yield",
+    ];
+    if KEYWORDS.contains(&name) {
+        format!("r#{}", name)
+    } else {
+        name.to_owned()
+    }
+}
+
+impl Generator {
+    fn new(bundle: &ReleaseBundle) -> Self {
+        let modules = bundle
+            .compiled_modules()
+            .into_iter()
+            .map(|m| (m.self_id(), m))
+            .collect();
+        Self {
+            modules,
+            structs: BTreeMap::new(),
+            functions: vec![],
+        }
+    }
+
+    fn struct_id(module: &CompiledModule, idx: StructHandleIndex) -> (ModuleId, String) {
+        let handle = module.struct_handle_at(idx);
+        let mh = module.module_handle_at(handle.module);
+        let id = ModuleId::new(
+            *module.address_identifier_at(mh.address),
+            module.identifier_at(mh.name).to_owned(),
+        );
+        (id, module.identifier_at(handle.name).to_string())
+    }
+
+    /// None if the type can't be used in a view call, like a signer
+    fn ty(&mut self, module: &CompiledModule, token: &SignatureToken) -> Option<Ty> {
+        use SignatureToken as S;
+        Some(match token {
+            S::Bool => Ty::Bool,
+            S::U8 => Ty::U8,
+            S::U16 => Ty::U16,
+            S::U32 => Ty::U32,
+            S::U64 => Ty::U64,
+            S::U128 => Ty::U128,
+            S::U256 => Ty::U256,
+            S::Address => Ty::Address,
+            S::Vector(t) if **t == S::U8 => Ty::Bytes,
+            S::Vector(t) => Ty::Vec(Box::new(self.ty(module, t)?)),
+            S::Struct(idx) => {
+                let (id, name) = Self::struct_id(module, *idx);
+                if id.address() == &AccountAddress::ONE
+                    && id.name().as_str() == "string"
+                    && name == "String"
+                {
+                    Ty::String
+                } else {
+                    self.gen_struct(&id, &name)
+                }
+            }
+            S::StructInstantiation(idx, args) => {
+                let (id, name) = Self::struct_id(module, *idx);
+                if id.address() == &AccountAddress::ONE
+                    && id.name().as_str() == "option"
+                    && name == "Option"
+                    && args.len() == 1
+                {
+                    Ty::Option(Box::new(self.ty(module, &args[0])?))
+                } else {
+                    Ty::Json
+                }
+            }
+            S::TypeParameter(_) => Ty::Json,
+            S::Signer | S::Reference(_) | S::MutableReference(_) => return None,
+        })
+    }
+
+    /// generate a Rust struct for a Move struct without type parameters
+    fn gen_struct(&mut self, id: &ModuleId, name: &str) -> Ty {
+        let rust_name = format!("{}{}", camel(id.name().as_str()), name);
+        if self.structs.contains_key(&rust_name) {
+            return Ty::Struct(rust_name);
+        }
+        let Some(module) = self.modules.get(id).cloned() else {
+            return Ty::Json;
+        };
+        let Some(def) = module.struct_defs().iter().find(|d| {
+            module
+                .identifier_at(module.struct_handle_at(d.struct_handle).name)
+                .as_str()
+                == name
+        }) else {
+            return Ty::Json;
+        };
+        let StructFieldInformation::Declared(fields) = &def.field_information else {
+            return Ty::Json;
+        };
+
+        self.structs.insert(rust_name.clone(), None);
+        let mut gen_fields = vec![];
+        for f in fields {
+            let ty = match self.ty(&module, &f.signature.0) {
+                Some(Ty::Struct(n))
+                    if self.structs.get(&n).map(|s| s.is_none()).unwrap_or(false) =>
+                {
+                    // recursive, keep the inner one as JSON
+                    Ty::Json
+                }
+                Some(t) => t,
+                None => Ty::Json,
+            };
+            gen_fields.push((module.identifier_at(f.name).to_string(), ty));
+        }
+        self.structs.insert(
+            rust_name.clone(),
+            Some(GenStruct {
+                move_name: format!("{}::{}", id.short_str_lossless(), name),
+                fields: gen_fields,
+            }),
+        );
+        Ty::Struct(rust_name)
+    }
+
+    fn collect(&mut self) {
+        let modules: Vec<CompiledModule> = self.modules.values().cloned().collect();
+        for module in &modules {
+            let Some(metadata) = get_metadata_from_compiled_module(module) else {
+                continue;
+            };
+            let id = module.self_id();
+            for def in module.function_defs() {
+                let handle = module.function_handle_at(def.function);
+                let name = module.identifier_at(handle.name).to_string();
+                let is_view = metadata
+                    .fun_attributes
+                    .get(&name)
+                    .map(|attrs| attrs.iter().any(|a| a.is_view_function()))
+                    .unwrap_or(false);
+                if !is_view {
+                    continue;
+                }
+
+                let params: Option<Vec<Ty>> = module
+                    .signature_at(handle.parameters)
+                    .0
+                    .iter()
+                    .map(|t| self.ty(module, t).map(|t| t.as_argument()))
+                    .collect();
+                let returns: Option<Vec<Ty>> = module
+                    .signature_at(handle.return_)
+                    .0
+                    .iter()
+                    .map(|t| self.ty(module, t))
+                    .collect();
+                let (Some(params), Some(returns)) = (params, returns) else {
+                    continue;
+                };
+
+                self.functions.push(GenFunction {
+                    move_name: format!("{}::{}", id.short_str_lossless(), name),
+                    rust_name: format!("{}_{}", id.name(), name),
+                    type_params: handle.type_parameters.len(),
+                    params,
+                    returns,
+                });
+            }
+        }
+        self.functions.sort_by(|a, b| a.rust_name.cmp(&b.rust_name));
+    }
+
+    fn emit(&self) -> String {
+        let writer = CodeWriter::new(Loc::default());
+        emitln!(
+            writer,
+            "// This file was generated from the #[view] functions of the framework release. Do not edit.\n"
+        );
+        emitln!(writer, "use crate::view_support::*;");
+        emitln!(writer, "use diem_rest_client::Client;");
+        emitln!(writer, "use diem_types::account_address::AccountAddress;");
+        emitln!(writer, "use move_core_types::u256::U256;");
+        emitln!(writer, "use serde_json::Value;\n");
+
+        for (rust_name, s) in &self.structs {
+            let Some(s) = s else { continue };
+            emitln!(writer, "/// `{}`", s.move_name);
+            emitln!(writer, "#[derive(Debug, Clone, PartialEq)]");
+            emitln!(writer, "pub struct {} {{", rust_name);
+            writer.indent();
+            for (name, ty) in &s.fields {
+                emitln!(writer, "pub {}: {},", field_name(name), ty.rust());
+            }
+            writer.unindent();
+            emitln!(writer, "}\n");
+
+            emitln!(writer, "impl {} {{", rust_name);
+            writer.indent();
+            emitln!(writer, "pub fn decode(v: &Value) -> anyhow::Result<Self> {");
+            writer.indent();
+            emitln!(writer, "Ok(Self {");
+            writer.indent();
+            for (name, ty) in &s.fields {
+                emitln!(
+                    writer,
+                    "{}: {}?,",
+                    field_name(name),
+                    ty.decode_expr(&format!("field(v, \"{}\")?", name))
+                );
+            }
+            writer.unindent();
+            emitln!(writer, "})");
+            writer.unindent();
+            emitln!(writer, "}");
+            writer.unindent();
+            emitln!(writer, "}\n");
+        }
+
+        for f in &self.functions {
+            emitln!(writer, "/// `{}`", f.move_name);
+            emitln!(writer, "pub async fn {}(", f.rust_name);
+            writer.indent();
+            emitln!(writer, "client: &Client,");
+            if f.type_params > 0 {
+                emitln!(writer, "type_arguments: &[&str],");
+            }
+            for (i, p) in f.params.iter().enumerate() {
+                emitln!(writer, "arg_{}: {},", i, p.rust());
+            }
+            writer.unindent();
+            let ret = match f.returns.len() {
+                1 => f.returns[0].rust(),
+                _ => format!(
+                    "({})",
+                    f.returns
+                        .iter()
+                        .map(Ty::rust)
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ),
+            };
+            emitln!(writer, ") -> anyhow::Result<{}> {{", ret);
+            writer.indent();
+
+            let args = f
+                .params
+                .iter()
+                .enumerate()
+                .map(|(i, p)| p.encode_expr(&format!("&arg_{}", i)))
+                .collect::<Vec<_>>()
+                .join(", ");
+            let ty_args = if f.type_params > 0 {
+                "type_arguments"
+            } else {
+                "&[]"
+            };
+            let res = if f.returns.is_empty() { "_res" } else { "res" };
+            emitln!(
+                writer,
+                "let {} = view(client, \"{}\", {}, vec![{}]).await?;",
+                res,
+                f.move_name,
+                ty_args,
+                args
+            );
+
+            let values = f
+                .returns
+                .iter()
+                .enumerate()
+                .map(|(i, r)| {
+                    format!(
+                        "{}?",
+                        r.decode_expr(&format!("ret(&res, {}, \"{}\")?", i, f.move_name))
+                    )
+                })
+                .collect::<Vec<_>>();
+            match values.len() {
+                1 => emitln!(writer, "Ok({})", values[0]),
+                _ => emitln!(writer, "Ok(({}))", values.join(", ")),
+            }
+            writer.unindent();
+            emitln!(writer, "}\n");
+        }
+
+        writer.process_result(|s| s.to_owned())
+    }
+}
+
+/// The bindings source for all the view functions in the bundle
+pub fn generate_view_bindings(bundle: &ReleaseBundle) -> String {
+    let mut g = Generator::new(bundle);
+    g.collect();
+    g.emit()
+}
+
+/// Write the bindings for the view functions in the bundle to `out`
+pub fn write_view_bindings(bundle: &ReleaseBundle, out: &Path) -> anyhow::Result<()> {
+    std::fs::write(out, generate_view_bindings(bundle)).context(format!(
+        "could not write view bindings to {}",
+        out.display()
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::release::ReleaseTarget;
+
+    #[test]
+    fn types_and_codecs() {
+        let t = Ty::Vec(Box::new(Ty::Vec(Box::new(Ty::Address))));
+        assert_eq!(t.rust(), "Vec<Vec<AccountAddress>>");
+        assert_eq!(
+            t.decode_expr("v"),
+            "decode_vec(v, |x: &Value| decode_vec(x, decode_address))"
+        );
+        assert_eq!(
+            t.encode_expr("&arg_0"),
+            "encode_vec(&arg_0, |x: &Vec<AccountAddress>| encode_vec(x, encode_address))"
+        );
+        let s = Ty::Option(Box::new(Ty::Struct("FooBar".into())));
+        assert_eq!(s.decode_expr("v"), "decode_option(v, FooBar::decode)");
+        assert_eq!(s.as_argument(), Ty::Option(Box::new(Ty::Json)));
+        assert_eq!(camel("page_rank_lazy"), "PageRankLazy");
+        assert_eq!(field_name("type"), "r#type");
+    }
+
+    #[test]
+    fn generates_mainnet_views() {
+        let bundle = ReleaseTarget::Mainnet.load_bundle().unwrap();
+        let src = generate_view_bindings(&bundle);
+        assert!(src.contains("pub async fn ol_account_balance("));
+        assert!(src.contains(") -> anyhow::Result<(u64, u64)> {"));
+        // entry functions which are not views are left to the sdk builder
+        assert!(!src.contains("pub async fn ol_account_transfer("));
+    }
+}
@@ -5,6 +5,7 @@ use crate::{
         init_move_dir_wrapper, libra_compile_script, make_framework_upgrade_artifacts, save_build,
     },
     release::ReleaseTarget,
+    release_diff::ReleaseDiff,
 };
 
 use anyhow::Context;
@@ -21,6 +22,8 @@ pub enum FrameworkCli {
     Governance(GovernanceScript),
     /// Creates all artifacts for a network governance upgrade
     Upgrade(FrameworkUpgrade),
+    /// Compares two .mrb releases, and checks the upgrade is compatible
+    Diff(ReleaseDiffCli),
 }
 
 impl FrameworkCli {
@@ -29,6 +32,7 @@ impl FrameworkCli {
             FrameworkCli::Release(tool) => tool.execute(),
             FrameworkCli::Governance(tool) => tool.execute(),
             FrameworkCli::Upgrade(tool) => tool.execute(),
+            FrameworkCli::Diff(tool) => tool.execute(),
         }
     }
 }
@@ -98,6 +102,33 @@ impl FrameworkUpgrade {
     }
 }
 
+/// Lists the added, removed and changed modules between two releases.
+/// Exits with an error if the new release can't be published over the old one.
+#[derive(Debug, Parser)]
+pub struct ReleaseDiffCli {
+    /// the .mrb release currently on chain
+    pub old: PathBuf,
+    /// the .mrb release to upgrade to
+    pub new: PathBuf,
+}
+
+impl ReleaseDiffCli {
+    pub fn execute(&self) -> anyhow::Result<()> {
+        let old = ReleaseTarget::load_bundle_from_file(self.old.clone())
+            .context(format!("could not read release {}", self.old.display()))?;
+        let new = ReleaseTarget::load_bundle_from_file(self.new.clone())
+            .context(format!("could not read release {}", self.new.display()))?;
+
+        let diff = ReleaseDiff::new(&old, &new);
+        print!("{}", diff);
+        if !diff.is_compatible() {
+            anyhow::bail!("the new release is not compatible with the old one");
+        }
+        println!("the new release is compatible");
+        Ok(())
+    }
+}
+
 /// Builds artifacts for any governance proposal that requires human written scripts.
 /// Also creates a template for a libra governance script
 #[derive(Debug, Parser)]
@@ -10,5 +10,6 @@ fn main() -> anyhow::Result<()> {
         FrameworkCli::Release(release) => release.execute(),
         FrameworkCli::Upgrade(release) => release.execute(),
         FrameworkCli::Governance(release) => release.execute(),
+        FrameworkCli::Diff(diff) => diff.execute(),
     }
 }
@@ -0,0 +1,389 @@
+//! Compare two framework releases, and check the upgrade is compatible.
+//! The rules follow the VM's check for the `compatible` upgrade policy:
+//! modules can't be removed, struct layouts can't change, and public or
+//! entry functions can't be removed or change their signature.
+//! Friend functions can change, since the friends are upgraded together.
+
+use diem_framework::ReleaseBundle;
+use move_binary_format::{
+    file_format::{AbilitySet, Visibility},
+    normalized::{Function, Module, Struct},
+    CompiledModule,
+};
+use std::{collections::BTreeMap, fmt};
+
+/// One difference between two versions of a module
+#[derive(Debug, Clone, PartialEq)]
+pub struct Change {
+    /// the upgrade would be rejected
+    pub incompatible: bool,
+    pub description: String,
+}
+
+impl Change {
+    fn info(description: String) -> Self {
+        Self {
+            incompatible: false,
+            description,
+        }
+    }
+
+    fn breaking(description: String) -> Self {
+        Self {
+            incompatible: true,
+            description,
+        }
+    }
+}
+
+/// The differences between two releases, by module
+#[derive(Debug, Default)]
+pub struct ReleaseDiff {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    /// modules with different bytecode, and what changed in their interface
+    pub changed: BTreeMap<String, Vec<Change>>,
+}
+
+impl ReleaseDiff {
+    pub fn new(old: &ReleaseBundle, new: &ReleaseBundle) -> Self {
+        let by_name = |b: &ReleaseBundle| -> BTreeMap<String, CompiledModule> {
+            b.compiled_modules()
+                .into_iter()
+                .map(|m| (m.self_id().short_str_lossless(), m))
+                .collect()
+        };
+        let old_modules = by_name(old);
+        let new_modules = by_name(new);
+
+        let mut diff = ReleaseDiff::default();
+        for (name, old_module) in &old_modules {
+            match new_modules.get(name) {
+                None => diff.removed.push(name.clone()),
+                Some(new_module) if new_module != old_module => {
+                    diff.changed
+                        .insert(name.clone(), diff_modules(old_module, new_module));
+                }
+                _ => {}
+            }
+        }
+        diff.added = new_modules
+            .keys()
+            .filter(|name| !old_modules.contains_key(*name))
+            .cloned()
+            .collect();
+        diff
+    }
+
+    /// the new release can be published over the old one
+    pub fn is_compatible(&self) -> bool {
+        self.removed.is_empty()
+            && self
+                .changed
+                .values()
+                .all(|changes| changes.iter().all(|c| !c.incompatible))
+    }
+}
+
+impl fmt::Display for ReleaseDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for name in &self.added {
+            writeln!(f, "+ module {}", name)?;
+        }
+        for name in &self.removed {
+            writeln!(
+                f,
+                "- module {} (INCOMPATIBLE: modules can't be removed)",
+                name
+            )?;
+        }
+        for (name, changes) in &self.changed {
+            writeln!(f, "~ module {}", name)?;
+            if changes.is_empty() {
+                writeln!(f, "    bytecode only")?;
+            }
+            for c in changes {
+                let tag = if c.incompatible { "INCOMPATIBLE: " } else { "" };
+                writeln!(f, "    {}{}", tag, c.description)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn abilities(set: AbilitySet) -> String {
+    set.into_iter()
+        .map(|a| format!("{:?}", a).to_lowercase())
+        .collect::<Vec<_>>()
+        .join(" + ")
+}
+
+fn function_signature(name: &str, f: &Function) -> String {
+    let visibility = match f.visibility {
+        Visibility::Public => "public ",
+        Visibility::Friend => "public(friend) ",
+        Visibility::Private => "",
+    };
+    let entry = if f.is_entry { "entry " } else { "" };
+    let type_params = if f.type_parameters.is_empty() {
+        String::new()
+    } else {
+        let params = f
+            .type_parameters
+            .iter()
+            .enumerate()
+            .map(|(i, a)| match abilities(*a) {
+                s if s.is_empty() => format!("T{}", i),
+                s => format!("T{}: {}", i, s),
+            })
+            .collect::<Vec<_>>();
+        format!("<{}>", params.join(", "))
+    };
+    let params = f
+        .parameters
+        .iter()
+        .map(|t| t.to_string())
+        .collect::<Vec<_>>()
+        .join(", ");
+    let returns = match f.return_.len() {
+        0 => String::new(),
+        1 => format!(": {}", f.return_[0]),
+        _ => format!(
+            ": ({})",
+            f.return_
+                .iter()
+                .map(|t| t.to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+    };
+    format!(
+        "{}{}fun {}{}({}){}",
+        visibility, entry, name, type_params, params, returns
+    )
+}
+
+fn struct_layout(name: &str, s: &Struct) -> String {
+    let fields = s
+        .fields
+        .iter()
+        .map(|f| format!("{}: {}", f.name, f.type_))
+        .collect::<Vec<_>>()
+        .join(", ");
+    format!(
+        "struct {} has {} {{ {} }}",
+        name,
+        abilities(s.abilities),
+        fields
+    )
+}
+
+/// The new struct keeps the layout of values already in storage.
+/// Abilities can be added, but not removed. Like the VM, a type parameter
+/// can become phantom, but a phantom one must stay phantom.
+pub fn struct_compatible(old: &Struct, new: &Struct) -> bool {
+    old.abilities.is_subset(new.abilities)
+        && old.fields == new.fields
+        && old.type_parameters.len() == new.type_parameters.len()
+        && old
+            .type_parameters
+            .iter()
+            .zip(&new.type_parameters)
+            .all(|(o, n)| n.constraints.is_subset(o.constraints) && (!o.is_phantom || n.is_phantom))
+}
+
+/// Callers of the old function still link against the new one.
+/// Type parameter constraints can be relaxed, but not added.
+pub fn function_compatible(old: &Function, new: &Function) -> bool {
+    let visibility = match (old.visibility, new.visibility) {
+        (Visibility::Public, Visibility::Public) => true,
+        (Visibility::Public, _) => false,
+        _ => true,
+    };
+    visibility
+        && (!old.is_entry || new.is_entry)
+        && old.parameters == new.parameters
+        && old.return_ == new.return_
+        && old.type_parameters.len() == new.type_parameters.len()
+        && old
+            .type_parameters
+            .iter()
+            .zip(&new.type_parameters)
+            .all(|(o, n)| n.is_subset(*o))
+}
+
+/// public and entry functions are linked or called from outside the module
+fn is_exposed(f: &Function) -> bool {
+    f.visibility == Visibility::Public || f.is_entry
+}
+
+/// The interface changes between two versions of a module
+pub fn diff_modules(old: &CompiledModule, new: &CompiledModule) -> Vec<Change> {
+    let old = Module::new(old);
+    let new = Module::new(new);
+    let mut changes = vec![];
+
+    for friend in new.friends.iter().filter(|f| !old.friends.contains(f)) {
+        changes.push(Change::info(format!(
+            "new friend {}",
+            friend.short_str_lossless()
+        )));
+    }
+    for friend in old.friends.iter().filter(|f| !new.friends.contains(f)) {
+        changes.push(Change::info(format!(
+            "removed friend {}",
+            friend.short_str_lossless()
+        )));
+    }
+
+    for (id, old_struct) in &old.structs {
+        let name = id.as_str();
+        match new.structs.get(id) {
+            None => changes.push(Change::breaking(format!(
+                "removed {}",
+                struct_layout(name, old_struct)
+            ))),
+            Some(new_struct) if new_struct != old_struct => {
+                let description = format!(
+                    "changed {} -> {}",
+                    struct_layout(name, old_struct),
+                    struct_layout(name, new_struct)
+                );
+                changes.push(if struct_compatible(old_struct, new_struct) {
+                    Change::info(description)
+                } else {
+                    Change::breaking(description)
+                });
+            }
+            _ => {}
+        }
+    }
+    for (name, s) in &new.structs {
+        if !old.structs.contains_key(name) {
+            changes.push(Change::info(format!(
+                "added {}",
+                struct_layout(name.as_str(), s)
+            )));
+        }
+    }
+
+    // exposed_functions also has the friend functions
+    for (id, old_fun) in &old.exposed_functions {
+        let name = id.as_str();
+        match new.exposed_functions.get(id) {
+            None => {
+                let description = format!("removed {}", function_signature(name, old_fun));
+                changes.push(if is_exposed(old_fun) {
+                    Change::breaking(description)
+                } else {
+                    Change::info(description)
+                });
+            }
+            Some(new_fun) if new_fun != old_fun => {
+                let description = format!(
+                    "changed {} -> {}",
+                    function_signature(name, old_fun),
+                    function_signature(name, new_fun)
+                );
+                changes.push(
+                    if !is_exposed(old_fun) || function_compatible(old_fun, new_fun) {
+                        Change::info(description)
+                    } else {
+                        Change::breaking(description)
+                    },
+                );
+            }
+            _ => {}
+        }
+    }
+    for (name, f) in &new.exposed_functions {
+        if !old.exposed_functions.contains_key(name) {
+            changes.push(Change::info(format!(
+                "added {}",
+                function_signature(name.as_str(), f)
+            )));
+        }
+    }
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::release::ReleaseTarget;
+    use move_binary_format::{
+        file_format::{Ability, StructTypeParameter},
+        normalized::Type,
+    };
+
+    fn fun(visibility: Visibility, is_entry: bool, parameters: Vec<Type>) -> Function {
+        Function {
+            visibility,
+            is_entry,
+            type_parameters: vec![],
+            parameters,
+            return_: vec![],
+        }
+    }
+
+    #[test]
+    fn function_rules() {
+        let old = fun(Visibility::Public, false, vec![Type::Address]);
+        assert!(function_compatible(&old, &old));
+        // a public function can become an entry function
+        assert!(function_compatible(
+            &old,
+            &fun(Visibility::Public, true, vec![Type::Address])
+        ));
+        assert!(!function_compatible(
+            &old,
+            &fun(Visibility::Friend, false, vec![Type::Address])
+        ));
+        assert!(!function_compatible(
+            &old,
+            &fun(Visibility::Public, false, vec![Type::Address, Type::U64])
+        ));
+
+        let entry = fun(Visibility::Private, true, vec![Type::U64]);
+        assert!(!function_compatible(
+            &entry,
+            &fun(Visibility::Private, false, vec![Type::U64])
+        ));
+
+        let mut generic = fun(Visibility::Public, false, vec![]);
+        generic.type_parameters = vec![AbilitySet::EMPTY.add(Ability::Store)];
+        let mut relaxed = generic.clone();
+        relaxed.type_parameters = vec![AbilitySet::EMPTY];
+        assert!(function_compatible(&generic, &relaxed));
+        assert!(!function_compatible(&relaxed, &generic));
+        assert_eq!(
+            function_signature("f", &generic),
+            "public fun f<T0: store>()"
+        );
+    }
+
+    #[test]
+    fn struct_phantom_rules() {
+        let generic = |is_phantom| Struct {
+            abilities: AbilitySet::EMPTY,
+            type_parameters: vec![StructTypeParameter {
+                constraints: AbilitySet::EMPTY,
+                is_phantom,
+            }],
+            fields: vec![],
+        };
+        assert!(struct_compatible(&generic(false), &generic(false)));
+        assert!(struct_compatible(&generic(true), &generic(true)));
+        // a type parameter can become phantom, but not the other way
+        assert!(struct_compatible(&generic(false), &generic(true)));
+        assert!(!struct_compatible(&generic(true), &generic(false)));
+    }
+
+    #[test]
+    fn same_release_has_no_changes() {
+        let bundle = ReleaseTarget::Mainnet.load_bundle().unwrap();
+        let diff = ReleaseDiff::new(&bundle, &bundle);
+        assert!(diff.added.is_empty() && diff.removed.is_empty() && diff.changed.is_empty());
+        assert!(diff.is_compatible());
+    }
+}
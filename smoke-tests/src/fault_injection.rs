@@ -0,0 +1,150 @@
+//! Simulate failures on a LibraSmoke swarm, so that jailing, reconfiguration
+//! and liveness recovery can be tested deterministically.
+//! All faults (other than kill) are applied by editing the node's
+//! validator.yaml and restarting it. The original config is kept alongside
+//! (validator.yaml.pre_fault) so `heal` can restore it.
+//! Validators are referenced by their index in the swarm, the same as
+//! `val_set_index` in the testnet output.
+
+use crate::{helpers::update_node_config_restart, libra_smoke::LibraSmoke};
+use anyhow::Context;
+use diem_config::config::{Identity, NodeConfig, PersistableConfig, RateLimitConfig};
+use diem_crypto::x25519;
+use diem_forge::{LocalNode, Node};
+use std::{fs, path::PathBuf};
+
+/// extension of the backup of the node config before any fault was applied
+pub const PRE_FAULT_EXT: &str = "pre_fault";
+
+impl LibraSmoke {
+    /// get a validator by its index in the swarm
+    pub fn validator_by_index(&mut self, idx: usize) -> anyhow::Result<&mut LocalNode> {
+        self.swarm
+            .validators_mut()
+            .nth(idx)
+            .context(format!("no validator at index {idx}"))
+    }
+
+    /// stop the validator process, like a crash
+    pub fn kill_validator(&mut self, idx: usize) -> anyhow::Result<()> {
+        println!("killing validator {idx}");
+        self.validator_by_index(idx)?.stop();
+        Ok(())
+    }
+
+    /// start a validator with its current config
+    pub fn restart_validator(&mut self, idx: usize) -> anyhow::Result<()> {
+        println!("restarting validator {idx}");
+        let node = self.validator_by_index(idx)?;
+        node.stop();
+        node.start()?;
+        Ok(())
+    }
+
+    /// Cut off the validators from the rest of the network.
+    /// Each node gets a new network identity, so the noise handshake fails
+    /// with all the peers which expect the key registered on chain.
+    /// NOTE: partitioned nodes can't reach each other either.
+    pub fn partition(&mut self, idxs: &[usize]) -> anyhow::Result<()> {
+        for idx in idxs {
+            println!("partitioning validator {idx}");
+            // deterministic keys, different from anything on chain
+            let key = x25519::PrivateKey::try_from([(*idx as u8) + 1; 32].as_slice())?;
+            apply_fault(self.validator_by_index(*idx)?, |cfg| {
+                let net = cfg
+                    .validator_network
+                    .as_mut()
+                    .context("no validator network in config")?;
+                net.identity = Identity::from_config(key, net.peer_id());
+                Ok(())
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Limit the validator network bandwidth of a node, in bytes per second
+    pub fn throttle(&mut self, idx: usize, bytes_per_sec: usize) -> anyhow::Result<()> {
+        println!("throttling validator {idx} to {bytes_per_sec} bytes/sec");
+        apply_fault(self.validator_by_index(idx)?, |cfg| {
+            let net = cfg
+                .validator_network
+                .as_mut()
+                .context("no validator network in config")?;
+            let limit = || RateLimitConfig {
+                ip_byte_bucket_rate: bytes_per_sec,
+                ip_byte_bucket_size: bytes_per_sec,
+                initial_bucket_fill_percentage: 100,
+                enabled: true,
+            };
+            net.inbound_rate_limit_config = Some(limit());
+            net.outbound_rate_limit_config = Some(limit());
+            Ok(())
+        })
+    }
+
+    /// Make a node lag behind, as if its clock was slow, by delaying every
+    /// consensus message it processes.
+    /// NOTE: requires the `libra` binary at DIEM_FORGE_NODE_BIN_PATH to be
+    /// built with the `failpoints` feature. Otherwise the node ignores the
+    /// failpoint, and the fault does nothing.
+    pub fn lag(&mut self, idx: usize, delay_ms: u64) -> anyhow::Result<()> {
+        println!("lagging validator {idx} by {delay_ms}ms");
+        apply_fault(self.validator_by_index(idx)?, |cfg| {
+            let mut fp = cfg.failpoints.clone().unwrap_or_default();
+            fp.insert(
+                "consensus::process::any".to_string(),
+                format!("sleep({delay_ms})"),
+            );
+            cfg.failpoints = Some(fp);
+            Ok(())
+        })
+    }
+
+    /// restore the config from before any fault, and restart
+    pub fn heal(&mut self, idx: usize) -> anyhow::Result<()> {
+        let node = self.validator_by_index(idx)?;
+        let backup = pre_fault_path(node);
+        if !backup.exists() {
+            // the node may only have been killed
+            if node.health_check().is_err() {
+                println!("restarting stopped validator {idx}");
+                node.start()?;
+            }
+            return Ok(());
+        }
+        println!("healing validator {idx}");
+        let mut cfg = NodeConfig::load_config(&backup)?;
+        update_node_config_restart(node, &mut cfg)?;
+        fs::remove_file(backup)?;
+        Ok(())
+    }
+
+    /// heal every validator in the swarm
+    pub fn heal_all(&mut self) -> anyhow::Result<()> {
+        let count = self.swarm.validators().count();
+        for idx in 0..count {
+            self.heal(idx)?;
+        }
+        Ok(())
+    }
+}
+
+fn pre_fault_path(node: &LocalNode) -> PathBuf {
+    node.config_path().with_extension(PRE_FAULT_EXT)
+}
+
+/// Backup the config once (so faults can be stacked), edit it, and restart
+fn apply_fault<F>(node: &mut LocalNode, edit: F) -> anyhow::Result<()>
+where
+    F: FnOnce(&mut NodeConfig) -> anyhow::Result<()>,
+{
+    let config_path = node.config_path();
+    let backup = pre_fault_path(node);
+    if !backup.exists() {
+        fs::copy(&config_path, &backup)?;
+    }
+    // Don't load the instantiated config! Get the saved one always!
+    let mut cfg = NodeConfig::load_config(&config_path)?;
+    edit(&mut cfg)?;
+    update_node_config_restart(node, &mut cfg)
+}
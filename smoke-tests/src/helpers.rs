@@ -79,6 +79,24 @@ pub async fn unlock_libra(
     Ok(())
 }
 
+/// the testnet root enables the epoch boundary trigger, so validators can
+/// call it before the epoch expires
+pub async fn enable_epoch_trigger(public_info: &mut DiemPublicInfo<'_>) -> anyhow::Result<()> {
+    let payload = public_info
+        .transaction_factory()
+        .payload(libra_stdlib::epoch_boundary_smoke_enable_trigger());
+
+    let enable_trigger_tx = public_info
+        .root_account()
+        .sign_with_transaction_builder(payload);
+
+    public_info
+        .client()
+        .submit_and_wait(&enable_trigger_tx)
+        .await?;
+    Ok(())
+}
+
 pub async fn is_making_progress(client: &Client) -> anyhow::Result<bool> {
     let res = client.get_index().await?;
     let block_height_pre = res.inner().block_height.inner();
@@ -1,5 +1,6 @@
 pub mod brain_salad_surgery;
 pub mod configure_validator;
 pub mod extract_credentials;
+pub mod fault_injection;
 pub mod helpers;
 pub mod libra_smoke;
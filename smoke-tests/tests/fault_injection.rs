@@ -0,0 +1,30 @@
+use diem_forge::Node;
+use libra_smoke_tests::{helpers::is_making_progress, libra_smoke::LibraSmoke};
+
+/// A network of four survives one crashed validator,
+/// halts when two are partitioned, and recovers when healed.
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn fault_kill_partition_and_heal() -> anyhow::Result<()> {
+    let mut s = LibraSmoke::new(Some(4), None).await?;
+
+    // watch the chain from a node we never touch
+    let client = s.swarm.validators().nth(3).unwrap().rest_client();
+
+    s.kill_validator(0)?;
+    assert!(
+        is_making_progress(&client).await?,
+        "should survive one crash"
+    );
+
+    s.heal_all()?;
+    s.partition(&[0, 1])?;
+    assert!(
+        !is_making_progress(&client).await?,
+        "should halt without quorum"
+    );
+
+    s.heal_all()?;
+    assert!(is_making_progress(&client).await?, "should recover");
+
+    Ok(())
+}
@@ -3,13 +3,18 @@ use crate::{
     get_genesis_artifacts::{download_genesis, get_genesis_waypoint},
     interactive::options::{fix_config, FixOptions},
     make_yaml_public_fullnode::init_fullnode_yaml,
+    manage::{NetworkSub, ProfileSub},
     validator_config::{validator_dialogue, vfn_dialogue},
+    validator_doctor::ValidatorSub,
 };
 use anyhow::{bail, Context, Result};
 use clap::Parser;
 use dialoguer::Select;
 use libra_types::{
-    core_types::network_playlist::NetworkPlaylist,
+    core_types::{
+        app_cfg::default_file_path, config_migrations::validate_file,
+        network_playlist::NetworkPlaylist,
+    },
     exports::{AccountAddress, AuthenticationKey, NamedChain},
     global_config_dir, ol_progress,
 };
@@ -88,6 +93,23 @@ enum ConfigSub {
         #[clap(long)]
         archive_mode: Option<bool>,
     },
+
+    /// Check libra-cli-config.yaml for unknown or invalid fields, and
+    /// pending schema migrations. Doesn't change the file.
+    Validate,
+
+    /// Manage the profiles, without prompts
+    #[clap(subcommand)]
+    Profile(ProfileSub),
+
+    /// Manage the network playlists and check the health of their nodes,
+    /// without prompts
+    #[clap(subcommand)]
+    Network(NetworkSub),
+
+    /// Check the validator's files and on-chain config
+    #[clap(subcommand)]
+    Validator(ValidatorSub),
 }
 
 impl ConfigCli {
@@ -241,6 +263,29 @@ impl ConfigCli {
 
                 Ok(())
             }
+            Some(ConfigSub::Validate) => {
+                let path = self.path.clone().unwrap_or_else(default_file_path);
+                let report = validate_file(&path)?;
+                println!("{}", serde_json::to_string_pretty(&report)?);
+                if !report.is_valid() {
+                    bail!("{} is not valid", path.display());
+                }
+                Ok(())
+            }
+            Some(ConfigSub::Profile(sub)) => {
+                let res = sub.run(self.path.clone())?;
+                println!("{}", serde_json::to_string_pretty(&res)?);
+                Ok(())
+            }
+            Some(ConfigSub::Network(sub)) => {
+                let res = sub.run(self.path.clone(), self.chain_name).await?;
+                println!("{}", serde_json::to_string_pretty(&res)?);
+                Ok(())
+            }
+            Some(ConfigSub::Validator(sub)) => {
+                let home_dir = self.path.clone().unwrap_or_else(global_config_dir);
+                sub.run(&home_dir, self.path.clone(), self.chain_name).await
+            }
             _ => {
                 println!("Sometimes I'm right and I can be wrong. My own beliefs are in my song. The butcher, the banker, the drummer and then. Makes no difference what group I'm in.");
 
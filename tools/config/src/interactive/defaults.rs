@@ -69,7 +69,7 @@ pub fn change_default_chain(cfg: &mut AppCfg) -> Result<()> {
 
     let selected_chain = configured_chains[selection];
 
-    cfg.workspace.default_chain_id = selected_chain;
+    cfg.set_default_chain(selected_chain)?;
     println!("Default chain changed to: {:?}", selected_chain);
     Ok(())
 }
@@ -25,8 +25,7 @@ pub fn add_fullnode_url(cfg: &mut AppCfg, chain_name: Option<NamedChain>) -> Res
 
     let url = Url::parse(&url_input).map_err(|_| anyhow!("Invalid URL format"))?;
 
-    let np = cfg.get_network_profile_mut(chain_name)?;
-    np.add_url(url.clone());
+    cfg.add_fullnode_url(chain_name, url.clone())?;
 
     println!("Added fullnode URL: {}", url);
     Ok(())
@@ -38,8 +37,7 @@ pub fn remove_all_fullnodes(cfg: &mut AppCfg, chain_name: Option<NamedChain>) ->
         .with_prompt("Are you sure you want to remove all fullnode URLs?")
         .interact()?
     {
-        let np = cfg.get_network_profile_mut(chain_name)?;
-        np.nodes.clear();
+        cfg.remove_all_fullnodes(chain_name)?;
         println!("All fullnode URLs removed.");
         Ok(true)
     } else {
@@ -46,74 +46,15 @@ pub async fn reset_address_profile(
 
 /// Remove a profile from the configuration
 pub fn remove_profile(cfg: &mut AppCfg, profile_identifier: &str) {
-    // First check if the profile exists and if it's the default
-    let profile_to_remove = cfg.user_profiles.iter().find(|p| {
-        p.account.to_hex_literal() == profile_identifier
-            || p.account.to_string() == profile_identifier
-            || p.nickname == profile_identifier
-    });
-
-    let is_default_profile = if let Some(profile) = profile_to_remove {
-        cfg.workspace
-            .default_profile
-            .as_ref()
-            .map(|default| {
-                default == &profile.account.to_hex_literal() || default == &profile.nickname
-            })
-            .unwrap_or(false)
-    } else {
-        false
-    };
-
-    // First try by the identifier directly (could be nickname or address)
-    let result = cfg.try_remove_profile(profile_identifier);
-
-    // If that fails, try to find by account address directly
-    if result.is_err() {
-        // Find the profile index by direct account comparison
-        if let Some(index) = cfg.user_profiles.iter().position(|p| {
-            p.account.to_hex_literal() == profile_identifier
-                || p.account.to_string() == profile_identifier
-                || p.nickname == profile_identifier
-        }) {
-            cfg.user_profiles.remove(index);
-
-            // Handle default profile reset after removal
-            if is_default_profile {
-                reset_default_profile_after_removal(cfg);
-            }
-
+    match cfg.remove_profile(profile_identifier) {
+        Ok(_) => {
             println!("Profile removed successfully!");
-            return;
-        }
-    }
-
-    if result.is_err() {
-        println!("no profile found matching {}", profile_identifier);
-    } else {
-        // Handle default profile reset after successful removal via try_remove_profile
-        if is_default_profile {
-            reset_default_profile_after_removal(cfg);
+            match &cfg.workspace.default_profile {
+                Some(d) => println!("Default profile is: {}", d),
+                None => println!("No profiles remaining, default profile cleared."),
+            }
         }
-        println!("Profile removed successfully!");
-    }
-}
-
-/// Reset the default profile after a profile removal
-/// Sets the first remaining profile as default, or clears default if no profiles remain
-fn reset_default_profile_after_removal(cfg: &mut AppCfg) {
-    if cfg.user_profiles.is_empty() {
-        // No profiles left, clear the default
-        cfg.workspace.default_profile = None;
-        println!("No profiles remaining, default profile cleared.");
-    } else {
-        // Set the first remaining profile as the new default
-        let new_default = cfg.user_profiles[0].account.to_hex_literal();
-        cfg.workspace.set_default(new_default);
-        println!(
-            "Default profile reset to: {} ({})",
-            cfg.user_profiles[0].nickname, cfg.user_profiles[0].account
-        );
+        Err(e) => println!("{}", e),
     }
 }
 
@@ -5,5 +5,8 @@ pub mod interactive;
 pub mod make_profile; // TODO: deprecated?
 pub mod make_yaml_public_fullnode;
 pub mod make_yaml_validator;
+pub mod manage;
+pub mod network_monitor;
 pub mod validator_config;
+pub mod validator_doctor;
 pub mod validator_registration;
@@ -0,0 +1,176 @@
+//! Scriptable config management: the actions of `config fix`, with flags
+//! instead of prompts. Every command prints the resulting state as JSON.
+
+use crate::network_monitor::{self, DEFAULT_MONITOR_INTERVAL_SECS};
+use anyhow::{Context, Result};
+use libra_types::{
+    core_types::app_cfg::AppCfg,
+    exports::{AccountAddress, AuthenticationKey, NamedChain},
+};
+use serde_json::{json, Value};
+use std::{path::PathBuf, time::Duration};
+use url::Url;
+
+#[derive(clap::Subcommand)]
+pub enum ProfileSub {
+    /// List the profiles
+    List,
+    /// Add a profile, or replace the one with the same address
+    Add {
+        /// account address
+        #[clap(short, long)]
+        address: AccountAddress,
+        /// defaults to the address, which is right if the keys were never rotated
+        #[clap(long)]
+        authkey: Option<AuthenticationKey>,
+        /// defaults to the start of the address
+        #[clap(short, long)]
+        nickname: Option<String>,
+        /// make it the default profile
+        #[clap(short, long)]
+        default: bool,
+    },
+    /// Remove a profile, by nickname or address
+    Remove { id: String },
+    /// Set the default profile, by nickname or address
+    SetDefault { id: String },
+    /// Change the nickname of a profile
+    Rename {
+        /// nickname or address of the profile
+        id: String,
+        /// the new nickname
+        nickname: String,
+    },
+}
+
+#[derive(clap::Subcommand)]
+pub enum NetworkSub {
+    /// List the networks and their fullnodes
+    List,
+    /// Add a fullnode URL to the playlist of the chain
+    AddUrl { url: Url },
+    /// Remove a fullnode URL from the playlist of the chain
+    RemoveUrl { url: Url },
+    /// Remove all fullnode URLs of the chain
+    ClearUrls,
+    /// Replace the playlist of the chain with one from a URL, or with the
+    /// default playlist
+    ImportPlaylist {
+        #[clap(short, long)]
+        url: Option<Url>,
+    },
+    /// Make the chain given with --chain-name the default one
+    SetDefault,
+    /// Probe the playlist nodes on an interval, measuring latency, version lag
+    /// and errors. The scores are saved in libra-cli-config.yaml, and the tools
+    /// use the best scored node.
+    Monitor {
+        /// seconds between probes
+        #[clap(short, long, default_value_t = DEFAULT_MONITOR_INTERVAL_SECS)]
+        interval: u64,
+        /// stop after this many probes, otherwise runs until stopped
+        #[clap(short, long)]
+        rounds: Option<u64>,
+    },
+}
+
+impl ProfileSub {
+    pub fn run(&self, config_path: Option<PathBuf>) -> Result<Value> {
+        let mut cfg = AppCfg::load(config_path)?;
+        match self {
+            ProfileSub::List => return Ok(profiles_json(&cfg)),
+            ProfileSub::Add {
+                address,
+                authkey,
+                nickname,
+                default,
+            } => {
+                let authkey =
+                    authkey.unwrap_or_else(|| AuthenticationKey::new(address.into_bytes()));
+                cfg.add_profile(authkey, *address, nickname.to_owned(), *default)?;
+            }
+            ProfileSub::Remove { id } => {
+                cfg.remove_profile(id)?;
+            }
+            ProfileSub::SetDefault { id } => cfg.set_default_profile(id)?,
+            ProfileSub::Rename { id, nickname } => cfg.rename_profile(id, nickname)?,
+        }
+        cfg.save_file()?;
+        Ok(profiles_json(&cfg))
+    }
+}
+
+impl NetworkSub {
+    pub async fn run(
+        &self,
+        config_path: Option<PathBuf>,
+        chain_name: Option<NamedChain>,
+    ) -> Result<Value> {
+        if let NetworkSub::Monitor { interval, rounds } = self {
+            network_monitor::monitor(
+                config_path.clone(),
+                chain_name,
+                Duration::from_secs(*interval),
+                *rounds,
+            )
+            .await?;
+        }
+
+        let mut cfg = AppCfg::load(config_path)?;
+        match self {
+            NetworkSub::List | NetworkSub::Monitor { .. } => return Ok(networks_json(&cfg)),
+            NetworkSub::AddUrl { url } => cfg.add_fullnode_url(chain_name, url.to_owned())?,
+            NetworkSub::RemoveUrl { url } => cfg.remove_fullnode_url(chain_name, url)?,
+            NetworkSub::ClearUrls => cfg.remove_all_fullnodes(chain_name)?,
+            NetworkSub::ImportPlaylist { url } => {
+                let chain = chain_name.unwrap_or(cfg.workspace.default_chain_id);
+                cfg.update_network_playlist(Some(chain), url.to_owned())
+                    .await?;
+            }
+            NetworkSub::SetDefault => {
+                cfg.set_default_chain(chain_name.context("set the chain with --chain-name")?)?
+            }
+        }
+        cfg.save_file()?;
+        Ok(networks_json(&cfg))
+    }
+}
+
+/// the profiles, without any keys
+pub fn profiles_json(cfg: &AppCfg) -> Value {
+    let list: Vec<Value> = cfg
+        .user_profiles
+        .iter()
+        .map(|p| {
+            let account = p.account.to_hex_literal();
+            let is_default = cfg
+                .workspace
+                .default_profile
+                .as_ref()
+                .map(|d| d == &account || d == &p.nickname)
+                .unwrap_or(false);
+            json!({
+                "nickname": p.nickname,
+                "account": account,
+                "auth_key": p.auth_key.to_string(),
+                "default": is_default,
+            })
+        })
+        .collect();
+    json!(list)
+}
+
+pub fn networks_json(cfg: &AppCfg) -> Value {
+    let list: Vec<Value> = cfg
+        .network_playlist
+        .iter()
+        .map(|np| {
+            json!({
+                "chain_name": np.chain_name.to_string(),
+                "default": np.chain_name == cfg.workspace.default_chain_id,
+                "nodes": np.ranked(),
+            })
+        })
+        .collect();
+    json!(list)
+}
@@ -0,0 +1,58 @@
+//! Probe the nodes of the network playlist on an interval, and keep their
+//! scores in libra-cli-config.yaml, so that the tools pick the best node.
+
+use anyhow::Result;
+use libra_types::{
+    core_types::{app_cfg::AppCfg, network_playlist::NetworkPlaylist},
+    exports::NamedChain,
+};
+use std::{path::PathBuf, time::Duration};
+
+pub const DEFAULT_MONITOR_INTERVAL_SECS: u64 = 30;
+
+/// Probe the playlist of `chain_name` every `interval`, saving the scores
+/// after every round. Runs until stopped, or for `rounds` rounds.
+pub async fn monitor(
+    config_path: Option<PathBuf>,
+    chain_name: Option<NamedChain>,
+    interval: Duration,
+    rounds: Option<u64>,
+) -> Result<()> {
+    let mut round = 0;
+    loop {
+        // reload each round, other tools may have changed the file
+        let mut cfg = AppCfg::load(config_path.clone())?;
+        let np = cfg.get_network_profile_mut(chain_name)?;
+        np.probe_and_score().await?;
+        print_scores(np);
+        cfg.save_file()?;
+
+        round += 1;
+        if rounds.map(|r| round >= r).unwrap_or(false) {
+            return Ok(());
+        }
+        tokio::time::sleep(interval).await;
+    }
+}
+
+/// print the nodes of the playlist, best first
+pub fn print_scores(np: &NetworkPlaylist) {
+    println!(
+        "\n{:<40} {:>7} {:>10} {:>8} {:>7} {:>7}",
+        "node", "score", "latency ms", "lag", "errors", "probes"
+    );
+    for n in np.ranked() {
+        match &n.score {
+            Some(s) => println!(
+                "{:<40} {:>7.1} {:>10.0} {:>8.0} {:>6.0}% {:>7}",
+                n.url.as_str(),
+                s.score,
+                s.latency_ms,
+                s.lag,
+                s.error_rate * 100.0,
+                s.probes
+            ),
+            None => println!("{:<40} {:>7}", n.url.as_str(), "-"),
+        }
+    }
+}
@@ -0,0 +1,413 @@
+//! Check a validator's local files agree with each other and with the
+//! chain, and that the advertised addresses can be reached.
+//! Every failed check comes with the steps to fix it.
+
+use crate::make_yaml_validator::NODE_YAML_FILE;
+use anyhow::{bail, Context, Result};
+use diem_config::config::{IdentityBlob, NodeConfig};
+use diem_crypto::PrivateKey;
+use diem_types::{
+    network_address::{NetworkAddress, Protocol},
+    validator_config::ValidatorConfig,
+};
+use libra_types::{
+    core_types::app_cfg::AppCfg,
+    exports::{AccountAddress, Client, NamedChain},
+    type_extensions::client_ext::ClientExt,
+};
+use libra_wallet::{
+    keys::{PUBLIC_KEYS_FILE, VALIDATOR_FILE},
+    utils::{read_operator_file, read_public_identity_file},
+    validator_files::OPERATOR_FILE,
+};
+use std::{
+    path::{Path, PathBuf},
+    time::Duration,
+};
+use url::Url;
+
+/// how long to wait for a TCP connection to an advertised address
+const CONNECT_TIMEOUT_SECS: u64 = 5;
+
+#[derive(clap::Subcommand)]
+pub enum ValidatorSub {
+    /// Check the validator files agree with each other and with the chain,
+    /// and that the advertised addresses can be reached. Prints how to fix
+    /// what's wrong.
+    Doctor {
+        /// node to read the on-chain config from, defaults to the one in
+        /// libra-cli-config.yaml
+        #[clap(short, long)]
+        url: Option<Url>,
+        /// only check the local files
+        #[clap(long)]
+        offline: bool,
+        /// don't try to connect to the advertised addresses
+        #[clap(long)]
+        skip_ports: bool,
+    },
+}
+
+impl ValidatorSub {
+    pub async fn run(
+        &self,
+        home: &Path,
+        config_path: Option<PathBuf>,
+        chain_name: Option<NamedChain>,
+    ) -> Result<()> {
+        let ValidatorSub::Doctor {
+            url,
+            offline,
+            skip_ports,
+        } = self;
+
+        let client = match (offline, url) {
+            (true, _) => None,
+            (false, Some(u)) => Some(Client::new(u.to_owned())),
+            (false, None) => {
+                let app_cfg = AppCfg::load(config_path)?;
+                Some(Client::from_libra_config(&app_cfg, chain_name).await?.0)
+            }
+        };
+
+        let findings = doctor(home, client.as_ref(), !skip_ports).await?;
+        print_findings(&findings);
+        if findings.iter().any(|f| !f.ok) {
+            bail!("some validator checks failed, see the fixes above");
+        }
+        Ok(())
+    }
+}
+
+/// The result of one check
+#[derive(Debug, Clone)]
+pub struct Finding {
+    pub check: String,
+    pub ok: bool,
+    pub detail: String,
+    /// what to do about it, if it failed
+    pub fix: Option<String>,
+}
+
+impl Finding {
+    fn pass(check: &str, detail: impl ToString) -> Self {
+        Self {
+            check: check.to_string(),
+            ok: true,
+            detail: detail.to_string(),
+            fix: None,
+        }
+    }
+
+    fn fail(check: &str, detail: impl ToString, fix: impl ToString) -> Self {
+        Self {
+            check: check.to_string(),
+            ok: false,
+            detail: detail.to_string(),
+            fix: Some(fix.to_string()),
+        }
+    }
+}
+
+pub fn print_findings(findings: &[Finding]) {
+    for f in findings {
+        let mark = if f.ok { "ok  " } else { "FAIL" };
+        println!("[{}] {}: {}", mark, f.check, f.detail);
+        if let Some(fix) = &f.fix {
+            println!("       fix: {}", fix);
+        }
+    }
+    let failed = findings.iter().filter(|f| !f.ok).count();
+    println!("\n{} checks, {} failed", findings.len(), failed);
+}
+
+/// Run all the checks on the validator files in `home`. The on-chain checks
+/// are skipped without a client, the reachability checks if `check_ports`
+/// is false.
+pub async fn doctor(
+    home: &Path,
+    client: Option<&Client>,
+    check_ports: bool,
+) -> Result<Vec<Finding>> {
+    let mut findings = vec![];
+
+    let oc = read_operator_file(&home.join(OPERATOR_FILE)).context(format!(
+        "cannot read {}, is this a validator home? try `libra config validator-init`",
+        OPERATOR_FILE
+    ))?;
+    let account: AccountAddress = oc.operator_account_address.into();
+    let local_val_addr = oc
+        .validator_host
+        .as_network_address(oc.validator_network_public_key)?;
+    let local_vfn_addr = match (&oc.full_node_host, oc.full_node_network_public_key) {
+        (Some(h), Some(k)) => Some(h.as_network_address(k)?),
+        _ => None,
+    };
+    let regenerate =
+        "regenerate the public files from your mnemonic with `libra config validator-init`";
+
+    // private keys against the public files
+    match IdentityBlob::from_file(&home.join(VALIDATOR_FILE)) {
+        Ok(id) => {
+            let consensus = id.consensus_private_key.as_ref().map(|k| k.public_key());
+            findings.push(if consensus.as_ref() == Some(&oc.consensus_public_key) {
+                Finding::pass(
+                    "consensus key",
+                    format!("{} matches {}", VALIDATOR_FILE, OPERATOR_FILE),
+                )
+            } else {
+                Finding::fail(
+                    "consensus key",
+                    format!(
+                        "{} has a different consensus key than {}",
+                        VALIDATOR_FILE, OPERATOR_FILE
+                    ),
+                    regenerate,
+                )
+            });
+            findings.push(
+                if id.network_private_key.public_key() == oc.validator_network_public_key {
+                    Finding::pass(
+                        "network key",
+                        format!("{} matches {}", VALIDATOR_FILE, OPERATOR_FILE),
+                    )
+                } else {
+                    Finding::fail(
+                        "network key",
+                        format!(
+                            "{} has a different network key than {}",
+                            VALIDATOR_FILE, OPERATOR_FILE
+                        ),
+                        regenerate,
+                    )
+                },
+            );
+        }
+        Err(e) => findings.push(Finding::fail(
+            "validator identity",
+            format!("cannot read {}: {}", VALIDATOR_FILE, e),
+            regenerate,
+        )),
+    }
+
+    match read_public_identity_file(&home.join(PUBLIC_KEYS_FILE)) {
+        Ok(pub_id) => {
+            let same = pub_id.account_address == account
+                && pub_id.consensus_public_key.as_ref() == Some(&oc.consensus_public_key)
+                && pub_id.validator_network_public_key == Some(oc.validator_network_public_key);
+            findings.push(if same {
+                Finding::pass(
+                    "public keys",
+                    format!("{} matches {}", PUBLIC_KEYS_FILE, OPERATOR_FILE),
+                )
+            } else {
+                Finding::fail(
+                    "public keys",
+                    format!(
+                        "{} and {} have different accounts or keys",
+                        PUBLIC_KEYS_FILE, OPERATOR_FILE
+                    ),
+                    regenerate,
+                )
+            });
+        }
+        Err(e) => findings.push(Finding::fail(
+            "public keys",
+            format!("cannot read {}: {}", PUBLIC_KEYS_FILE, e),
+            regenerate,
+        )),
+    }
+
+    // the node config listens where the operator file advertises
+    let node_yaml = home.join(NODE_YAML_FILE);
+    match NodeConfig::load_from_path(&node_yaml) {
+        Ok(node) => {
+            let advertised = local_val_addr.find_port();
+            let listening = node
+                .validator_network
+                .as_ref()
+                .and_then(|n| n.listen_address.find_port());
+            findings.push(if advertised == listening {
+                Finding::pass(
+                    "validator port",
+                    format!("{} listens on the port in {}", NODE_YAML_FILE, OPERATOR_FILE),
+                )
+            } else {
+                Finding::fail(
+                    "validator port",
+                    format!(
+                        "{} listens on {:?}, {} advertises {:?}",
+                        NODE_YAML_FILE, listening, OPERATOR_FILE, advertised
+                    ),
+                    format!(
+                        "set validator_network.listen_address to /ip4/0.0.0.0/tcp/{} in {}, or change the port in {} and run `libra txs validator update`",
+                        advertised.unwrap_or_default(),
+                        node_yaml.display(),
+                        OPERATOR_FILE
+                    ),
+                )
+            });
+        }
+        Err(e) => findings.push(Finding::fail(
+            "node config",
+            format!("cannot load {}: {}", node_yaml.display(), e),
+            "create it with `libra config validator-init`",
+        )),
+    }
+
+    // the chain knows the same keys and addresses
+    let mut advertised = vec![("validator", local_val_addr.clone())];
+    if let Some(a) = &local_vfn_addr {
+        advertised.push(("fullnode", a.clone()));
+    }
+    if let Some(client) = client {
+        match client.get_move_resource::<ValidatorConfig>(account).await {
+            Ok(on_chain) => {
+                findings.push(if on_chain.consensus_public_key == oc.consensus_public_key {
+                    Finding::pass("on-chain consensus key", "matches the local keys")
+                } else {
+                    Finding::fail(
+                        "on-chain consensus key",
+                        "the chain has another consensus key, this node's votes will not count",
+                        format!(
+                            "restore the {} whose key is registered, or rotate the on-chain consensus key to the one in {}",
+                            VALIDATOR_FILE, OPERATOR_FILE
+                        ),
+                    )
+                });
+
+                let update = format!(
+                    "publish the addresses in {} with `libra txs validator update`",
+                    OPERATOR_FILE
+                );
+                let on_chain_val = on_chain.validator_network_addresses().unwrap_or_default();
+                findings.push(compare_addresses(
+                    "on-chain validator address",
+                    &on_chain_val,
+                    Some(&local_val_addr),
+                    &update,
+                ));
+                let on_chain_vfn = on_chain.fullnode_network_addresses().unwrap_or_default();
+                findings.push(compare_addresses(
+                    "on-chain fullnode address",
+                    &on_chain_vfn,
+                    local_vfn_addr.as_ref(),
+                    &update,
+                ));
+
+                // check what other validators actually dial
+                for a in on_chain_val.iter().filter(|a| **a != local_val_addr) {
+                    advertised.push(("on-chain validator", a.clone()));
+                }
+            }
+            Err(_) => findings.push(Finding::fail(
+                "on-chain config",
+                format!("no validator config on chain for {}", account),
+                "register with `libra txs validator register`",
+            )),
+        }
+    }
+
+    if check_ports {
+        for (name, addr) in advertised {
+            findings.push(check_reachable(name, &addr).await);
+        }
+    }
+
+    Ok(findings)
+}
+
+fn compare_addresses(
+    check: &str,
+    on_chain: &[NetworkAddress],
+    local: Option<&NetworkAddress>,
+    fix: &str,
+) -> Finding {
+    match local {
+        Some(l) if on_chain.contains(l) => Finding::pass(check, l),
+        Some(l) => Finding::fail(
+            check,
+            format!(
+                "chain has {:?}, {} has {}",
+                on_chain.iter().map(|a| a.to_string()).collect::<Vec<_>>(),
+                OPERATOR_FILE,
+                l
+            ),
+            fix,
+        ),
+        None => Finding::fail(
+            check,
+            format!("no fullnode host in {}", OPERATOR_FILE),
+            format!(
+                "add full_node_host to {} and run `libra txs validator update`",
+                OPERATOR_FILE
+            ),
+        ),
+    }
+}
+
+/// the host and port to dial for an address
+fn host_and_port(addr: &NetworkAddress) -> Option<(String, u16)> {
+    let host = addr.as_slice().iter().find_map(|p| match p {
+        Protocol::Ip4(ip) => Some(ip.to_string()),
+        Protocol::Ip6(ip) => Some(ip.to_string()),
+        Protocol::Dns(name) | Protocol::Dns4(name) | Protocol::Dns6(name) => Some(name.to_string()),
+        _ => None,
+    })?;
+    Some((host, addr.find_port()?))
+}
+
+async fn check_reachable(name: &str, addr: &NetworkAddress) -> Finding {
+    let check = format!("{} reachable", name);
+    let Some((host, port)) = host_and_port(addr) else {
+        return Finding::fail(
+            &check,
+            format!("cannot find host and port in {}", addr),
+            "fix the address in operator.yaml",
+        );
+    };
+
+    let resolved = match tokio::net::lookup_host((host.as_str(), port)).await {
+        Ok(mut a) => a.next(),
+        Err(_) => None,
+    };
+    let Some(socket) = resolved else {
+        return Finding::fail(
+            &check,
+            format!("{} does not resolve", host),
+            format!(
+                "create a DNS record for {}, or advertise the IP instead",
+                host
+            ),
+        );
+    };
+
+    let connect = tokio::net::TcpStream::connect(socket);
+    match tokio::time::timeout(Duration::from_secs(CONNECT_TIMEOUT_SECS), connect).await {
+        Ok(Ok(_)) => Finding::pass(&check, format!("{}:{} accepts connections", host, port)),
+        _ => Finding::fail(
+            &check,
+            format!("cannot connect to {}:{}", host, port),
+            format!(
+                "check the node is running, and TCP port {} is open on the firewall",
+                port
+            ),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_host_and_port() {
+        let addr: NetworkAddress = "/dns/node.example.com/tcp/6180".parse().unwrap();
+        assert_eq!(
+            host_and_port(&addr),
+            Some(("node.example.com".to_string(), 6180))
+        );
+        let addr: NetworkAddress = "/ip4/127.0.0.1/tcp/6182".parse().unwrap();
+        assert_eq!(host_and_port(&addr), Some(("127.0.0.1".to_string(), 6182)));
+    }
+}
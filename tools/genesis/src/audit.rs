@@ -0,0 +1,238 @@
+//! Audit a genesis blob against the recovery file it was built from.
+//! Runs every check we have, and collects the mismatches per category,
+//! instead of stopping at the first failure.
+
+use crate::{
+    compare::{self, get_struct, CompareError},
+    genesis_reader::{self, total_supply},
+    supply,
+};
+use anyhow::Context;
+use diem_types::transaction::Transaction;
+use libra_backwards_compatibility::legacy_recovery_v6::{self, AccountRole, LegacyRecoveryV6};
+use libra_types::{
+    exports::AccountAddress,
+    move_resource::{
+        ancestry::AncestryResource, donor_voice::RegistryResource, receipts::ReceiptsResource,
+    },
+    ol_progress::OLProgress,
+};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Mismatches found in a genesis, by category
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct AuditReport {
+    /// accounts from the recovery file which were audited (not dropped, not system)
+    pub accounts_audited: u64,
+    /// balance was not migrated as expected
+    pub balances: Vec<CompareError>,
+    /// slow wallet unlocked amounts
+    pub slow_wallets: Vec<CompareError>,
+    /// ancestry trees
+    pub ancestry: Vec<CompareError>,
+    /// payment receipts
+    pub receipts: Vec<CompareError>,
+    /// community wallets missing from the donor voice registry
+    pub community_wallets: Vec<CompareError>,
+    /// validators expected but not in the set, or the other way around
+    pub validator_set: Vec<CompareError>,
+    /// total supply against the sum of migrated balances
+    pub supply: Vec<CompareError>,
+}
+
+impl AuditReport {
+    /// name and mismatches of each category, in display order
+    pub fn categories(&self) -> Vec<(&'static str, &Vec<CompareError>)> {
+        vec![
+            ("balances", &self.balances),
+            ("slow wallets", &self.slow_wallets),
+            ("ancestry", &self.ancestry),
+            ("receipts", &self.receipts),
+            ("community wallets", &self.community_wallets),
+            ("validator set", &self.validator_set),
+            ("total supply", &self.supply),
+        ]
+    }
+
+    pub fn mismatch_count(&self) -> usize {
+        self.categories().iter().map(|(_, v)| v.len()).sum()
+    }
+
+    pub fn is_clean(&self) -> bool {
+        self.mismatch_count() == 0
+    }
+
+    /// print a summary table of the mismatches per category
+    pub fn print_summary(&self) {
+        println!("\naccounts audited: {}", self.accounts_audited);
+        println!("{:<20} {:>10}", "category", "mismatches");
+        println!("{:-<31}", "");
+        for (name, list) in self.categories() {
+            println!("{:<20} {:>10}", name, list.len());
+        }
+        println!("{:-<31}", "");
+        println!("{:<20} {:>10}", "total", self.mismatch_count());
+    }
+
+    /// write the full report as JSON
+    pub fn save(&self, path: &Path) -> anyhow::Result<()> {
+        std::fs::write(path, serde_json::to_string_pretty(self)?).context(format!(
+            "could not write audit report to {}",
+            path.display()
+        ))
+    }
+}
+
+/// Run all the checks of the recovery data against the genesis transaction.
+/// If `expected_vals` is empty, only checks that the validator set is not empty.
+pub fn audit_genesis(
+    recovery: &mut [LegacyRecoveryV6],
+    genesis_tx: &Transaction,
+    expected_vals: &[AccountAddress],
+) -> anyhow::Result<AuditReport> {
+    let (db_rw, _) = genesis_reader::bootstrap_db_reader_from_gen_tx(genesis_tx)?;
+    let db = &db_rw.reader;
+    let supply_stats = supply::populate_supply_stats_from_legacy(recovery)?;
+
+    let mut report = AuditReport::default();
+
+    // balances and slow wallets
+    let errs = compare::compare_recovery_vec_to_genesis_tx(recovery, db, &supply_stats)?;
+    let (slow, balances): (Vec<_>, Vec<_>) = errs
+        .into_iter()
+        .partition(|e| e.message.contains("unlocked"));
+    report.balances = balances;
+    report.slow_wallets = slow;
+    OLProgress::complete("audited balances and slow wallets");
+
+    let mut list = recovery.to_vec();
+    legacy_recovery_v6::strip_system_address(&mut list);
+
+    let cw_registry = get_struct::<RegistryResource>(db, None).ok();
+    let expected_supply = supply::expected_genesis_supply(&list);
+
+    for (i, old) in list.iter().enumerate() {
+        if old.role == AccountRole::Drop {
+            continue;
+        }
+        let Some(account) = old.account else {
+            continue;
+        };
+        report.accounts_audited += 1;
+
+        let err = |expected: usize, migrated: usize, message: &str| CompareError {
+            index: i as u64,
+            account: Some(account),
+            expected: expected as u64,
+            migrated: migrated as u64,
+            message: message.to_string(),
+        };
+
+        // ancestry
+        if let Some(old_anc) = &old.ancestry {
+            match get_struct::<AncestryResource>(db, Some(account)) {
+                Ok(new_anc) if new_anc.tree != old_anc.tree => report.ancestry.push(err(
+                    old_anc.tree.len(),
+                    new_anc.tree.len(),
+                    "ancestry tree mismatch",
+                )),
+                Ok(_) => {}
+                Err(_) => report
+                    .ancestry
+                    .push(err(old_anc.tree.len(), 0, "ancestry not migrated")),
+            }
+        }
+
+        // receipts
+        if let Some(old_rec) = &old.receipts {
+            match get_struct::<ReceiptsResource>(db, Some(account)) {
+                Ok(new_rec) => {
+                    if new_rec.destination != old_rec.destination {
+                        report.receipts.push(err(
+                            old_rec.destination.len(),
+                            new_rec.destination.len(),
+                            "receipts destinations mismatch",
+                        ));
+                    } else if new_rec.cumulative != old_rec.cumulative {
+                        let sum = |v: &[u64]| v.iter().sum::<u64>() as usize;
+                        report.receipts.push(err(
+                            sum(&old_rec.cumulative),
+                            sum(&new_rec.cumulative),
+                            "receipts cumulative mismatch",
+                        ));
+                    }
+                }
+                Err(_) => {
+                    report
+                        .receipts
+                        .push(err(old_rec.destination.len(), 0, "receipts not migrated"))
+                }
+            }
+        }
+
+        // community wallets
+        if old.comm_wallet.is_some() {
+            let registered = cw_registry
+                .as_ref()
+                .map(|r| r.list.contains(&account))
+                .unwrap_or(false);
+            if !registered {
+                report.community_wallets.push(err(
+                    1,
+                    0,
+                    "community wallet not in donor voice registry",
+                ));
+            }
+        }
+    }
+    OLProgress::complete("audited ancestry, receipts, and community wallets");
+
+    // validator set
+    let val_set = compare::get_val_set(db)?;
+    if expected_vals.is_empty() && val_set.is_empty() {
+        report.validator_set.push(CompareError {
+            index: 0,
+            account: None,
+            expected: 1,
+            migrated: 0,
+            message: "validator set is empty".to_string(),
+        });
+    }
+    for v in expected_vals.iter().filter(|v| !val_set.contains(v)) {
+        report.validator_set.push(CompareError {
+            index: 0,
+            account: Some(*v),
+            expected: 1,
+            migrated: 0,
+            message: "validator missing from genesis set".to_string(),
+        });
+    }
+    if !expected_vals.is_empty() {
+        for v in val_set.iter().filter(|v| !expected_vals.contains(v)) {
+            report.validator_set.push(CompareError {
+                index: 0,
+                account: Some(*v),
+                expected: 0,
+                migrated: 1,
+                message: "unexpected validator in genesis set".to_string(),
+            });
+        }
+    }
+
+    // total supply
+    let on_chain_supply = total_supply(db).context("genesis has no supply")? as u64;
+    if on_chain_supply != expected_supply {
+        report.supply.push(CompareError {
+            index: 0,
+            account: None,
+            expected: expected_supply,
+            migrated: on_chain_supply,
+            message: "total supply differs from the migrated balances and escrowed pledges"
+                .to_string(),
+        });
+    }
+    OLProgress::complete("audited validator set and supply");
+
+    Ok(report)
+}
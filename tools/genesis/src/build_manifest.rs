@@ -0,0 +1,373 @@
+//! A manifest of every input to a genesis build, so that validators can
+//! independently rebuild the genesis.blob and confirm it's byte-identical.
+//! Validators who reproduced the build can sign the manifest.
+
+use crate::{
+    genesis_builder, genesis_registry::GenesisRegistry, migration_filters::MigrationFilters,
+    parse_json,
+};
+use anyhow::{bail, Context, Result};
+use diem_config::config::IdentityBlob;
+use diem_crypto::{
+    ed25519::{Ed25519PublicKey, Ed25519Signature},
+    PrivateKey, Signature, SigningKey,
+};
+use diem_temppath::TempPath;
+use libra_types::{
+    exports::{AccountAddress, ChainId, NamedChain},
+    ol_progress::OLProgress,
+};
+use libra_wallet::keys::VALIDATOR_FILE;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{
+    cell::RefCell,
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+pub const BUILD_MANIFEST_FILE: &str = "build_manifest.json";
+
+/// Files given to the build, other than the registry ones
+#[derive(Debug, Clone, Default)]
+pub struct BuildInputs {
+    /// legacy recovery json
+    pub recovery: Option<PathBuf>,
+    /// list of accounts to drop from the recovery
+    pub drop_list: Option<PathBuf>,
+    /// filters applied to the recovery, after the drop list
+    pub filters: MigrationFilters,
+}
+
+/// A file and its hash
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HashedFile {
+    pub path: PathBuf,
+    pub sha256: String,
+}
+
+impl HashedFile {
+    pub fn from_path(path: &Path) -> Result<Self> {
+        let bytes = fs::read(path).context(format!("cannot read {}", path.display()))?;
+        Ok(Self {
+            path: path.to_owned(),
+            sha256: sha256_hex(&bytes),
+        })
+    }
+
+    /// check the file at `path` (or the recorded path) has the recorded hash
+    pub fn check(&self, path: Option<&Path>) -> Result<PathBuf> {
+        let path = path.unwrap_or(&self.path);
+        let found = Self::from_path(path)?;
+        if found.sha256 != self.sha256 {
+            bail!(
+                "{} has sha256 {}, the manifest expects {}",
+                path.display(),
+                found.sha256,
+                self.sha256
+            );
+        }
+        Ok(path.to_owned())
+    }
+}
+
+/// A validator's signature of the manifest
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ManifestSignature {
+    pub signer: AccountAddress,
+    pub public_key: Ed25519PublicKey,
+    pub signature: Ed25519Signature,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BuildManifest {
+    /// chain id of the genesis
+    pub chain_id: u8,
+    /// the framework .mrb, if it was not fetched from the registry
+    pub framework: Option<HashedFile>,
+    /// the legacy recovery file
+    pub recovery: Option<HashedFile>,
+    /// the drop list
+    pub drop_list: Option<HashedFile>,
+    /// the migration filters, if any
+    #[serde(default)]
+    pub filters: Option<MigrationFilters>,
+    /// the account list of the include-only filter
+    #[serde(default)]
+    pub include_only: Option<HashedFile>,
+    /// the registry the validators were read from, none for testnets
+    pub registry: Option<String>,
+    /// hashes of the files read from the registry: the layout,
+    /// every validator's operator and owner files, and maybe the framework
+    pub registry_files: BTreeMap<String, String>,
+    /// hash of the genesis.blob
+    pub genesis_sha256: String,
+    pub waypoint: String,
+    /// validators who reproduced this build
+    #[serde(default)]
+    pub signatures: Vec<ManifestSignature>,
+}
+
+impl BuildManifest {
+    pub fn load(path: &Path) -> Result<Self> {
+        let s = fs::read_to_string(path)
+            .context(format!("cannot read build manifest {}", path.display()))?;
+        Ok(serde_json::from_str(&s)?)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// what the validators sign: the manifest without any signatures
+    pub fn signing_bytes(&self) -> Result<Vec<u8>> {
+        let mut unsigned = self.clone();
+        unsigned.signatures = vec![];
+        Ok(serde_json::to_vec(&unsigned)?)
+    }
+
+    /// sign with the account key of the validator in `home`
+    pub fn sign(&mut self, home: &Path) -> Result<()> {
+        let id = IdentityBlob::from_file(&home.join(VALIDATOR_FILE))?;
+        let key = id
+            .account_private_key
+            .context(format!("no account private key in {}", VALIDATOR_FILE))?;
+        let signer = id
+            .account_address
+            .context(format!("no account address in {}", VALIDATOR_FILE))?;
+
+        let signature = key.sign_arbitrary_message(&self.signing_bytes()?);
+        self.signatures.retain(|s| s.signer != signer);
+        self.signatures.push(ManifestSignature {
+            signer,
+            public_key: key.public_key(),
+            signature,
+        });
+        Ok(())
+    }
+
+    /// check every signature on the manifest
+    pub fn verify_signatures(&self) -> Result<()> {
+        let msg = self.signing_bytes()?;
+        for s in &self.signatures {
+            s.signature
+                .verify_arbitrary_msg(&msg, &s.public_key)
+                .context(format!("invalid signature from {}", s.signer))?;
+        }
+        Ok(())
+    }
+}
+
+/// Wraps a registry and records the hash of every file read from it
+pub struct HashingRegistry<'a> {
+    inner: &'a dyn GenesisRegistry,
+    files: RefCell<BTreeMap<String, String>>,
+}
+
+impl<'a> HashingRegistry<'a> {
+    pub fn new(inner: &'a dyn GenesisRegistry) -> Self {
+        Self {
+            inner,
+            files: RefCell::new(BTreeMap::new()),
+        }
+    }
+
+    pub fn hashes(&self) -> BTreeMap<String, String> {
+        self.files.borrow().clone()
+    }
+}
+
+impl GenesisRegistry for HashingRegistry<'_> {
+    fn get_file(&self, path: &str) -> Result<Vec<u8>> {
+        let bytes = self.inner.get_file(path)?;
+        self.files
+            .borrow_mut()
+            .insert(path.to_owned(), sha256_hex(&bytes));
+        Ok(bytes)
+    }
+
+    fn put_file(&self, path: &str, content: &[u8]) -> Result<()> {
+        self.inner.put_file(path, content)
+    }
+
+    fn describe(&self) -> String {
+        self.inner.describe()
+    }
+}
+
+pub fn sha256_hex(bytes: &[u8]) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(bytes);
+    hex::encode(hasher.finalize())
+}
+
+/// Rebuild the genesis from the inputs of a manifest, and check the blob is
+/// byte-identical. Local files can be given at other paths than the ones
+/// recorded, as long as the hashes match.
+/// If `sign_home` is set, the validator in that home signs the manifest.
+pub fn verify_build(
+    manifest_path: &Path,
+    registry: &dyn GenesisRegistry,
+    framework_mrb_path: Option<PathBuf>,
+    inputs: &BuildInputs,
+    sign_home: Option<&Path>,
+) -> Result<()> {
+    let mut manifest = BuildManifest::load(manifest_path)?;
+    manifest.verify_signatures()?;
+    OLProgress::complete(&format!(
+        "manifest has {} valid signature(s)",
+        manifest.signatures.len()
+    ));
+
+    let framework = match &manifest.framework {
+        Some(f) => Some(f.check(framework_mrb_path.as_deref())?),
+        None => None,
+    };
+    let recovery_path = match &manifest.recovery {
+        Some(f) => Some(f.check(inputs.recovery.as_deref())?),
+        None => None,
+    };
+    let drop_list_path = match &manifest.drop_list {
+        Some(f) => Some(f.check(inputs.drop_list.as_deref())?),
+        None => None,
+    };
+    let mut filters = manifest.filters.clone().unwrap_or_default();
+    if let Some(f) = &manifest.include_only {
+        filters.include_only = Some(f.check(inputs.filters.include_only.as_deref())?);
+    } else if filters.include_only.is_some() {
+        bail!("the manifest has an include-only filter, but no hash of its account list");
+    }
+    OLProgress::complete("local inputs match the manifest");
+
+    let mut recovery = match &recovery_path {
+        Some(p) => parse_json::recovery_file_parse(p.to_owned())?,
+        None => vec![],
+    };
+    if let Some(dp) = &drop_list_path {
+        parse_json::drop_accounts(&mut recovery, dp)?;
+    }
+    if !filters.is_empty() {
+        filters.apply(&mut recovery)?;
+    }
+
+    let chain = NamedChain::from_chain_id(&ChainId::new(manifest.chain_id))
+        .map_err(|e| anyhow::anyhow!("unknown chain id in manifest: {}", e))?;
+
+    let temp_home = TempPath::new();
+    temp_home.create_as_dir()?;
+    genesis_builder::build(
+        Some(registry),
+        temp_home.path().to_owned(),
+        framework,
+        &mut recovery,
+        chain,
+        None,
+        BuildInputs {
+            recovery: recovery_path,
+            drop_list: drop_list_path,
+            filters,
+        },
+    )?;
+    let rebuilt = BuildManifest::load(&temp_home.path().join("genesis").join(BUILD_MANIFEST_FILE))?;
+
+    // point to the registry files which changed since the build
+    let mut errs = vec![];
+    for (path, hash) in &manifest.registry_files {
+        match rebuilt.registry_files.get(path) {
+            Some(h) if h == hash => {}
+            Some(h) => errs.push(format!("{}: sha256 {} expected {}", path, h, hash)),
+            None => errs.push(format!("{}: not read in the rebuild", path)),
+        }
+    }
+    for path in rebuilt.registry_files.keys() {
+        if !manifest.registry_files.contains_key(path) {
+            errs.push(format!("{}: not in the manifest", path));
+        }
+    }
+    if rebuilt.genesis_sha256 != manifest.genesis_sha256 {
+        errs.push(format!(
+            "genesis.blob sha256 {} expected {}",
+            rebuilt.genesis_sha256, manifest.genesis_sha256
+        ));
+    }
+    if rebuilt.waypoint != manifest.waypoint {
+        errs.push(format!(
+            "waypoint {} expected {}",
+            rebuilt.waypoint, manifest.waypoint
+        ));
+    }
+    if !errs.is_empty() {
+        bail!("genesis build is not reproduced:\n{}", errs.join("\n"));
+    }
+    OLProgress::complete(&format!(
+        "genesis reproduced, waypoint {}",
+        manifest.waypoint
+    ));
+
+    if let Some(home) = sign_home {
+        manifest.sign(home)?;
+        manifest.save(manifest_path)?;
+        OLProgress::complete("signed the build manifest");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::genesis_registry::LocalDirRegistry;
+    use diem_crypto::ed25519::Ed25519PrivateKey;
+
+    fn sample_manifest() -> BuildManifest {
+        BuildManifest {
+            chain_id: 4,
+            framework: None,
+            recovery: None,
+            drop_list: None,
+            filters: None,
+            include_only: None,
+            registry: None,
+            registry_files: BTreeMap::new(),
+            genesis_sha256: sha256_hex(b"genesis"),
+            waypoint: "0:abc".to_string(),
+            signatures: vec![],
+        }
+    }
+
+    #[test]
+    fn hashing_registry_records_reads() -> Result<()> {
+        let temp = TempPath::new();
+        temp.create_as_dir()?;
+        let inner = LocalDirRegistry::new(temp.path().to_owned());
+        inner.put_file("layout.yaml", b"users: []")?;
+
+        let registry = HashingRegistry::new(&inner);
+        registry.get_file("layout.yaml")?;
+        assert_eq!(
+            registry.hashes().get("layout.yaml"),
+            Some(&sha256_hex(b"users: []"))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn signatures_cover_the_manifest() -> Result<()> {
+        let key = Ed25519PrivateKey::try_from([7u8; 32].as_slice())?;
+
+        let mut m = sample_manifest();
+        let signature = key.sign_arbitrary_message(&m.signing_bytes()?);
+        m.signatures.push(ManifestSignature {
+            signer: AccountAddress::random(),
+            public_key: key.public_key(),
+            signature,
+        });
+        m.verify_signatures()?;
+
+        // any change to the inputs breaks the signature
+        m.genesis_sha256 = sha256_hex(b"another genesis");
+        assert!(m.verify_signatures().is_err());
+        Ok(())
+    }
+}
@@ -1,10 +1,24 @@
 use crate::{
-    genesis_builder, parse_json,
+    audit,
+    build_manifest::{self, BuildInputs},
+    genesis_builder, genesis_reader, genesis_registration,
+    genesis_registry::{GenesisRegistry, GitDirRegistry, GithubRegistry, LocalDirRegistry},
+    migration_filters::{MigrationFilters, FILTER_REPORT_FILE},
+    parse_json,
     wizard::{GenesisWizard, GITHUB_TOKEN_FILENAME},
 };
+use anyhow::{bail, Context};
 use clap::{Args, Parser, Subcommand};
-use libra_types::{exports::NamedChain, global_config_dir};
-use std::{fs, path::PathBuf};
+use diem_config::config::IdentityBlob;
+use libra_types::{
+    exports::{AccountAddress, NamedChain},
+    global_config_dir,
+};
+use libra_wallet::keys::VALIDATOR_FILE;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
 
 #[derive(Parser)]
 #[clap(author, version, about, long_about = None, arg_required_else_help = true)]
@@ -27,7 +41,11 @@ impl GenesisCli {
         let chain_name = self.chain.unwrap_or(NamedChain::TESTNET); // chain_id = 2
 
         match &self.command {
-            Some(Sub::Build { github, drop_list }) => {
+            Some(Sub::Build {
+                github,
+                drop_list,
+                filters,
+            }) => {
                 let mut recovery = if let Some(p) = github.json_legacy.clone() {
                     parse_json::recovery_file_parse(p)?
                 } else {
@@ -38,35 +56,117 @@ impl GenesisCli {
                     parse_json::drop_accounts(&mut recovery, dp)?;
                 };
 
-                // TODO: there's no reason a github token should be needed to
-                // read the genesis
-                let github_token = fs::read_to_string(data_path.join(GITHUB_TOKEN_FILENAME))?;
+                if !filters.is_empty() {
+                    let report = filters.apply(&mut recovery)?;
+                    report.print_summary();
+                    let genesis_dir = data_path.join("genesis");
+                    fs::create_dir_all(&genesis_dir)?;
+                    report.save(&genesis_dir.join(FILTER_REPORT_FILE))?;
+                }
+
+                let registry = github.registry(&data_path)?;
 
                 genesis_builder::build(
-                    github.org_github.to_owned(),
-                    github.name_github.to_owned(),
-                    github_token,
+                    Some(registry.as_ref()),
                     data_path,
                     github.local_framework.to_owned(),
                     &mut recovery,
                     chain_name,
                     None,
+                    BuildInputs {
+                        recovery: github.json_legacy.to_owned(),
+                        drop_list: drop_list.to_owned(),
+                        filters: filters.to_owned(),
+                    },
                 )?;
             }
+            Some(Sub::Register { github }) if github.is_offline() => {
+                // no wizard: the validator configs must already be in the home dir
+                let id = IdentityBlob::from_file(&data_path.join(VALIDATOR_FILE))?;
+                let address = id
+                    .account_address
+                    .context(format!(
+                        "cannot find an account address in {}",
+                        VALIDATOR_FILE
+                    ))?
+                    .to_hex_literal();
+                let registry = github.registry(&data_path)?;
+                genesis_registration::register(address.clone(), registry.as_ref(), data_path)?;
+                println!(
+                    "registered {} to {}, it must also be added to the layout.yaml",
+                    address,
+                    registry.describe()
+                );
+            }
             Some(Sub::Register { github }) => {
-                GenesisWizard::new(
-                    github.org_github.to_owned(),
-                    github.name_github.to_owned(),
-                    Some(data_path),
-                    chain_name,
-                )
-                .start_wizard(
+                let (Some(org), Some(name)) = (&github.org_github, &github.name_github) else {
+                    bail!("provide --org-github and --name-github, or --local-registry, or --git-registry");
+                };
+                GenesisWizard::new(org.to_owned(), name.to_owned(), Some(data_path), chain_name)
+                    .start_wizard(
+                        github.local_framework.to_owned(),
+                        github.json_legacy.to_owned(),
+                        github.token_github_file.to_owned(),
+                        false,
+                    )
+                    .await?;
+            }
+            Some(Sub::VerifyBuild {
+                manifest,
+                github,
+                drop_list,
+                include_only,
+                sign,
+            }) => {
+                let registry = github.registry(&data_path)?;
+                // the filters come from the manifest, only the include list
+                // can be at another local path
+                let inputs = BuildInputs {
+                    recovery: github.json_legacy.to_owned(),
+                    drop_list: drop_list.to_owned(),
+                    filters: MigrationFilters {
+                        include_only: include_only.to_owned(),
+                        ..Default::default()
+                    },
+                };
+                let manifest = manifest.to_owned().unwrap_or_else(|| {
+                    data_path
+                        .join("genesis")
+                        .join(build_manifest::BUILD_MANIFEST_FILE)
+                });
+                build_manifest::verify_build(
+                    &manifest,
+                    registry.as_ref(),
                     github.local_framework.to_owned(),
-                    github.json_legacy.to_owned(),
-                    github.token_github_file.to_owned(),
-                    false,
-                )
-                .await?;
+                    &inputs,
+                    sign.then_some(data_path.as_path()),
+                )?;
+            }
+            Some(Sub::Audit {
+                recovery,
+                genesis,
+                drop_list,
+                validator,
+                out,
+            }) => {
+                let mut recovery = parse_json::recovery_file_parse(recovery.to_owned())?;
+                if let Some(dp) = drop_list {
+                    parse_json::drop_accounts(&mut recovery, dp)?;
+                };
+                let gen_tx = genesis_reader::read_blob_to_tx(genesis.to_owned())?;
+
+                let report = audit::audit_genesis(&mut recovery, &gen_tx, validator)?;
+                report.print_summary();
+
+                let out = out
+                    .to_owned()
+                    .unwrap_or_else(|| PathBuf::from("genesis_audit.json"));
+                report.save(&out)?;
+                println!("report saved to {}", out.display());
+
+                if !report.is_clean() {
+                    bail!("genesis audit found {} mismatches", report.mismatch_count());
+                }
             }
             _ => {}
         }
@@ -83,10 +183,19 @@ struct GithubArgs {
     token_github_file: Option<PathBuf>,
     /// what are the settings for the genesis repo configs
     #[clap(short, long)]
-    org_github: String,
+    org_github: Option<String>,
     /// name of the repo
     #[clap(short, long)]
-    name_github: String,
+    name_github: Option<String>,
+    /// use a local directory as the genesis registry, instead of github
+    #[clap(long, conflicts_with_all = ["org_github", "git_registry"])]
+    local_registry: Option<PathBuf>,
+    /// use a git repository on disk (usually bare) as the genesis registry, instead of github
+    #[clap(long, conflicts_with = "org_github")]
+    git_registry: Option<PathBuf>,
+    /// branch of the git registry
+    #[clap(long, requires = "git_registry")]
+    git_branch: Option<String>,
     /// uses the local framework build
     #[clap(short, long)]
     local_framework: Option<PathBuf>,
@@ -95,9 +204,44 @@ struct GithubArgs {
     json_legacy: Option<PathBuf>,
 }
 
+impl GithubArgs {
+    /// registries which don't need github
+    fn is_offline(&self) -> bool {
+        self.local_registry.is_some() || self.git_registry.is_some()
+    }
+
+    fn registry(&self, data_path: &Path) -> anyhow::Result<Box<dyn GenesisRegistry>> {
+        if let Some(p) = &self.local_registry {
+            return Ok(Box::new(LocalDirRegistry::new(p.to_owned())));
+        }
+        if let Some(p) = &self.git_registry {
+            return Ok(Box::new(GitDirRegistry::new(
+                p.to_owned(),
+                self.git_branch.to_owned(),
+            )));
+        }
+        let (Some(org), Some(name)) = (&self.org_github, &self.name_github) else {
+            bail!("provide --org-github and --name-github, or --local-registry, or --git-registry");
+        };
+        let token_path = self
+            .token_github_file
+            .to_owned()
+            .unwrap_or_else(|| data_path.join(GITHUB_TOKEN_FILENAME));
+        let token = fs::read_to_string(&token_path).context(format!(
+            "cannot read github token at {}",
+            token_path.display()
+        ))?;
+        Ok(Box::new(GithubRegistry::new(
+            org.to_owned(),
+            name.to_owned(),
+            token.trim().to_owned(),
+        )))
+    }
+}
+
 #[derive(Subcommand)]
 enum Sub {
-    /// build a genesis file from the coordination git repo
+    /// build a genesis file from the coordination registry (github, a directory, or a git repo)
     Build {
         /// github args
         #[clap(flatten)]
@@ -106,8 +250,50 @@ enum Sub {
         /// Ark B
         #[clap(long)]
         drop_list: Option<PathBuf>,
+
+        /// filters on the accounts to migrate, applied after the drop list
+        #[clap(flatten)]
+        filters: MigrationFilters,
     }, // just do genesis without wizard
-    /// register to the genesis coordination git repository
+    /// rebuild the genesis from the inputs in a build manifest, and check the
+    /// genesis.blob is identical. Local files can be given at other paths.
+    VerifyBuild {
+        /// path to the build_manifest.json, defaults to the one in home_dir/genesis
+        #[clap(short, long)]
+        manifest: Option<PathBuf>,
+        /// registry args, and the local framework and recovery files
+        #[clap(flatten)]
+        github: GithubArgs,
+        /// the drop list, if the manifest has one
+        #[clap(long)]
+        drop_list: Option<PathBuf>,
+        /// the include-only account list, if the manifest filters have one
+        #[clap(long)]
+        include_only: Option<PathBuf>,
+        /// sign the manifest with the validator keys in home_dir, if the build is reproduced
+        #[clap(long)]
+        sign: bool,
+    },
+    /// audit a genesis blob against the recovery file it was built from
+    Audit {
+        /// path to the legacy recovery json file
+        #[clap(short, long)]
+        recovery: PathBuf,
+        /// path to the genesis.blob
+        #[clap(short, long)]
+        genesis: PathBuf,
+        /// the drop list used for the genesis, if any
+        #[clap(long)]
+        drop_list: Option<PathBuf>,
+        /// expected genesis validators, can be repeated
+        #[clap(long)]
+        validator: Vec<AccountAddress>,
+        /// where to write the JSON report, defaults to ./genesis_audit.json
+        #[clap(short, long)]
+        out: Option<PathBuf>,
+    },
+    /// register to the genesis coordination registry.
+    /// With a local or git registry the validator configs must already exist
     Register {
         /// github args
         #[clap(flatten)]
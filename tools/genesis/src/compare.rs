@@ -200,7 +200,7 @@ pub fn compare_json_to_genesis_blob(
 }
 
 // Check that the genesis validators are present in the genesis blob file, once we read the db.
-fn get_val_set(db_reader: &Arc<dyn DbReader>) -> anyhow::Result<Vec<AccountAddress>> {
+pub fn get_val_set(db_reader: &Arc<dyn DbReader>) -> anyhow::Result<Vec<AccountAddress>> {
     let db_state_view = db_reader.latest_state_checkpoint_view().unwrap();
     let root_account_state_view = db_state_view.as_account_with_state_view(&CORE_CODE_ADDRESS);
 
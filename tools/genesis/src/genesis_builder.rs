@@ -1,11 +1,18 @@
 //! build the genesis file
 
-use crate::{compare, supply, vm, wizard::DEFAULT_GIT_BRANCH};
+use crate::{
+    build_manifest::{
+        sha256_hex, BuildInputs, BuildManifest, HashedFile, HashingRegistry, BUILD_MANIFEST_FILE,
+    },
+    compare,
+    genesis_registry::GenesisRegistry,
+    supply, vm,
+};
 use crate::{
     genesis::make_recovery_genesis_from_vec_legacy_recovery,
     genesis_reader::bootstrap_db_reader_from_gen_tx,
 };
-use anyhow::{anyhow, bail, Result};
+use anyhow::{anyhow, bail, Context, Result};
 use diem_crypto::{
     bls12381,
     ed25519::{Ed25519PublicKey, ED25519_PUBLIC_KEY_LENGTH},
@@ -19,7 +26,6 @@ use diem_genesis::{
     },
     GenesisInfo,
 };
-use diem_github_client::Client;
 use diem_types::{
     account_address::{AccountAddress, AccountAddressWithChecks},
     on_chain_config::{OnChainConsensusConfig, OnChainExecutionConfig},
@@ -68,14 +74,13 @@ struct LibraSimpleLayout {
 // TODO: too many arguments, minor infraction
 #[allow(clippy::too_many_arguments)]
 pub fn build(
-    github_owner: String,
-    github_repository: String,
-    github_token: String,
+    registry: Option<&dyn GenesisRegistry>,
     home_path: PathBuf,
     framework_mrb_path: Option<PathBuf>,
     legacy_recovery: &mut [LegacyRecoveryV6],
     chain_name: NamedChain,
     testnet_vals: Option<Vec<ValidatorConfiguration>>,
+    inputs: BuildInputs,
 ) -> Result<Vec<PathBuf>> {
     let output_dir = home_path.join("genesis");
     std::fs::create_dir_all(&output_dir)?;
@@ -91,6 +96,13 @@ pub fn build(
 
     let genesis_config = vm::libra_genesis_default(chain_name);
 
+    // record everything read from the registry for the build manifest
+    let registry = registry.map(HashingRegistry::new);
+    let framework_file = match &framework_mrb_path {
+        Some(p) => Some(HashedFile::from_path(p)?),
+        None => None,
+    };
+
     let mut gen_info = if let Some(vals) = testnet_vals {
         let dummy_root = Ed25519PublicKey::from_encoded_string(
             "0x0000000000000000000000000000000000000000000000000000000000000000",
@@ -112,14 +124,10 @@ pub fn build(
             &silly_config(&genesis_config),
         )?
     } else {
-        fetch_genesis_info(
-            github_owner,
-            github_repository,
-            github_token,
-            framework_mrb_path,
-            &genesis_config,
-            &chain_name,
-        )?
+        let registry = registry
+            .as_ref()
+            .context("a genesis registry is needed to find the validators")?;
+        fetch_genesis_info(registry, framework_mrb_path, &genesis_config, &chain_name)?
     };
 
     println!("building genesis block");
@@ -140,17 +148,30 @@ pub fn build(
         .with_message("saving files");
     pb.enable_steady_tick(Duration::from_millis(100));
 
-    write_to_user_only_file(
-        genesis_file.as_path(),
-        GENESIS_FILE,
-        bcs::to_bytes(gen_info.get_genesis())?.as_slice(),
-    )?;
+    let genesis_bytes = bcs::to_bytes(gen_info.get_genesis())?;
+    write_to_user_only_file(genesis_file.as_path(), GENESIS_FILE, &genesis_bytes)?;
 
-    write_to_user_only_file(
-        waypoint_file.as_path(),
-        WAYPOINT_FILE,
-        gen_info.generate_waypoint()?.to_string().as_bytes(),
-    )?;
+    let waypoint = gen_info.generate_waypoint()?.to_string();
+    write_to_user_only_file(waypoint_file.as_path(), WAYPOINT_FILE, waypoint.as_bytes())?;
+
+    let hashed = |p: &Option<PathBuf>| -> Result<Option<HashedFile>> {
+        p.as_deref().map(HashedFile::from_path).transpose()
+    };
+    let manifest = BuildManifest {
+        chain_id: chain_name.id(),
+        framework: framework_file,
+        recovery: hashed(&inputs.recovery)?,
+        drop_list: hashed(&inputs.drop_list)?,
+        include_only: hashed(&inputs.filters.include_only)?,
+        filters: (!inputs.filters.is_empty()).then_some(inputs.filters),
+        registry: registry.as_ref().map(|r| r.describe()),
+        registry_files: registry.map(|r| r.hashes()).unwrap_or_default(),
+        genesis_sha256: sha256_hex(&genesis_bytes),
+        waypoint,
+        signatures: vec![],
+    };
+    let manifest_file = output_dir.join(BUILD_MANIFEST_FILE);
+    manifest.save(&manifest_file)?;
     pb.finish_and_clear();
     OLProgress::complete(&format!(
         "genesis file saved to {}",
@@ -174,7 +195,7 @@ pub fn build(
     }
 
     OLProgress::complete("LFG, ready for genesis");
-    Ok(vec![genesis_file, waypoint_file])
+    Ok(vec![genesis_file, waypoint_file, manifest_file])
 }
 
 /// there are two structs called GenesisConfiguration in Vendor code, sigh.
@@ -199,29 +220,20 @@ fn silly_config(cfg: &VmGenesisGenesisConfiguration) -> GenesisConfiguration {
     }
 }
 
-/// Retrieves all information for mainnet genesis from the Git repository
+/// Retrieves all information for mainnet genesis from the registry
 pub fn fetch_genesis_info(
-    github_owner: String,
-    github_repository: String,
-    github_token: String,
+    registry: &dyn GenesisRegistry,
     framework_mrb_path: Option<PathBuf>,
     genesis_config: &VmGenesisGenesisConfiguration,
     chain_id: &NamedChain,
 ) -> Result<GenesisInfo> {
-    let client = Client::new(
-        github_owner, // doesn't matter
-        github_repository,
-        DEFAULT_GIT_BRANCH.to_string(),
-        github_token,
-    );
-
-    let l_file = client.get_file(&Path::new(LAYOUT_FILE).display().to_string())?;
-    let layout: LibraSimpleLayout = from_yaml(&String::from_utf8(base64::decode(l_file)?)?)?;
-    OLProgress::complete("fetched layout file");
+    let l_file = registry.get_file(LAYOUT_FILE)?;
+    let layout: LibraSimpleLayout = from_yaml(&String::from_utf8(l_file)?)?;
+    OLProgress::complete(&format!("fetched layout file from {}", registry.describe()));
 
     let pb = OLProgress::spin_steady(500, "fetching validator registrations".to_string());
 
-    let validators = get_validator_configs(&client, &layout, false)?;
+    let validators = get_validator_configs(registry, &layout, false)?;
     OLProgress::complete("fetched validator configs");
     pb.finish_and_clear();
 
@@ -229,8 +241,8 @@ pub fn fetch_genesis_info(
         // use the local head release
         ReleaseTarget::load_bundle_from_file(p)?
     } else {
-        // get from github
-        let bytes = base64::decode(client.get_file(FRAMEWORK_NAME)?)?;
+        // get from the registry
+        let bytes = registry.get_file(FRAMEWORK_NAME)?;
         bcs::from_bytes::<ReleaseBundle>(&bytes)?
     };
 
@@ -251,14 +263,14 @@ pub fn fetch_genesis_info(
 
 /// Retrieves validator configurations
 fn get_validator_configs(
-    client: &Client,
+    registry: &dyn GenesisRegistry,
     layout: &LibraSimpleLayout,
     is_mainnet: bool,
 ) -> Result<Vec<ValidatorConfiguration>> {
     let mut validators = Vec::new();
     let mut errors = Vec::new();
     for user in &layout.users {
-        match get_config(client, user, is_mainnet) {
+        match get_config(registry, user, is_mainnet) {
             Ok(validator) => {
                 validators.push(validator);
             }
@@ -276,15 +288,18 @@ fn get_validator_configs(
 }
 
 /// Do proper parsing so more information is known about failures
-fn get_config(client: &Client, user: &str, _is_mainnet: bool) -> Result<ValidatorConfiguration> {
+fn get_config(
+    registry: &dyn GenesisRegistry,
+    user: &str,
+    _is_mainnet: bool,
+) -> Result<ValidatorConfiguration> {
     // Load a user's configuration files
     let dir = PathBuf::from(user);
     let owner_file = dir.join(OWNER_FILE);
     let owner_file = owner_file.as_path();
 
-    let file = client.get_file(&Path::new(owner_file).display().to_string())?;
-    let owner_config: StringOwnerConfiguration =
-        from_yaml(&String::from_utf8(base64::decode(file)?)?)?;
+    let file = registry.get_file(&owner_file.display().to_string())?;
+    let owner_config: StringOwnerConfiguration = from_yaml(&String::from_utf8(file)?)?;
 
     // Check and convert fields in owner file
     let owner_account_address: AccountAddress = parse_required_option(
@@ -357,9 +372,8 @@ fn get_config(client: &Client, user: &str, _is_mainnet: bool) -> Result<Validato
     let operator_file = dir.join(OPERATOR_FILE);
     let operator_file = operator_file.as_path();
 
-    let file = client.get_file(&Path::new(operator_file).display().to_string())?;
-    let operator_config: StringOperatorConfiguration =
-        from_yaml(&String::from_utf8(base64::decode(file)?)?)?;
+    let file = registry.get_file(&operator_file.display().to_string())?;
+    let operator_config: StringOperatorConfiguration = from_yaml(&String::from_utf8(file)?)?;
 
     // Check and convert fields in operator file
     let operator_account_address_from_file: AccountAddress = parse_required_option(
@@ -556,14 +570,13 @@ fn parse_optional_option<F: Fn(&str) -> Result<T, E>, T, E: std::fmt::Display>(
 #[test]
 #[ignore] //dev helper
 fn test_github_info() {
-    use crate::vm::libra_genesis_default;
+    use crate::{genesis_registry::GithubRegistry, vm::libra_genesis_default};
     let gh_token_path = libra_types::global_config_dir().join("github_token.txt");
     let token = std::fs::read_to_string(gh_token_path).unwrap();
     let framework_mrb_path = ReleaseTarget::Head.find_bundle_path().ok();
+    let registry = GithubRegistry::new("0o-de-lally".to_string(), "a-genesis".to_string(), token);
     let _genesis_info = fetch_genesis_info(
-        "0o-de-lally".to_string(),
-        "a-genesis".to_string(),
-        token,
+        &registry,
         framework_mrb_path,
         &libra_genesis_default(NamedChain::TESTING),
         &NamedChain::TESTING,
@@ -577,15 +590,19 @@ fn test_build() {
     let home = libra_types::global_config_dir();
     let token = std::fs::read_to_string(home.join("github_token.txt")).unwrap();
     let framework_mrb_path = ReleaseTarget::Head.find_bundle_path().ok();
-    build(
+    let registry = crate::genesis_registry::GithubRegistry::new(
         "0o-de-lally".to_string(),
         "a-genesis".to_string(),
         token,
+    );
+    build(
+        Some(&registry),
         home,
         framework_mrb_path,
         &mut [],
         NamedChain::TESTING,
         None,
+        BuildInputs::default(),
     )
     .unwrap();
 }
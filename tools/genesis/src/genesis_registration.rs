@@ -1,5 +1,5 @@
+use crate::genesis_registry::GenesisRegistry;
 use anyhow::Result;
-use diem_github_client::Client;
 use libra_wallet::{
     utils::to_yaml,
     validator_files::{SetValidatorConfiguration, OPERATOR_FILE, OWNER_FILE},
@@ -9,12 +9,10 @@ use std::path::PathBuf;
 // TODO: duplicate with libra-wallet and diem/crate/diem/src/genesis/keys
 pub const PUBLIC_KEYS_FILE: &str = "public-keys.yaml";
 
-/// Function to publish the validator configuration files to github
+/// Function to publish the validator configuration files to the genesis registry
 pub fn register(
     genesis_username: String,
-    github_owner: String,
-    github_repository: String,
-    github_token: String,
+    registry: &dyn GenesisRegistry,
     home_path: PathBuf,
 ) -> Result<()> {
     let directory = PathBuf::from(genesis_username);
@@ -24,21 +22,14 @@ pub fn register(
     let (operator_config, owner_config) =
         SetValidatorConfiguration::read_configs_from_file(Some(home_path))?;
 
-    let git_client = Client::new(
-        github_owner,
-        github_repository,
-        "main".to_owned(),
-        github_token,
-    );
-
-    git_client.put(
+    registry.put_file(
         &operator_file.display().to_string(),
-        &base64::encode(to_yaml(&operator_config)?),
+        to_yaml(&operator_config)?.as_bytes(),
     )?;
 
-    git_client.put(
+    registry.put_file(
         &owner_file.display().to_string(),
-        &base64::encode(to_yaml(&owner_config)?),
+        to_yaml(&owner_config)?.as_bytes(),
     )?;
 
     Ok(())
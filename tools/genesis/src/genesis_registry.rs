@@ -0,0 +1,265 @@
+//! Where validators register for genesis, and where the genesis builder
+//! reads the registrations from.
+//! The files are the same for every backend: a `layout.yaml` at the root,
+//! an `operator.yaml` and `owner.yaml` per validator directory, and
+//! optionally a `framework.mrb`.
+//! Besides GitHub, a plain directory or a git repository on disk can be used,
+//! so that testnets and private networks can do genesis offline.
+
+use crate::wizard::DEFAULT_GIT_BRANCH;
+use anyhow::{bail, Context, Result};
+use diem_github_client::Client;
+use diem_temppath::TempPath;
+use std::{
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+};
+
+/// Read and write the genesis coordination files
+pub trait GenesisRegistry {
+    /// get the contents of a file, by its path relative to the registry root
+    fn get_file(&self, path: &str) -> Result<Vec<u8>>;
+    /// create or replace a file, by its path relative to the registry root
+    fn put_file(&self, path: &str, content: &[u8]) -> Result<()>;
+    /// for display
+    fn describe(&self) -> String;
+}
+
+/// The genesis repository on GitHub, the canonical ceremony
+pub struct GithubRegistry {
+    client: Client,
+    name: String,
+}
+
+impl GithubRegistry {
+    pub fn new(owner: String, repo: String, token: String) -> Self {
+        let name = format!("github:{}/{}", owner, repo);
+        Self {
+            client: Client::new(owner, repo, DEFAULT_GIT_BRANCH.to_string(), token),
+            name,
+        }
+    }
+}
+
+impl GenesisRegistry for GithubRegistry {
+    fn get_file(&self, path: &str) -> Result<Vec<u8>> {
+        let encoded = self.client.get_file(path)?;
+        Ok(base64::decode(encoded)?)
+    }
+
+    fn put_file(&self, path: &str, content: &[u8]) -> Result<()> {
+        self.client.put(path, &base64::encode(content))?;
+        Ok(())
+    }
+
+    fn describe(&self) -> String {
+        self.name.clone()
+    }
+}
+
+/// A plain directory, e.g. on a shared drive
+pub struct LocalDirRegistry {
+    root: PathBuf,
+}
+
+impl LocalDirRegistry {
+    pub fn new(root: PathBuf) -> Self {
+        Self { root }
+    }
+}
+
+impl GenesisRegistry for LocalDirRegistry {
+    fn get_file(&self, path: &str) -> Result<Vec<u8>> {
+        let p = self.root.join(path);
+        fs::read(&p).context(format!("cannot read registry file {}", p.display()))
+    }
+
+    fn put_file(&self, path: &str, content: &[u8]) -> Result<()> {
+        let p = self.root.join(path);
+        if let Some(parent) = p.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&p, content).context(format!("cannot write registry file {}", p.display()))
+    }
+
+    fn describe(&self) -> String {
+        format!("dir:{}", self.root.display())
+    }
+}
+
+/// A git repository on disk, usually a bare one which every operator
+/// can push to. Files are read from, and committed to, a branch directly
+/// without a checkout, so the registry never touches a working tree.
+/// NOTE: requires the `git` executable.
+pub struct GitDirRegistry {
+    repo: PathBuf,
+    branch: String,
+}
+
+impl GitDirRegistry {
+    pub fn new(repo: PathBuf, branch: Option<String>) -> Self {
+        Self {
+            repo,
+            branch: branch.unwrap_or_else(|| DEFAULT_GIT_BRANCH.to_string()),
+        }
+    }
+
+    fn git(&self, args: &[&str], stdin: Option<&[u8]>, index: Option<&Path>) -> Result<Vec<u8>> {
+        let mut cmd = Command::new("git");
+        cmd.arg("-C").arg(&self.repo);
+        // commits need an identity, don't fail on machines without one
+        if args.first() == Some(&"commit-tree") && !has_git_identity(&self.repo) {
+            cmd.args([
+                "-c",
+                "user.name=libra genesis",
+                "-c",
+                "user.email=genesis@localhost",
+            ]);
+        }
+        cmd.args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        if let Some(i) = index {
+            cmd.env("GIT_INDEX_FILE", i);
+        }
+
+        let mut child = cmd.spawn().context("cannot run git, is it installed?")?;
+        if let Some(input) = stdin {
+            child
+                .stdin
+                .take()
+                .context("no stdin for git")?
+                .write_all(input)?;
+        }
+        let out = child.wait_with_output()?;
+        if !out.status.success() {
+            bail!(
+                "git {} failed in {}: {}",
+                args.join(" "),
+                self.repo.display(),
+                String::from_utf8_lossy(&out.stderr).trim()
+            );
+        }
+        Ok(out.stdout)
+    }
+
+    fn git_str(&self, args: &[&str], stdin: Option<&[u8]>, index: Option<&Path>) -> Result<String> {
+        let out = self.git(args, stdin, index)?;
+        Ok(String::from_utf8(out)?.trim().to_owned())
+    }
+
+    /// the tip of the branch, None if nothing was committed yet
+    fn head(&self) -> Option<String> {
+        let ref_name = format!("refs/heads/{}", self.branch);
+        self.git_str(&["rev-parse", "--verify", "-q", &ref_name], None, None)
+            .ok()
+    }
+}
+
+impl GenesisRegistry for GitDirRegistry {
+    fn get_file(&self, path: &str) -> Result<Vec<u8>> {
+        self.git(
+            &["cat-file", "blob", &format!("{}:{}", self.branch, path)],
+            None,
+            None,
+        )
+    }
+
+    fn put_file(&self, path: &str, content: &[u8]) -> Result<()> {
+        let blob = self.git_str(&["hash-object", "-w", "--stdin"], Some(content), None)?;
+
+        // build the new tree in a throwaway index
+        let index_file = TempPath::new();
+        let index = Some(index_file.path());
+        let parent = self.head();
+        if let Some(p) = &parent {
+            self.git(&["read-tree", p], None, index)?;
+        }
+        self.git(
+            &[
+                "update-index",
+                "--add",
+                "--cacheinfo",
+                &format!("100644,{},{}", blob, path),
+            ],
+            None,
+            index,
+        )?;
+        let tree = self.git_str(&["write-tree"], None, index)?;
+
+        let msg = format!("register {}", path);
+        let mut args = vec!["commit-tree", tree.as_str(), "-m", msg.as_str()];
+        if let Some(p) = &parent {
+            args.extend(["-p", p.as_str()]);
+        }
+        let commit = self.git_str(&args, None, None)?;
+
+        // fails if someone else committed in the meantime
+        let ref_name = format!("refs/heads/{}", self.branch);
+        let mut args = vec!["update-ref", ref_name.as_str(), commit.as_str()];
+        if let Some(p) = &parent {
+            args.push(p.as_str());
+        }
+        self.git(&args, None, None)?;
+        Ok(())
+    }
+
+    fn describe(&self) -> String {
+        format!("git:{}#{}", self.repo.display(), self.branch)
+    }
+}
+
+fn has_git_identity(repo: &Path) -> bool {
+    Command::new("git")
+        .arg("-C")
+        .arg(repo)
+        .args(["var", "GIT_COMMITTER_IDENT"])
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .map(|s| s.success())
+        .unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(registry: &dyn GenesisRegistry) -> Result<()> {
+        registry.put_file("layout.yaml", b"users:\n  - alice\n")?;
+        registry.put_file("alice/owner.yaml", b"owner")?;
+        registry.put_file("alice/owner.yaml", b"owner v2")?;
+        assert_eq!(registry.get_file("layout.yaml")?, b"users:\n  - alice\n");
+        assert_eq!(registry.get_file("alice/owner.yaml")?, b"owner v2");
+        assert!(registry.get_file("bob/owner.yaml").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn local_dir_registry() -> Result<()> {
+        let temp = TempPath::new();
+        temp.create_as_dir()?;
+        let registry = LocalDirRegistry::new(temp.path().to_owned());
+        round_trip(&registry)?;
+        assert!(temp.path().join("alice/owner.yaml").exists());
+        Ok(())
+    }
+
+    #[test]
+    fn git_dir_registry() -> Result<()> {
+        let temp = TempPath::new();
+        temp.create_as_dir()?;
+        let status = Command::new("git")
+            .args(["init", "--bare", "-q"])
+            .arg(temp.path())
+            .status()?;
+        assert!(status.success());
+
+        let registry = GitDirRegistry::new(temp.path().to_owned(), None);
+        round_trip(&registry)?;
+        Ok(())
+    }
+}
@@ -1,4 +1,6 @@
 //! genesis
+pub mod audit;
+pub mod build_manifest;
 pub mod cli;
 pub mod compare;
 
@@ -7,7 +9,9 @@ pub mod genesis_builder;
 pub mod genesis_functions;
 pub mod genesis_reader;
 pub mod genesis_registration;
+pub mod genesis_registry;
 pub mod github_extensions;
+pub mod migration_filters;
 pub mod parse_json;
 pub mod process_comm_wallet;
 pub mod supply;
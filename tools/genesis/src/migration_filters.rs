@@ -0,0 +1,346 @@
+//! Declarative filters on the legacy accounts to migrate, for forks and
+//! test networks which don't want the whole state.
+//! Filters are applied to the recovery data before genesis, and every
+//! affected account is reported, along with the supply before and after.
+
+use crate::{
+    parse_json,
+    supply::{self, Supply},
+};
+use anyhow::{bail, Context};
+use clap::Args;
+use libra_backwards_compatibility::legacy_recovery_v6::{AccountRole, LegacyRecoveryV6};
+use libra_types::exports::AccountAddress;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+pub const FILTER_REPORT_FILE: &str = "migration_filter_report.json";
+
+/// Filters on the accounts to migrate. System accounts are never filtered.
+#[derive(Debug, Clone, Default, Args, Serialize, Deserialize, PartialEq)]
+pub struct MigrationFilters {
+    /// only migrate the accounts in this file, same format as the drop list
+    #[clap(long)]
+    pub include_only: Option<PathBuf>,
+    /// only migrate accounts with these roles: validator, operator, end-user.
+    /// Can be repeated
+    #[clap(long)]
+    pub keep_role: Vec<String>,
+    /// drop accounts with a balance lower than this
+    #[clap(long)]
+    pub min_balance: Option<u64>,
+    /// drop accounts whose last known activity (proofs mined, vouches,
+    /// pledges) is before this epoch. Accounts without any activity record
+    /// are kept
+    #[clap(long)]
+    pub inactive_since: Option<u64>,
+    /// cap every balance to this amount
+    #[clap(long)]
+    pub balance_cap: Option<u64>,
+    /// multiply every balance by this factor
+    #[clap(long)]
+    pub balance_scale: Option<f64>,
+}
+
+/// An account changed by the filters
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AffectedAccount {
+    pub account: AccountAddress,
+    /// which filter applied
+    pub action: String,
+    pub balance_before: u64,
+    pub balance_after: u64,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct FilterReport {
+    pub filters: MigrationFilters,
+    pub affected: Vec<AffectedAccount>,
+    pub supply_before: Supply,
+    pub supply_after: Supply,
+}
+
+impl FilterReport {
+    pub fn dropped(&self) -> usize {
+        self.affected
+            .iter()
+            .filter(|a| a.action.starts_with("drop"))
+            .count()
+    }
+
+    pub fn print_summary(&self) {
+        println!(
+            "\naccounts affected by migration filters: {}",
+            self.affected.len()
+        );
+        println!("dropped: {}", self.dropped());
+        println!("balances changed: {}", self.affected.len() - self.dropped());
+        println!(
+            "total supply: {} -> {}",
+            self.supply_before.total, self.supply_after.total
+        );
+        println!(
+            "slow locked: {} -> {}",
+            self.supply_before.slow_locked, self.supply_after.slow_locked
+        );
+    }
+
+    pub fn save(&self, path: &Path) -> anyhow::Result<()> {
+        std::fs::write(path, serde_json::to_string_pretty(self)?).context(format!(
+            "could not write filter report to {}",
+            path.display()
+        ))
+    }
+}
+
+impl MigrationFilters {
+    pub fn is_empty(&self) -> bool {
+        self == &Self::default()
+    }
+
+    /// Apply the filters in place. Dropped accounts become tombstones, like
+    /// the ones in the drop list.
+    pub fn apply(&self, recovery: &mut [LegacyRecoveryV6]) -> anyhow::Result<FilterReport> {
+        let include_only = match &self.include_only {
+            Some(p) => Some(parse_json::read_account_list(p)?),
+            None => None,
+        };
+        let keep_roles = self
+            .keep_role
+            .iter()
+            .map(|r| parse_role(r))
+            .collect::<anyhow::Result<Vec<AccountRole>>>()?;
+        if let Some(f) = self.balance_scale {
+            if !f.is_finite() || f < 0.0 {
+                bail!("balance scale must be a positive number");
+            }
+        }
+
+        let supply_before = supply::populate_supply_stats_from_legacy(recovery)?;
+        let mut affected = vec![];
+
+        for e in recovery.iter_mut() {
+            let Some(account) = e.account else {
+                continue;
+            };
+            if e.role == AccountRole::System || e.role == AccountRole::Drop {
+                continue;
+            }
+            let balance = e.balance.as_ref().map(|b| b.coin).unwrap_or(0);
+
+            let drop_reason = if include_only
+                .as_ref()
+                .map(|l| !l.contains(&account))
+                .unwrap_or(false)
+            {
+                Some("drop: not in include list")
+            } else if !keep_roles.is_empty() && !keep_roles.contains(&e.role) {
+                Some("drop: role")
+            } else if self.min_balance.map(|m| balance < m).unwrap_or(false) {
+                Some("drop: min balance")
+            } else if self
+                .inactive_since
+                .zip(last_active_epoch(e))
+                .map(|(since, last)| last < since)
+                .unwrap_or(false)
+            {
+                Some("drop: inactive")
+            } else {
+                None
+            };
+
+            if let Some(reason) = drop_reason {
+                parse_json::tombstone(e);
+                affected.push(AffectedAccount {
+                    account,
+                    action: reason.to_string(),
+                    balance_before: balance,
+                    balance_after: 0,
+                });
+                continue;
+            }
+
+            let mut new_balance = balance;
+            let mut actions = vec![];
+            if let Some(cap) = self.balance_cap {
+                if new_balance > cap {
+                    new_balance = cap;
+                    actions.push("cap");
+                }
+            }
+            if let Some(f) = self.balance_scale {
+                new_balance = (new_balance as f64 * f) as u64;
+                actions.push("scale");
+            }
+
+            if new_balance != balance {
+                set_balance(e, new_balance);
+                affected.push(AffectedAccount {
+                    account,
+                    action: actions.join(", "),
+                    balance_before: balance,
+                    balance_after: new_balance,
+                });
+            }
+        }
+
+        let supply_after = supply::populate_supply_stats_from_legacy(recovery)?;
+
+        Ok(FilterReport {
+            filters: self.clone(),
+            affected,
+            supply_before,
+            supply_after,
+        })
+    }
+}
+
+/// the latest epoch we know the account did something in.
+/// Note: FeeMaker.epoch is the fees made this epoch, not an epoch number
+fn last_active_epoch(e: &LegacyRecoveryV6) -> Option<u64> {
+    let mined = e.miner_state.as_ref().map(|m| m.latest_epoch_mining);
+    let vouched = e
+        .my_vouches
+        .as_ref()
+        .and_then(|v| v.epoch_vouched.iter().max().copied());
+    let pledged = e
+        .my_pledge
+        .as_ref()
+        .and_then(|p| p.list.iter().map(|p| p.epoch_of_last_deposit).max());
+    mined.max(vouched).max(pledged)
+}
+
+/// change the balance, and scale the slow wallet unlocked amount by the same
+/// ratio, so the locked share of the account doesn't change
+fn set_balance(e: &mut LegacyRecoveryV6, coin: u64) {
+    let Some(b) = e.balance.as_mut() else {
+        return;
+    };
+    let before = b.coin;
+    b.coin = coin;
+    if let Some(s) = e.slow_wallet.as_mut() {
+        s.unlocked = if before == 0 {
+            0
+        } else {
+            (s.unlocked as u128 * coin as u128 / before as u128).min(coin as u128) as u64
+        };
+    }
+}
+
+fn parse_role(s: &str) -> anyhow::Result<AccountRole> {
+    match s.to_lowercase().replace(['-', '_'], "").as_str() {
+        "validator" => Ok(AccountRole::Validator),
+        "operator" => Ok(AccountRole::Operator),
+        "enduser" | "user" => Ok(AccountRole::EndUser),
+        _ => bail!(
+            "unknown role {}, expected one of: validator, operator, end-user",
+            s
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use libra_types::move_resource::{
+        cumulative_deposits::LegacyBalanceResourceV6, wallet::SlowWalletResource,
+    };
+
+    fn single() -> Vec<LegacyRecoveryV6> {
+        let p = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/single.json");
+        parse_json::recovery_file_parse(p).unwrap()
+    }
+
+    #[test]
+    fn no_filters_changes_nothing() {
+        let mut r = single();
+        let report = MigrationFilters::default().apply(&mut r).unwrap();
+        assert!(report.affected.is_empty());
+        assert_eq!(report.supply_before.total, report.supply_after.total);
+    }
+
+    #[test]
+    fn min_balance_drops() {
+        let mut r = single();
+        let filters = MigrationFilters {
+            min_balance: Some(u64::MAX),
+            ..Default::default()
+        };
+        let report = filters.apply(&mut r).unwrap();
+        assert!(report.dropped() > 0);
+        assert!(r
+            .iter()
+            .filter(|e| e.role != AccountRole::System)
+            .all(|e| e.role == AccountRole::Drop));
+        assert!(report.supply_after.total < report.supply_before.total);
+    }
+
+    #[test]
+    fn cap_and_scale() {
+        let mut r = single();
+        let filters = MigrationFilters {
+            balance_cap: Some(1000),
+            balance_scale: Some(0.5),
+            ..Default::default()
+        };
+        filters.apply(&mut r).unwrap();
+        for e in r.iter().filter(|e| e.role != AccountRole::System) {
+            let b = e.balance.as_ref().map(|b| b.coin).unwrap_or(0);
+            assert!(b <= 500);
+            if let Some(s) = &e.slow_wallet {
+                assert!(s.unlocked <= b);
+            }
+        }
+    }
+
+    #[test]
+    fn inactive_since_drops() {
+        let mut r = single();
+        let active: Vec<_> = r
+            .iter()
+            .filter(|e| e.role != AccountRole::System)
+            .filter_map(|e| e.account.zip(last_active_epoch(e)))
+            .collect();
+        assert!(!active.is_empty());
+        let (account, last) = active[0];
+
+        // active in the epoch of the filter
+        let filters = MigrationFilters {
+            inactive_since: Some(last),
+            ..Default::default()
+        };
+        let report = filters.apply(&mut r.clone()).unwrap();
+        assert!(!report.affected.iter().any(|a| a.account == account));
+
+        let filters = MigrationFilters {
+            inactive_since: Some(last + 1),
+            ..Default::default()
+        };
+        let report = filters.apply(&mut r).unwrap();
+        assert!(report
+            .affected
+            .iter()
+            .any(|a| a.account == account && a.action == "drop: inactive"));
+    }
+
+    #[test]
+    fn slow_unlocked_scales_with_balance() {
+        let mut e = LegacyRecoveryV6 {
+            balance: Some(LegacyBalanceResourceV6 { coin: 1000 }),
+            slow_wallet: Some(SlowWalletResource {
+                unlocked: 400,
+                transferred: 0,
+            }),
+            ..Default::default()
+        };
+        set_balance(&mut e, 500);
+        assert_eq!(e.balance.as_ref().unwrap().coin, 500);
+        assert_eq!(e.slow_wallet.as_ref().unwrap().unlocked, 200);
+    }
+
+    #[test]
+    fn roles_parse() {
+        assert_eq!(parse_role("end-user").unwrap(), AccountRole::EndUser);
+        assert!(parse_role("wizard").is_err());
+    }
+}
@@ -43,24 +43,34 @@ struct DropList {
     account: AccountAddress,
 }
 
-/// strip accounts from legacy
-pub fn drop_accounts(r: &mut [LegacyRecoveryV6], drop_file: &Path) -> anyhow::Result<()> {
-    let data = fs::read_to_string(drop_file).expect("Unable to read file");
-    let list: Vec<DropList> = serde_json::from_str(&data).expect("Unable to parse");
-    let mapped: Vec<AccountAddress> = list.into_iter().map(|e| e.account).collect();
+/// read a list of accounts, in the format of the drop list: `[{ "account": "0x.." }]`
+pub fn read_account_list(file: &Path) -> anyhow::Result<Vec<AccountAddress>> {
+    let data = fs::read_to_string(file)?;
+    let list: Vec<DropList> = serde_json::from_str(&data)?;
+    Ok(list.into_iter().map(|e| e.account).collect())
+}
+
+/// replace the account with an empty one, marked to be dropped
+pub fn tombstone(e: &mut LegacyRecoveryV6) {
     let tombstone = [9u8; 32];
     // let auth_key = b"Oh, is it too late now to say sorry?".to_vec();
     // tombstone.copy_from_slice(&auth_key);
+    let mut dead = LegacyRecoveryV6 {
+        ..Default::default()
+    };
+    dead.account = e.account;
+    dead.auth_key = Some(AuthenticationKey::new(tombstone));
+    dead.role = AccountRole::Drop;
+    *e = dead;
+}
+
+/// strip accounts from legacy
+pub fn drop_accounts(r: &mut [LegacyRecoveryV6], drop_file: &Path) -> anyhow::Result<()> {
+    let mapped = read_account_list(drop_file).expect("Unable to parse");
     r.iter_mut().for_each(|e| {
         if let Some(account) = e.account {
             if mapped.contains(&account) {
-                let mut dead = LegacyRecoveryV6 {
-                    ..Default::default()
-                };
-                dead.account = Some(account);
-                dead.auth_key = Some(AuthenticationKey::new(tombstone));
-                dead.role = AccountRole::Drop;
-                *e = dead;
+                tombstone(e);
             }
         }
     });
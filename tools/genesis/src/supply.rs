@@ -1,9 +1,10 @@
 use indicatif::ProgressBar;
-use libra_backwards_compatibility::legacy_recovery_v6::LegacyRecoveryV6;
+use libra_backwards_compatibility::legacy_recovery_v6::{AccountRole, LegacyRecoveryV6};
 use libra_types::ol_progress::OLProgress;
+use serde::{Deserialize, Serialize};
 use std::time::Duration;
 
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Supply {
     pub total: f64,
     pub normal: f64,
@@ -98,3 +99,21 @@ pub fn populate_supply_stats_from_legacy(rec: &[LegacyRecoveryV6]) -> anyhow::Re
     pb.finish_and_clear();
     Ok(supply)
 }
+
+/// The exact supply genesis should mint from the recovery file: the balance
+/// of every migrated account plus the coins escrowed in its pledges.
+/// Genesis validators are paid out of the infra escrow, so they don't add to it.
+pub fn expected_genesis_supply(rec: &[LegacyRecoveryV6]) -> u64 {
+    rec.iter()
+        .filter(|r| r.role != AccountRole::Drop && r.account.is_some() && r.auth_key.is_some())
+        .map(|r| {
+            let balance = r.balance.as_ref().map(|b| b.coin).unwrap_or(0);
+            let escrow: u64 = r
+                .my_pledge
+                .as_ref()
+                .map(|p| p.list.iter().map(|p| p.pledge).sum())
+                .unwrap_or(0);
+            balance + escrow
+        })
+        .sum()
+}
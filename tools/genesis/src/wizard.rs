@@ -2,7 +2,9 @@
 //! instead of using many CLI tools.
 //! genesis wizard
 
-use crate::{genesis_builder, parse_json};
+use crate::{
+    build_manifest::BuildInputs, genesis_builder, genesis_registry::GithubRegistry, parse_json,
+};
 ///////
 // TODO: import from libra
 use crate::genesis_registration;
@@ -144,21 +146,28 @@ impl GenesisWizard {
 
         if ready {
             // Get Legacy Recovery from file
-            let mut legacy_recovery = if let Some(p) = legacy_recovery_path {
+            let mut legacy_recovery = if let Some(p) = legacy_recovery_path.clone() {
                 parse_json::recovery_file_parse(p)?
             } else {
                 vec![]
             };
 
-            genesis_builder::build(
+            let registry = GithubRegistry::new(
                 self.genesis_repo_org.clone(),
                 self.repo_name.clone(),
                 self.github_token.clone(),
+            );
+            genesis_builder::build(
+                Some(&registry),
                 self.data_path.clone(),
                 framework_mrb_path,
                 &mut legacy_recovery,
                 self.chain,
                 None,
+                BuildInputs {
+                    recovery: legacy_recovery_path,
+                    ..Default::default()
+                },
             )?;
 
             for _ in (0..10)
@@ -300,11 +309,15 @@ impl GenesisWizard {
         let pb = ProgressBar::new(1000).with_style(OLProgress::spinner());
         pb.enable_steady_tick(Duration::from_millis(100));
 
-        genesis_registration::register(
-            self.validator_address.clone(),
-            self.github_username.clone(), // Do the registration on the fork.
+        // Do the registration on the fork.
+        let registry = GithubRegistry::new(
+            self.github_username.clone(),
             self.repo_name.clone(),
             self.github_token.clone(),
+        );
+        genesis_registration::register(
+            self.validator_address.clone(),
+            &registry,
             self.data_path.clone(),
         )?;
 
@@ -0,0 +1,44 @@
+//! Tests for the genesis audit report
+mod support;
+use diem_types::chain_id::NamedChain;
+use libra_framework::testing_local_release_bundle;
+use libra_genesis_tools::{
+    audit, genesis::make_recovery_genesis_from_vec_legacy_recovery, parse_json,
+    vm::libra_genesis_default,
+};
+use libra_types::exports::{AccountAddress, ChainId};
+use support::{path_utils::json_path, test_vals};
+
+#[test]
+// a genesis made from the recovery file should have no mismatches in the migrated accounts
+fn test_audit_single() {
+    let genesis_vals = test_vals::get_test_valset(1);
+    let json = json_path().parent().unwrap().join("single.json");
+    let mut user_accounts = parse_json::recovery_file_parse(json).unwrap();
+
+    let gen_tx = make_recovery_genesis_from_vec_legacy_recovery(
+        &mut user_accounts,
+        &genesis_vals,
+        &testing_local_release_bundle(),
+        ChainId::mainnet(),
+        &libra_genesis_default(NamedChain::MAINNET),
+    )
+    .unwrap();
+
+    let vals: Vec<AccountAddress> = genesis_vals.iter().map(|v| v.owner_address).collect();
+    let report = audit::audit_genesis(&mut user_accounts, &gen_tx, &vals).unwrap();
+    report.print_summary();
+
+    assert!(report.accounts_audited > 0);
+    assert!(report.balances.is_empty());
+    assert!(report.slow_wallets.is_empty());
+    assert!(report.ancestry.is_empty());
+    assert!(report.receipts.is_empty());
+    assert!(report.validator_set.is_empty());
+    assert!(report.supply.is_empty());
+
+    // an unknown validator must be reported
+    let report =
+        audit::audit_genesis(&mut user_accounts, &gen_tx, &[AccountAddress::random()]).unwrap();
+    assert_eq!(report.validator_set.len(), 2);
+}
@@ -1,15 +1,20 @@
 //! Helper functions for querying account-related data using the Diem SDK client.
 
+use anyhow::Context;
 use diem_sdk::{
     rest_client::{
-        diem_api_types::{Transaction, VersionedEvent, ViewRequest},
+        diem_api_types::{Transaction, VersionedEvent},
         Client,
     },
     types::{account_address::AccountAddress, validator_config::ValidatorConfig},
 };
+use libra_cached_packages::libra_framework_views as views;
 use libra_types::{
-    move_resource::{gas_coin::SlowWalletBalance, txschedule::TxSchedule},
-    type_extensions::client_ext::{entry_function_id, ClientExt},
+    move_resource::{
+        burn::UserBurnPreferenceResource, fee_maker::FeeMakerResource, gas_coin::SlowWalletBalance,
+        txschedule::TxSchedule,
+    },
+    type_extensions::client_ext::ClientExt,
 };
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
@@ -33,16 +38,67 @@ pub async fn get_account_balance_libra(
     client: &Client,
     account: AccountAddress,
 ) -> anyhow::Result<SlowWalletBalance> {
-    let slow_balance_id = entry_function_id("ol_account", "balance")?;
-    let request = ViewRequest {
-        function: slow_balance_id,
-        type_arguments: vec![],
-        arguments: vec![account.to_string().into()],
-    };
+    let (unlocked, total) = views::ol_account_balance(client, account).await?;
+    Ok(SlowWalletBalance { unlocked, total })
+}
+
+/// An account's burn preference, and where the fees end up
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BurnReport {
+    pub account: AccountAddress,
+    /// fees are recycled to the community, otherwise burned
+    pub send_community: bool,
+    /// the account never set a preference, the default is to burn
+    pub preference_set: bool,
+    /// all fees burned by the network
+    pub network_lifetime_burned: u64,
+    /// all fees recycled by the network
+    pub network_lifetime_recycled: u64,
+    pub fees_this_epoch: u64,
+    /// share of all the fees made this epoch, in basis points
+    pub epoch_fee_share_bps: u64,
+    /// the chain doesn't track how much of these was recycled or burned,
+    /// the preference may have changed over time
+    pub lifetime_fees: u64,
+}
 
-    let res = client.view(&request, None).await?.into_inner();
+/// Reports the burn preference of an account, the network's burn counter,
+/// and the fees the account made.
+pub async fn burn_report(client: &Client, account: AccountAddress) -> anyhow::Result<BurnReport> {
+    let preference = client
+        .get_move_resource::<UserBurnPreferenceResource>(account)
+        .await
+        .ok();
+    let send_community = preference
+        .as_ref()
+        .map(|p| p.send_community)
+        .unwrap_or(false);
+    let (network_lifetime_burned, network_lifetime_recycled) =
+        views::burn_get_lifetime_tracker(client).await?;
+    let fees = client
+        .get_move_resource::<FeeMakerResource>(account)
+        .await
+        .unwrap_or(FeeMakerResource {
+            epoch: 0,
+            lifetime: 0,
+        });
+    let all_fees_this_epoch = views::fee_maker_get_all_fees_made(client).await?;
+    let epoch_fee_share_bps = if all_fees_this_epoch > 0 {
+        ((fees.epoch as u128 * 10_000) / all_fees_this_epoch as u128) as u64
+    } else {
+        0
+    };
 
-    SlowWalletBalance::from_value(res)
+    Ok(BurnReport {
+        account,
+        send_community,
+        preference_set: preference.is_some(),
+        network_lifetime_burned,
+        network_lifetime_recycled,
+        fees_this_epoch: fees.epoch,
+        epoch_fee_share_bps,
+        lifetime_fees: fees.lifetime,
+    })
 }
 
 /// Retrieves the validator configuration for a given account.
@@ -99,15 +155,8 @@ pub async fn is_community_wallet_migrated(
     client: &Client,
     account: AccountAddress,
 ) -> anyhow::Result<serde_json::Value> {
-    let community_wallet_migrated_id = entry_function_id("community_wallet", "qualifies")?;
-    let request = ViewRequest {
-        function: community_wallet_migrated_id,
-        type_arguments: vec![],
-        arguments: vec![account.to_string().into()],
-    };
-
-    let res = client.view(&request, None).await?.into_inner();
-    Ok(json!(res))
+    let qualifies = views::community_wallet_init_qualifies(client, account).await?;
+    Ok(json!([qualifies]))
 }
 
 /// Retrieves signers for the community wallet associated with a given account.
@@ -115,16 +164,12 @@ pub async fn community_wallet_signers(
     client: &Client,
     account: AccountAddress,
 ) -> anyhow::Result<serde_json::Value> {
-    //they are empty for now
-    let community_wallet_migrated_id = entry_function_id("multi_action", "get_authorities")?;
-    let request = ViewRequest {
-        function: community_wallet_migrated_id,
-        type_arguments: vec![],
-        arguments: vec![account.to_string().into()],
-    };
-
-    let res = client.view(&request, None).await?.into_inner();
-    Ok(json!(res))
+    let authorities: Vec<String> = views::multi_action_get_authorities(client, account)
+        .await?
+        .iter()
+        .map(|a| a.to_hex_literal())
+        .collect();
+    Ok(json!([authorities]))
 }
 
 /// Retrieves scheduled transactions for the community wallet associated with a given account.
@@ -132,7 +177,12 @@ pub async fn community_wallet_scheduled_transactions(
     client: &Client,
     account: AccountAddress,
 ) -> anyhow::Result<TxSchedule> {
-    client.get_move_resource::<TxSchedule>(account).await
+    let res = client
+        .get_account_resource(account, "0x1::donor_voice_txs::TxSchedule")
+        .await?
+        .into_inner()
+        .context(format!("no TxSchedule at {}", account))?;
+    Ok(serde_json::from_value(res.data)?)
 }
 
 /// Retrieves all multi_auth actions (pending, approved, expired) for a given multi_auth account.
@@ -155,53 +205,7 @@ pub async fn page_rank_calculate_score(
     client: &Client,
     account: AccountAddress,
 ) -> anyhow::Result<(u64, u64, u64)> {
-    let calculate_score_id = entry_function_id("page_rank_lazy", "calculate_score")?;
-    let request = ViewRequest {
-        function: calculate_score_id,
-        type_arguments: vec![],
-        arguments: vec![account.to_string().into()],
-    };
-
-    let res = client.view(&request, None).await?.into_inner();
-
-    // Parse the tuple response (score, max_depth_reached, accounts_processed)
-    if res.len() != 3 {
-        return Err(anyhow::anyhow!(
-            "Expected 3 values from calculate_score, got {}",
-            res.len()
-        ));
-    }
-
-    // Handle string or numeric responses from the Move VM
-    let score: u64 = match &res[0] {
-        serde_json::Value::String(s) => s.parse()?,
-        serde_json::Value::Number(n) => n
-            .as_u64()
-            .ok_or_else(|| anyhow::anyhow!("Invalid number format for score"))?,
-        _ => return Err(anyhow::anyhow!("Unexpected response type for score")),
-    };
-
-    let max_depth_reached: u64 = match &res[1] {
-        serde_json::Value::String(s) => s.parse()?,
-        serde_json::Value::Number(n) => n
-            .as_u64()
-            .ok_or_else(|| anyhow::anyhow!("Invalid number format for max_depth"))?,
-        _ => return Err(anyhow::anyhow!("Unexpected response type for max_depth")),
-    };
-
-    let accounts_processed: u64 = match &res[2] {
-        serde_json::Value::String(s) => s.parse()?,
-        serde_json::Value::Number(n) => n
-            .as_u64()
-            .ok_or_else(|| anyhow::anyhow!("Invalid number format for accounts_processed"))?,
-        _ => {
-            return Err(anyhow::anyhow!(
-                "Unexpected response type for accounts_processed"
-            ))
-        }
-    };
-
-    Ok((score, max_depth_reached, accounts_processed))
+    views::page_rank_lazy_calculate_score(client, account).await
 }
 
 /// Retrieves the cached page rank trust score for an account.
@@ -210,29 +214,7 @@ pub async fn page_rank_get_cached_score(
     client: &Client,
     account: AccountAddress,
 ) -> anyhow::Result<u64> {
-    let get_cached_score_id = entry_function_id("page_rank_lazy", "get_cached_score")?;
-    let request = ViewRequest {
-        function: get_cached_score_id,
-        type_arguments: vec![],
-        arguments: vec![account.to_string().into()],
-    };
-
-    let res = client.view(&request, None).await?.into_inner();
-
-    // Parse the single u64 response
-    if res.is_empty() {
-        return Err(anyhow::anyhow!("No values returned from get_cached_score"));
-    }
-
-    // Handle both string and numeric responses from the Move VM
-    let score: u64 = match &res[0] {
-        serde_json::Value::String(s) => s.parse()?,
-        serde_json::Value::Number(n) => n
-            .as_u64()
-            .ok_or_else(|| anyhow::anyhow!("Invalid number format"))?,
-        _ => return Err(anyhow::anyhow!("Unexpected response type for cached score")),
-    };
-    Ok(score)
+    views::page_rank_lazy_get_cached_score(client, account).await
 }
 
 /// Calculates the maximum number of vouches a user should be able to give based on their trust score.
@@ -241,31 +223,7 @@ pub async fn vouch_limits_calculate_score_limit(
     client: &Client,
     account: AccountAddress,
 ) -> anyhow::Result<u64> {
-    let calculate_score_limit_id = entry_function_id("vouch_limits", "calculate_score_limit")?;
-    let request = ViewRequest {
-        function: calculate_score_limit_id,
-        type_arguments: vec![],
-        arguments: vec![account.to_string().into()],
-    };
-
-    let res = client.view(&request, None).await?.into_inner();
-
-    // Parse the single u64 response
-    if res.is_empty() {
-        return Err(anyhow::anyhow!(
-            "No values returned from calculate_score_limit"
-        ));
-    }
-
-    // Handle both string and numeric responses from the Move VM
-    let limit: u64 = match &res[0] {
-        serde_json::Value::String(s) => s.parse()?,
-        serde_json::Value::Number(n) => n
-            .as_u64()
-            .ok_or_else(|| anyhow::anyhow!("Invalid number format"))?,
-        _ => return Err(anyhow::anyhow!("Unexpected response type for score limit")),
-    };
-    Ok(limit)
+    views::vouch_limits_calculate_score_limit(client, account).await
 }
 
 /// Returns the number of vouches a user can still give based on system limits.
@@ -275,29 +233,7 @@ pub async fn vouch_limits_get_vouch_limit(
     client: &Client,
     account: AccountAddress,
 ) -> anyhow::Result<u64> {
-    let get_vouch_limit_id = entry_function_id("vouch_limits", "get_vouch_limit")?;
-    let request = ViewRequest {
-        function: get_vouch_limit_id,
-        type_arguments: vec![],
-        arguments: vec![account.to_string().into()],
-    };
-
-    let res = client.view(&request, None).await?.into_inner();
-
-    // Parse the single u64 response
-    if res.is_empty() {
-        return Err(anyhow::anyhow!("No values returned from get_vouch_limit"));
-    }
-
-    // Handle both string and numeric responses from the Move VM
-    let limit: u64 = match &res[0] {
-        serde_json::Value::String(s) => s.parse()?,
-        serde_json::Value::Number(n) => n
-            .as_u64()
-            .ok_or_else(|| anyhow::anyhow!("Invalid number format"))?,
-        _ => return Err(anyhow::anyhow!("Unexpected response type for vouch limit")),
-    };
-    Ok(limit)
+    views::vouch_limits_get_vouch_limit(client, account).await
 }
 
 /// Creates a comprehensive vouch report for an account, combining page rank scores and vouch limits.
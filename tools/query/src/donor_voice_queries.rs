@@ -0,0 +1,228 @@
+//! Queries for Donor Voice accounts, like community wallets.
+
+use crate::{account_queries::community_wallet_scheduled_transactions, chain_queries::get_epoch};
+use diem_sdk::{rest_client::Client, types::account_address::AccountAddress};
+use libra_cached_packages::libra_framework_views as views;
+use libra_types::{
+    move_resource::{donor_voice::RegistryResource, receipts::ReceiptsResource},
+    type_extensions::client_ext::ClientExt,
+};
+use serde::{Deserialize, Serialize};
+
+/// The tally of a liquidation ballot, see donor_voice_governance::get_liquidation_tally.
+/// Percentages are in basis points.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LiquidationTally {
+    pub ballot_id: u64,
+    pub approval_pct: u64,
+    pub turnout_pct: u64,
+    pub threshold_pct: u64,
+    pub epoch_deadline: u64,
+    pub minimum_turnout_pct: u64,
+    pub approved: bool,
+    pub is_complete: bool,
+    pub status_enum: u8,
+    pub ballot_completed: bool,
+}
+
+/// What a donor would get back if the account was liquidated now,
+/// see donor_voice_txs::get_pro_rata
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LiquidationRefund {
+    pub donor: AccountAddress,
+    pub refund: u64,
+}
+
+/// The state of a liquidation vote on a Donor Voice account
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LiquidationReport {
+    pub community_wallet: AccountAddress,
+    /// a pending liquidation ballot, if any
+    pub tally: Option<LiquidationTally>,
+    /// otherwise the funds go to the match index
+    pub refunds_to_donors: bool,
+    pub balance: u64,
+    pub total_donations: u64,
+    pub refunds: Vec<LiquidationRefund>,
+}
+
+/// The pending liquidation tally of a Donor Voice account, and the refund
+/// each donor would receive.
+pub async fn community_wallet_liquidation(
+    client: &Client,
+    community_wallet: AccountAddress,
+) -> anyhow::Result<LiquidationReport> {
+    let (pending, _, _) =
+        views::donor_voice_governance_get_liquidation_ballots(client, community_wallet).await?;
+    let tally = match pending.first() {
+        Some(id) => {
+            let t =
+                views::donor_voice_governance_get_liquidation_tally(client, community_wallet, *id)
+                    .await?;
+            Some(LiquidationTally {
+                ballot_id: *id,
+                approval_pct: t.0,
+                turnout_pct: t.1,
+                threshold_pct: t.2,
+                epoch_deadline: t.3,
+                minimum_turnout_pct: t.4,
+                approved: t.5,
+                is_complete: t.6,
+                status_enum: t.7,
+                ballot_completed: t.8,
+            })
+        }
+        None => None,
+    };
+
+    let refunds_to_donors =
+        !views::donor_voice_txs_is_liquidate_to_match_index(client, community_wallet).await?;
+    let (_, balance) = views::ol_account_balance(client, community_wallet).await?;
+    let total_donations =
+        views::cumulative_deposits_get_cumulative_deposits(client, community_wallet).await?;
+
+    let (donors, amounts) = views::donor_voice_txs_get_pro_rata(client, community_wallet).await?;
+    let refunds = donors
+        .into_iter()
+        .zip(amounts)
+        .map(|(donor, refund)| LiquidationRefund { donor, refund })
+        .collect();
+
+    Ok(LiquidationReport {
+        community_wallet,
+        tally,
+        refunds_to_donors,
+        balance,
+        total_donations,
+        refunds,
+    })
+}
+
+/// A payment scheduled by a Donor Voice account, which donors can still veto
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VetoablePayment {
+    pub tx_id: u64,
+    pub payee: AccountAddress,
+    pub value: u64,
+    pub description: String,
+    /// the epoch the payment executes, unless vetoed
+    pub deadline_epoch: u64,
+    pub epochs_remaining: u64,
+    /// a donor already opened a veto ballot on it
+    pub veto_pending: bool,
+}
+
+/// An open reauthorization ballot
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReauthBallot {
+    pub ballot_id: u64,
+    pub deadline_epoch: u64,
+    pub epochs_remaining: u64,
+}
+
+/// A donor's standing in one Donor Voice account
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DonorStanding {
+    pub community_wallet: AccountAddress,
+    /// cumulative donations, which is also the weight of the donor's votes
+    pub donated: u64,
+    pub last_donation_timestamp: u64,
+    pub last_donation_value: u64,
+    /// share of all the donations the account received, in basis points
+    pub veto_weight_bps: u64,
+    pub vetoable_payments: Vec<VetoablePayment>,
+    pub reauth_ballot: Option<ReauthBallot>,
+}
+
+/// A donor's standing in every Donor Voice account they donated to
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DonorReport {
+    pub donor: AccountAddress,
+    pub epoch: u64,
+    pub total_donated: u64,
+    pub wallets: Vec<DonorStanding>,
+}
+
+/// The Donor Voice accounts a donor gave to, from their receipts, with the
+/// payments they can veto and the reauthorization votes open.
+pub async fn donor_report(client: &Client, donor: AccountAddress) -> anyhow::Result<DonorReport> {
+    let registry = client
+        .get_move_resource::<RegistryResource>(AccountAddress::ONE)
+        .await?;
+    let epoch = get_epoch(client).await?;
+    let mut report = DonorReport {
+        donor,
+        epoch,
+        total_donated: 0,
+        wallets: vec![],
+    };
+    // no receipts, never donated
+    let Ok(receipts) = client.get_move_resource::<ReceiptsResource>(donor).await else {
+        return Ok(report);
+    };
+
+    for (i, dv) in receipts.destination.iter().enumerate() {
+        let donated = receipts.cumulative.get(i).copied().unwrap_or(0);
+        if !registry.list.contains(dv) || donated == 0 {
+            continue;
+        }
+        report.total_donated += donated;
+
+        let total = views::cumulative_deposits_get_cumulative_deposits(client, *dv).await?;
+        let veto_weight_bps = if total > 0 {
+            ((donated as u128 * 10_000) / total as u128) as u64
+        } else {
+            0
+        };
+
+        let mut vetoable_payments = vec![];
+        if let Ok(schedule) = community_wallet_scheduled_transactions(client, *dv).await {
+            for t in schedule.scheduled {
+                let tx_id = t.uid.creation_num.into();
+                let deadline_epoch: u64 = t.deadline.into();
+                vetoable_payments.push(VetoablePayment {
+                    tx_id,
+                    payee: t.tx.payee,
+                    value: t.tx.value.into(),
+                    description: String::from_utf8_lossy(&t.tx.description.0).to_string(),
+                    deadline_epoch,
+                    epochs_remaining: deadline_epoch.saturating_sub(epoch),
+                    veto_pending: views::donor_voice_governance_tx_has_veto_pending(
+                        client, *dv, tx_id,
+                    )
+                    .await
+                    .unwrap_or(false),
+                });
+            }
+        }
+
+        // the governance views abort if the account has no ballots
+        let reauth_ballot = match views::donor_voice_governance_get_reauth_ballots(client, *dv)
+            .await
+            .ok()
+            .and_then(|(pending, _, _)| pending.first().copied())
+        {
+            Some(ballot_id) => {
+                let deadline_epoch =
+                    views::donor_voice_governance_get_reauth_expiry(client, *dv).await?;
+                Some(ReauthBallot {
+                    ballot_id,
+                    deadline_epoch,
+                    epochs_remaining: deadline_epoch.saturating_sub(epoch),
+                })
+            }
+            None => None,
+        };
+
+        report.wallets.push(DonorStanding {
+            community_wallet: *dv,
+            donated,
+            last_donation_timestamp: receipts.last_payment_timestamp.get(i).copied().unwrap_or(0),
+            last_donation_value: receipts.last_payment_value.get(i).copied().unwrap_or(0),
+            veto_weight_bps,
+            vetoable_payments,
+            reauth_ballot,
+        });
+    }
+    Ok(report)
+}
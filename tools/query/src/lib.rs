@@ -1,5 +1,9 @@
 pub mod account_queries;
 pub mod chain_queries;
+pub mod donor_voice_queries;
+pub mod pledge_queries;
 pub mod query_cli;
 pub mod query_type;
 pub mod query_view;
+pub mod slow_wallet_queries;
+pub mod vouch_graph;
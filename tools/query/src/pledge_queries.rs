@@ -0,0 +1,82 @@
+//! Queries for pledge accounts, like the infra escrow.
+
+use diem_sdk::{rest_client::Client, types::account_address::AccountAddress};
+use libra_cached_packages::libra_framework_views as views;
+use libra_types::{
+    move_resource::pledge_account::MyPledgesResource, type_extensions::client_ext::ClientExt,
+};
+use serde::{Deserialize, Serialize};
+
+/// One pledge account of a user
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PledgeView {
+    pub beneficiary: AccountAddress,
+    /// the purpose in the beneficiary's policy
+    pub purpose: Option<String>,
+    /// coins still pledged
+    pub amount: u64,
+    pub epoch_of_last_deposit: u64,
+    pub lifetime_pledged: u64,
+    pub lifetime_withdrawn: u64,
+    /// all the coins pledged to the beneficiary
+    pub beneficiary_available: u64,
+    /// the pledgers voted to revoke the policy
+    pub revoked: bool,
+}
+
+/// All the pledges of a user
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PledgesReport {
+    pub account: AccountAddress,
+    pub total_pledged: u64,
+    pub pledges: Vec<PledgeView>,
+}
+
+/// the purpose of a beneficiary policy, if it has one
+async fn policy_purpose(client: &Client, beneficiary: AccountAddress) -> Option<String> {
+    let res = client
+        .get_account_resource(beneficiary, "0x1::pledge_accounts::BeneficiaryPolicy")
+        .await
+        .ok()?
+        .into_inner()?;
+    let hex_purpose = res.data.get("purpose")?.as_str()?;
+    let bytes = hex::decode(hex_purpose.trim_start_matches("0x")).ok()?;
+    Some(String::from_utf8_lossy(&bytes).to_string())
+}
+
+/// The pledge accounts of a user, and the state of each beneficiary
+pub async fn pledges_report(
+    client: &Client,
+    account: AccountAddress,
+) -> anyhow::Result<PledgesReport> {
+    let mut report = PledgesReport {
+        account,
+        total_pledged: 0,
+        pledges: vec![],
+    };
+    // never pledged
+    let Ok(my_pledges) = client.get_move_resource::<MyPledgesResource>(account).await else {
+        return Ok(report);
+    };
+
+    for p in my_pledges.list {
+        let beneficiary = p.address_of_beneficiary;
+        let (revoked, _) = views::pledge_accounts_get_revoke_vote(client, beneficiary).await?;
+        report.total_pledged += p.amount;
+        report.pledges.push(PledgeView {
+            beneficiary,
+            purpose: policy_purpose(client, beneficiary).await,
+            amount: p.amount,
+            epoch_of_last_deposit: p.epoch_of_last_deposit,
+            lifetime_pledged: p.lifetime_pledged,
+            lifetime_withdrawn: p.lifetime_withdrawn,
+            beneficiary_available: views::pledge_accounts_get_available_to_beneficiary(
+                client,
+                beneficiary,
+            )
+            .await?,
+            revoked,
+        });
+    }
+    Ok(report)
+}
@@ -2,10 +2,10 @@ use std::path::PathBuf;
 
 use crate::query_type::QueryType;
 
-use anyhow::Result;
+use anyhow::{bail, Result};
 use clap::Parser;
 use libra_types::{
-    core_types::app_cfg::AppCfg, exports::Client, type_extensions::client_ext::ClientExt,
+    core_types::app_cfg::AppCfg, exports::Client, type_extensions::failover_client::FailoverClient,
 };
 use serde_json;
 use url::Url;
@@ -26,6 +26,11 @@ pub struct QueryCli {
     /// Otherwise will default to what is in the config file
     #[clap(short, long)]
     pub url: Option<Url>,
+
+    /// optional, send the query to this many nodes of the playlist, and
+    /// flag any disagreement in ledger versions or values
+    #[clap(short, long)]
+    pub quorum: Option<usize>,
 }
 
 impl QueryCli {
@@ -37,18 +42,52 @@ impl QueryCli {
         // The user can set an alternative path the the config,
         // which is useful in testnets.
 
+        if let Some(res) = self.subcommand.query_offline().await? {
+            println!("{}", serde_json::to_string_pretty(&res)?);
+            return Ok(());
+        }
+
         // Initialize client
-        let client = if let Some(u) = &self.url {
-            Client::new(u.clone())
-        } else if let Some(p) = &self.config_path {
-            let app_cfg = AppCfg::load(Some(p.to_owned()))?;
-            let (c, _) = Client::from_libra_config(&app_cfg, None).await?;
-            c
+        if let Some(u) = &self.url {
+            if self.quorum.is_some() {
+                bail!("quorum reads use the nodes of the playlist, don't set --url");
+            }
+            let res = self
+                .subcommand
+                .query_to_json(&Client::new(u.clone()))
+                .await?;
+            println!("{}", serde_json::to_string_pretty(&res)?);
+            return Ok(());
+        }
+
+        // otherwise use every node in the playlist, failing over if one is down
+        let (app_cfg, notes) = AppCfg::load_with_notes(self.config_path.clone())?;
+        if let Some(n) = notes {
+            // stdout is the query result
+            eprintln!("{}", n);
+        }
+        let failover = FailoverClient::from_app_cfg(&app_cfg, None)?;
+        let query = &self.subcommand;
+
+        let res = if let Some(n) = self.quorum {
+            let report = failover
+                .quorum_read(n, |c| async move { query.query_to_json(&c).await })
+                .await?;
+            if !report.is_consistent() {
+                eprintln!("WARN: nodes disagree:");
+                for d in &report.disagreements {
+                    eprintln!("  {}", d);
+                }
+            }
+            match report.majority {
+                Some(v) => v,
+                None => bail!("no majority answer from {} nodes", report.responses.len()),
+            }
         } else {
-            Client::default().await?
+            failover
+                .run(|c| async move { query.query_to_json(&c).await })
+                .await?
         };
-
-        let res = self.subcommand.query_to_json(&client).await?;
         let pretty_json = serde_json::to_string_pretty(&res)?;
         println!("{}", pretty_json);
 
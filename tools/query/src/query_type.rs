@@ -1,11 +1,15 @@
 use crate::{
     account_queries::{
-        account_vouch_report, community_wallet_scheduled_transactions, community_wallet_signers,
-        get_account_balance_libra, get_events, get_transactions, get_val_config,
-        is_community_wallet_migrated,
+        account_vouch_report, burn_report, community_wallet_scheduled_transactions,
+        community_wallet_signers, get_account_balance_libra, get_events, get_transactions,
+        get_val_config, is_community_wallet_migrated,
     },
     chain_queries::{get_epoch, get_height},
+    donor_voice_queries::{community_wallet_liquidation, donor_report},
+    pledge_queries::pledges_report,
     query_view::get_view,
+    slow_wallet_queries::slow_wallet_projection,
+    vouch_graph::VouchGraph,
 };
 use anyhow::{bail, Context, Result};
 use diem_api_types::Transaction;
@@ -14,6 +18,7 @@ use diem_sdk::{rest_client::Client, types::account_address::AccountAddress};
 use indoc::indoc;
 use libra_types::{exports::AuthenticationKey, type_extensions::client_ext::ClientExt};
 use serde_json::json;
+use std::path::PathBuf;
 
 #[derive(Debug, clap::Subcommand)]
 pub enum QueryType {
@@ -123,6 +128,37 @@ pub enum QueryType {
         /// account to query txs of
         account: AccountAddress,
     },
+    /// Liquidation tally of the community wallet, and the refund each donor would receive
+    ComWalletLiquidation {
+        /// the community wallet account
+        account: AccountAddress,
+    },
+    /// A donor's standing in the Donor Voice accounts they donated to
+    Donor {
+        /// the donor account
+        account: AccountAddress,
+    },
+    /// Burn preference of an account, the network burn counter, and the fees the account made
+    Burn {
+        /// account to query the burn preference of
+        account: AccountAddress,
+    },
+    /// When the locked coins of a slow wallet unlock, and what can be transferred now
+    SlowWallet {
+        /// the slow wallet account
+        account: AccountAddress,
+        #[clap(long, default_value = "7")]
+        /// epochs between each step of the schedule
+        every: u64,
+        #[clap(long, default_value = "12")]
+        /// maximum number of steps in the schedule
+        limit: usize,
+    },
+    /// The pledge accounts of a user, like pledges to the infra escrow
+    Pledges {
+        /// the pledging account
+        account: AccountAddress,
+    },
     /// Display all account structs
     Annotate { account: AccountAddress },
     /// Generate a comprehensive vouch report showing page rank scores and vouch limits
@@ -130,9 +166,77 @@ pub enum QueryType {
         /// account to generate vouch report for
         account: AccountAddress,
     },
+    /// Export the web of trust, and recompute the trust scores offline to find stale cached scores
+    VouchGraph {
+        #[clap(short, long)]
+        /// also crawl from these accounts, besides the roots of trust
+        account: Vec<AccountAddress>,
+        #[clap(long, default_value = "1000")]
+        /// stop crawling the node after this many accounts
+        max_accounts: usize,
+        #[clap(long)]
+        /// read the graph from the state.manifest of a snapshot instead of a node
+        snapshot: Option<PathBuf>,
+        #[clap(long)]
+        /// score with these roots of trust instead of the ones in the registry
+        root: Vec<AccountAddress>,
+        #[clap(long)]
+        /// also write the graph as GraphML to this file
+        graphml: Option<PathBuf>,
+        #[clap(long)]
+        /// only list the accounts with a stale cached score
+        stale_only: bool,
+    },
 }
 
 impl QueryType {
+    /// queries which don't need a node, like reading a snapshot
+    pub async fn query_offline(&self) -> Result<Option<serde_json::Value>> {
+        match self {
+            QueryType::VouchGraph {
+                snapshot: Some(_), ..
+            } => Ok(Some(self.vouch_graph(None).await?)),
+            _ => Ok(None),
+        }
+    }
+
+    async fn vouch_graph(&self, client: Option<&Client>) -> Result<serde_json::Value> {
+        let QueryType::VouchGraph {
+            account,
+            max_accounts,
+            snapshot,
+            root,
+            graphml,
+            stale_only,
+        } = self
+        else {
+            bail!("not a vouch graph query");
+        };
+
+        let mut graph = match (snapshot, client) {
+            (Some(p), _) => VouchGraph::from_snapshot(p).await?,
+            (None, Some(c)) => {
+                let seeds: Vec<AccountAddress> = account.iter().chain(root).cloned().collect();
+                VouchGraph::from_node(c, &seeds, *max_accounts).await?
+            }
+            (None, None) => bail!("needs a node or a snapshot to read the graph from"),
+        };
+        if !root.is_empty() {
+            graph.roots = root.clone();
+        }
+
+        let report = graph.report();
+        if let Some(p) = graphml {
+            std::fs::write(p, report.to_graphml())
+                .context(format!("could not write graph to {}", p.display()))?;
+        }
+        if *stale_only {
+            Ok(json!({ "stale": report.stale }))
+        } else {
+            Ok(json!(report))
+        }
+    }
+
     pub async fn query_to_json(&self, client: &Client) -> Result<serde_json::Value> {
         match self {
             QueryType::Balance { account } => {
@@ -240,6 +344,30 @@ impl QueryType {
                 let _res = community_wallet_scheduled_transactions(client, *account).await?;
                 Ok(json!({ "pending_transactions": "None" }))
             }
+            QueryType::ComWalletLiquidation { account } => {
+                let res = community_wallet_liquidation(client, *account).await?;
+                Ok(json!(res))
+            }
+            QueryType::Donor { account } => {
+                let res = donor_report(client, *account).await?;
+                Ok(json!(res))
+            }
+            QueryType::Burn { account } => {
+                let res = burn_report(client, *account).await?;
+                Ok(json!(res))
+            }
+            QueryType::SlowWallet {
+                account,
+                every,
+                limit,
+            } => {
+                let res = slow_wallet_projection(client, *account, *every, *limit).await?;
+                Ok(json!(res))
+            }
+            QueryType::Pledges { account } => {
+                let res = pledges_report(client, *account).await?;
+                Ok(json!(res))
+            }
             QueryType::Annotate { account } => {
                 let dbgger = DiemDebugger::rest_client(client.clone())?;
                 let version = dbgger.get_latest_version().await?;
@@ -258,6 +386,7 @@ impl QueryType {
                 // Return the data as JSON
                 Ok(json!(report_data))
             }
+            QueryType::VouchGraph { .. } => self.vouch_graph(Some(client)).await,
             _ => {
                 bail!(
                     "Not implemented for type: {:?}\n Ground control to Major Tom.",
@@ -0,0 +1,177 @@
+//! Slow wallet queries, and a projection of when the locked coins unlock.
+
+use crate::chain_queries::get_epoch;
+use anyhow::Context;
+use diem_sdk::{rest_client::Client, types::account_address::AccountAddress};
+use libra_cached_packages::libra_framework_views as views;
+use serde::{Deserialize, Serialize};
+
+/// The unlocked balance of the account at the start of an epoch
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UnlockStep {
+    pub epoch: u64,
+    /// estimated time of the epoch boundary, in seconds
+    pub timestamp: u64,
+    /// RFC 3339 date of the epoch boundary
+    pub date: String,
+    pub unlocked: u64,
+    pub locked: u64,
+}
+
+/// When the locked coins of a slow wallet will be liquid
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SlowWalletProjection {
+    pub account: AccountAddress,
+    pub is_slow: bool,
+    /// slow wallets only unlock once the account is v8 authorized
+    pub v8_authorized: bool,
+    pub total: u64,
+    pub unlocked: u64,
+    pub locked: u64,
+    /// lifetime transfers of unlocked coins
+    pub transferred: u64,
+    /// what the account can transfer now
+    pub transfer_limit: u64,
+    /// coins unlocked at every epoch boundary
+    pub drip_per_epoch: u64,
+    pub epoch: u64,
+    pub epoch_interval_secs: u64,
+    /// None if the account is not unlocking
+    pub fully_unlocked_epoch: Option<u64>,
+    pub fully_unlocked_date: Option<String>,
+    pub schedule: Vec<UnlockStep>,
+}
+
+fn to_date(timestamp: u64) -> String {
+    chrono::DateTime::from_timestamp(timestamp as i64, 0)
+        .map(|d| d.to_rfc3339())
+        .unwrap_or_default()
+}
+
+/// The unlocked balance after `epochs` boundaries, same as
+/// slow_wallet::slow_wallet_epoch_drip, assuming no transfers in or out.
+pub fn unlocked_after(unlocked: u64, total: u64, drip: u64, epochs: u64) -> u64 {
+    let next = unlocked as u128 + drip as u128 * epochs as u128;
+    next.min(total as u128) as u64
+}
+
+/// The number of epoch boundaries until the whole balance is unlocked
+pub fn epochs_to_unlock(unlocked: u64, total: u64, drip: u64) -> Option<u64> {
+    if unlocked >= total {
+        return Some(0);
+    }
+    if drip == 0 {
+        return None;
+    }
+    Some((total - unlocked).div_ceil(drip))
+}
+
+/// Project the unlocks at every `every` epochs, up to `limit` steps or
+/// until the whole balance is unlocked.
+/// `epoch_start` is the time the current epoch started, in seconds.
+#[allow(clippy::too_many_arguments)]
+pub fn project_unlocks(
+    unlocked: u64,
+    total: u64,
+    drip: u64,
+    epoch: u64,
+    epoch_start: u64,
+    interval_secs: u64,
+    every: u64,
+    limit: usize,
+) -> Vec<UnlockStep> {
+    let every = every.max(1);
+    let mut schedule = vec![];
+    let Some(last) = epochs_to_unlock(unlocked, total, drip) else {
+        return schedule;
+    };
+    let mut n = every.min(last);
+    while n > 0 && schedule.len() < limit {
+        let now_unlocked = unlocked_after(unlocked, total, drip, n);
+        let timestamp = epoch_start + interval_secs * n;
+        schedule.push(UnlockStep {
+            epoch: epoch + n,
+            timestamp,
+            date: to_date(timestamp),
+            unlocked: now_unlocked,
+            locked: total - now_unlocked,
+        });
+        if n == last {
+            break;
+        }
+        n = (n + every).min(last);
+    }
+    schedule
+}
+
+/// the time the current epoch started, in seconds
+async fn epoch_start_secs(client: &Client) -> anyhow::Result<u64> {
+    let res = client
+        .get_account_resource(AccountAddress::ONE, "0x1::reconfiguration::Configuration")
+        .await?
+        .into_inner()
+        .context("no reconfiguration::Configuration at 0x1")?;
+    let usecs: u64 = res
+        .data
+        .get("last_reconfiguration_time")
+        .and_then(|v| v.as_str())
+        .context("no last_reconfiguration_time in reconfiguration::Configuration")?
+        .parse()?;
+    Ok(usecs / 1_000_000)
+}
+
+/// Project the unlock schedule of a slow wallet, with the transfer limit
+/// available now.
+pub async fn slow_wallet_projection(
+    client: &Client,
+    account: AccountAddress,
+    every: u64,
+    limit: usize,
+) -> anyhow::Result<SlowWalletProjection> {
+    let is_slow = views::slow_wallet_is_slow(client, account).await?;
+    let v8_authorized = views::reauthorization_is_v8_authorized(client, account).await?;
+    let (unlocked, total) = views::ol_account_balance(client, account).await?;
+    let transferred = views::slow_wallet_transferred_amount(client, account).await?;
+    let epoch = get_epoch(client).await?;
+    let epoch_interval_secs = views::block_get_epoch_interval_secs(client).await?;
+    let epoch_start = epoch_start_secs(client).await?;
+
+    // a normal account has nothing locked, and an unauthorized slow wallet
+    // doesn't unlock until it migrates
+    let drip_per_epoch = if is_slow && v8_authorized {
+        views::sacred_cows_get_slow_drip_const(client).await?
+    } else {
+        0
+    };
+    let unlocked = unlocked.min(total);
+
+    let fully_unlocked_epoch = epochs_to_unlock(unlocked, total, drip_per_epoch);
+    let fully_unlocked_date =
+        fully_unlocked_epoch.map(|n| to_date(epoch_start + epoch_interval_secs * n));
+
+    Ok(SlowWalletProjection {
+        account,
+        is_slow,
+        v8_authorized,
+        total,
+        unlocked,
+        locked: total - unlocked,
+        transferred,
+        transfer_limit: unlocked,
+        drip_per_epoch,
+        epoch,
+        epoch_interval_secs,
+        fully_unlocked_epoch: fully_unlocked_epoch.map(|n| epoch + n),
+        fully_unlocked_date,
+        schedule: project_unlocks(
+            unlocked,
+            total,
+            drip_per_epoch,
+            epoch,
+            epoch_start,
+            epoch_interval_secs,
+            every,
+            limit,
+        ),
+    })
+}
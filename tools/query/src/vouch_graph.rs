@@ -0,0 +1,408 @@
+//! Export the web of trust, and recompute the trust scores offline.
+//! The graph is crawled from a node, or read from a state snapshot. The
+//! scores use the same walk as page_rank_lazy.move, so any account whose
+//! cached score on chain differs from the offline one is stale.
+
+use anyhow::{bail, Context, Result};
+use diem_sdk::{rest_client::Client, types::account_address::AccountAddress};
+use diem_types::account_view::AccountView;
+use libra_cached_packages::libra_framework_views as views;
+use libra_storage::read_snapshot::{accounts_from_snapshot_backup, load_snapshot_manifest};
+use libra_types::move_resource::vouch::{
+    GivenVouchesResource, MyVouchesResource, ReceivedVouchesResource, RootOfTrustResource,
+    UserTrustRecordResource,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, BTreeSet, VecDeque},
+    fmt::Write,
+    path::Path,
+};
+
+/// see MAX_VOUCH_SCORE in page_rank_lazy.move
+pub const MAX_VOUCH_SCORE: u64 = 100_000;
+/// see MAX_PROCESSED_ADDRESSES in page_rank_lazy.move
+pub const MAX_PROCESSED_ADDRESSES: u64 = 10_000;
+/// see MAX_PATH_DEPTH in page_rank_lazy.move
+pub const MAX_PATH_DEPTH: u64 = 4;
+
+/// An account of the web of trust
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct VouchNode {
+    /// has the vouch structs, otherwise it is a dead end for the walk
+    pub is_init: bool,
+    /// who vouched for this account
+    pub received: Vec<AccountAddress>,
+    /// who this account vouched for
+    pub given: Vec<AccountAddress>,
+    /// the score cached on chain, if the account has a trust record
+    pub cached_score: Option<u64>,
+    /// the chain already knows the cached score is stale
+    pub marked_stale: Option<bool>,
+}
+
+/// The score of an account, computed offline
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OfflineScore {
+    pub score: u64,
+    pub max_depth_reached: u64,
+    pub accounts_processed: u64,
+    /// the walk reached accounts which are not in the graph, and counted
+    /// them as dead ends
+    pub left_graph: bool,
+}
+
+/// The web of trust, by account
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct VouchGraph {
+    pub roots: Vec<AccountAddress>,
+    pub nodes: BTreeMap<AccountAddress, VouchNode>,
+    /// the crawl stopped before reaching every account
+    pub truncated: bool,
+}
+
+/// An account in the report, with its offline score
+#[derive(Debug, Serialize, Deserialize)]
+pub struct VouchGraphAccount {
+    pub account: AccountAddress,
+    pub received: Vec<AccountAddress>,
+    pub given: Vec<AccountAddress>,
+    pub cached_score: Option<u64>,
+    pub offline: Option<OfflineScore>,
+    /// the cached score is out of date
+    pub stale: bool,
+}
+
+/// The graph with the offline scores
+#[derive(Debug, Serialize, Deserialize)]
+pub struct VouchGraphReport {
+    pub roots: Vec<AccountAddress>,
+    pub truncated: bool,
+    pub accounts: Vec<VouchGraphAccount>,
+    /// accounts whose cached score is stale
+    pub stale: Vec<AccountAddress>,
+}
+
+impl VouchGraph {
+    /// Crawl the graph from a node, starting at the roots of trust and the
+    /// seed accounts, following vouches in both directions.
+    pub async fn from_node(
+        client: &Client,
+        seeds: &[AccountAddress],
+        max_accounts: usize,
+    ) -> Result<Self> {
+        let roots =
+            views::root_of_trust_get_current_roots_at_registry(client, AccountAddress::ONE).await?;
+        let mut graph = VouchGraph {
+            roots: roots.clone(),
+            ..Default::default()
+        };
+
+        let mut queue: VecDeque<AccountAddress> = roots.into_iter().chain(seeds.to_vec()).collect();
+        let mut seen: BTreeSet<AccountAddress> = queue.iter().cloned().collect();
+        while let Some(addr) = queue.pop_front() {
+            if graph.nodes.len() >= max_accounts {
+                graph.truncated = true;
+                break;
+            }
+            let node = fetch_node(client, addr).await?;
+            for next in node.received.iter().chain(node.given.iter()) {
+                if seen.insert(*next) {
+                    queue.push_back(*next);
+                }
+            }
+            graph.nodes.insert(addr, node);
+        }
+        Ok(graph)
+    }
+
+    /// Read the graph from the accounts of a state snapshot.
+    /// Snapshots from before the vouch upgrade only have MyVouches, the
+    /// given vouches are then derived from the received ones.
+    pub async fn from_snapshot(manifest_path: &Path) -> Result<Self> {
+        let manifest = load_snapshot_manifest(manifest_path)?;
+        let archive_path = manifest_path
+            .parent()
+            .context("the manifest should be in the snapshot folder")?;
+        let accounts = accounts_from_snapshot_backup(manifest, archive_path).await?;
+
+        let mut graph = VouchGraph::default();
+        let mut legacy = false;
+        for state in &accounts {
+            let Some(addr) = state.get_account_address()? else {
+                continue;
+            };
+            if addr == AccountAddress::ONE {
+                if let Some(r) = state.get_move_resource::<RootOfTrustResource>()? {
+                    graph.roots = r.roots;
+                }
+            }
+
+            let received = state.get_move_resource::<ReceivedVouchesResource>()?;
+            let given = state.get_move_resource::<GivenVouchesResource>()?;
+            let my_vouches = state.get_move_resource::<MyVouchesResource>()?;
+            let record = state.get_move_resource::<UserTrustRecordResource>()?;
+
+            let node = match (received, given, my_vouches) {
+                (Some(r), Some(g), _) => VouchNode {
+                    is_init: true,
+                    received: r.incoming_vouches,
+                    given: g.outgoing_vouches,
+                    ..Default::default()
+                },
+                (None, None, Some(m)) => {
+                    legacy = true;
+                    VouchNode {
+                        is_init: true,
+                        received: m.my_buddies,
+                        ..Default::default()
+                    }
+                }
+                (r, g, _) => {
+                    if r.is_none() && g.is_none() && record.is_none() {
+                        continue;
+                    }
+                    VouchNode {
+                        received: r.map(|r| r.incoming_vouches).unwrap_or_default(),
+                        given: g.map(|g| g.outgoing_vouches).unwrap_or_default(),
+                        ..Default::default()
+                    }
+                }
+            };
+            let node = VouchNode {
+                cached_score: record.as_ref().map(|r| r.cached_score),
+                marked_stale: record.as_ref().map(|r| r.is_stale),
+                ..node
+            };
+            graph.nodes.insert(addr, node);
+        }
+
+        if legacy {
+            graph.derive_given();
+        }
+        Ok(graph)
+    }
+
+    /// fill in the given vouches from the received ones
+    fn derive_given(&mut self) {
+        let edges: Vec<(AccountAddress, AccountAddress)> = self
+            .nodes
+            .iter()
+            .flat_map(|(to, n)| n.received.iter().map(move |from| (*from, *to)))
+            .collect();
+        for (from, to) in edges {
+            let node = self.nodes.entry(from).or_default();
+            if !node.given.contains(&to) {
+                node.given.push(to);
+            }
+        }
+    }
+
+    /// The trust score of an account, as `page_rank_lazy::calculate_score`
+    /// would compute it. Errors where the walk would abort on chain.
+    pub fn score(&self, addr: &AccountAddress, max_depth: u64) -> Result<OfflineScore> {
+        let mut processed_count = 0;
+        let mut max_depth_reached = 0;
+        let mut left_graph = false;
+        let score = self.walk_backwards(
+            addr,
+            &mut vec![],
+            2 * MAX_VOUCH_SCORE,
+            0,
+            &mut processed_count,
+            &mut max_depth_reached,
+            &mut left_graph,
+            max_depth,
+        )?;
+        Ok(OfflineScore {
+            score,
+            max_depth_reached,
+            accounts_processed: processed_count,
+            left_graph,
+        })
+    }
+
+    /// see walk_backwards_from_target_with_stats in page_rank_lazy.move
+    #[allow(clippy::too_many_arguments)]
+    fn walk_backwards(
+        &self,
+        current: &AccountAddress,
+        visited: &mut Vec<AccountAddress>,
+        current_power: u64,
+        current_depth: u64,
+        processed_count: &mut u64,
+        max_depth_reached: &mut u64,
+        left_graph: &mut bool,
+        max_depth: u64,
+    ) -> Result<u64> {
+        if current_depth > *max_depth_reached {
+            *max_depth_reached = current_depth;
+        }
+
+        if current_depth >= max_depth || visited.contains(current) {
+            return Ok(0);
+        }
+        let Some(node) = self.nodes.get(current) else {
+            *left_graph = true;
+            return Ok(0);
+        };
+        if !node.is_init {
+            return Ok(0);
+        }
+        if current_power < 2 {
+            return Ok(0);
+        }
+        if self.roots.contains(current) && current_depth > 0 {
+            return Ok(current_power);
+        }
+
+        if *processed_count >= MAX_PROCESSED_ADDRESSES {
+            bail!(
+                "the walk would abort on chain, over {MAX_PROCESSED_ADDRESSES} accounts processed"
+            );
+        }
+        *processed_count += 1;
+
+        if node.received.is_empty() {
+            return Ok(0);
+        }
+        visited.push(*current);
+
+        let mut total_score = 0;
+        for neighbor in &node.received {
+            if *processed_count >= MAX_PROCESSED_ADDRESSES {
+                bail!("the walk would abort on chain, over {MAX_PROCESSED_ADDRESSES} accounts processed");
+            }
+            if !visited.contains(neighbor) {
+                // each branch gets its own copy of the visited path
+                let mut visited_copy = visited.clone();
+                total_score += self.walk_backwards(
+                    neighbor,
+                    &mut visited_copy,
+                    current_power / 2,
+                    current_depth + 1,
+                    processed_count,
+                    max_depth_reached,
+                    left_graph,
+                    max_depth,
+                )?;
+            }
+        }
+        Ok(total_score)
+    }
+
+    /// Score every account, and compare to the cached scores
+    pub fn report(&self) -> VouchGraphReport {
+        let mut accounts = vec![];
+        let mut stale = vec![];
+        for (addr, node) in &self.nodes {
+            // a walk which would abort on chain can't refresh the cache either
+            let offline = self.score(addr, MAX_PATH_DEPTH).ok();
+            let is_stale = match (&offline, node.cached_score) {
+                (Some(o), Some(cached)) => {
+                    // in a truncated crawl the missing accounts aren't dead ends,
+                    // so the offline score is only comparable if the walk
+                    // stayed inside the graph
+                    let comparable = !(self.truncated && o.left_graph);
+                    node.marked_stale.unwrap_or(false) || (comparable && o.score != cached)
+                }
+                _ => false,
+            };
+            if is_stale {
+                stale.push(*addr);
+            }
+            accounts.push(VouchGraphAccount {
+                account: *addr,
+                received: node.received.clone(),
+                given: node.given.clone(),
+                cached_score: node.cached_score,
+                offline,
+                stale: is_stale,
+            });
+        }
+        VouchGraphReport {
+            roots: self.roots.clone(),
+            truncated: self.truncated,
+            accounts,
+            stale,
+        }
+    }
+}
+
+impl VouchGraphReport {
+    /// GraphML of the graph, an edge goes from the voucher to the vouchee
+    pub fn to_graphml(&self) -> String {
+        let mut out = String::new();
+        out.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        out.push_str("<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">\n");
+        for (id, ty) in [
+            ("root", "boolean"),
+            ("cached_score", "long"),
+            ("offline_score", "long"),
+            ("stale", "boolean"),
+        ] {
+            let _ = writeln!(
+                out,
+                "  <key id=\"{id}\" for=\"node\" attr.name=\"{id}\" attr.type=\"{ty}\"/>"
+            );
+        }
+        out.push_str("  <graph id=\"vouch\" edgedefault=\"directed\">\n");
+        for a in &self.accounts {
+            let _ = writeln!(out, "    <node id=\"{}\">", a.account.to_hex_literal());
+            let _ = writeln!(
+                out,
+                "      <data key=\"root\">{}</data>",
+                self.roots.contains(&a.account)
+            );
+            if let Some(c) = a.cached_score {
+                let _ = writeln!(out, "      <data key=\"cached_score\">{c}</data>");
+            }
+            if let Some(o) = &a.offline {
+                let _ = writeln!(out, "      <data key=\"offline_score\">{}</data>", o.score);
+            }
+            let _ = writeln!(out, "      <data key=\"stale\">{}</data>", a.stale);
+            out.push_str("    </node>\n");
+        }
+        for a in &self.accounts {
+            for to in &a.given {
+                let _ = writeln!(
+                    out,
+                    "    <edge source=\"{}\" target=\"{}\"/>",
+                    a.account.to_hex_literal(),
+                    to.to_hex_literal()
+                );
+            }
+        }
+        out.push_str("  </graph>\n</graphml>\n");
+        out
+    }
+}
+
+async fn fetch_node(client: &Client, addr: AccountAddress) -> Result<VouchNode> {
+    let is_init = views::vouch_is_init(client, addr).await?;
+    let (received, _) = views::vouch_get_received_vouches(client, addr).await?;
+    let given = if is_init {
+        views::vouch_get_given_vouches(client, addr).await?.0
+    } else {
+        vec![]
+    };
+    // views abort when there is no trust record
+    let cached_score = views::page_rank_lazy_get_cached_score(client, addr)
+        .await
+        .ok();
+    let marked_stale = match cached_score {
+        Some(_) => Some(
+            views::page_rank_lazy_is_stale(client, addr)
+                .await
+                .context("could not read the trust record")?,
+        ),
+        None => None,
+    };
+    Ok(VouchNode {
+        is_init,
+        received,
+        given,
+        cached_score,
+        marked_stale,
+    })
+}
@@ -0,0 +1,29 @@
+use libra_query::slow_wallet_queries::{epochs_to_unlock, project_unlocks, unlocked_after};
+
+#[test]
+fn unlocks_are_capped_by_the_balance() {
+    assert_eq!(unlocked_after(10, 100, 25, 1), 35);
+    assert_eq!(unlocked_after(10, 100, 25, 4), 100);
+    assert_eq!(unlocked_after(0, u64::MAX, u64::MAX, 3), u64::MAX);
+
+    assert_eq!(epochs_to_unlock(10, 100, 25), Some(4));
+    assert_eq!(epochs_to_unlock(100, 100, 25), Some(0));
+    // not unlocking
+    assert_eq!(epochs_to_unlock(10, 100, 0), None);
+}
+
+#[test]
+fn schedule_ends_when_fully_unlocked() {
+    let day = 86_400;
+    let schedule = project_unlocks(10, 100, 25, 5, 1_700_000_000, day, 3, 12);
+    let epochs: Vec<u64> = schedule.iter().map(|s| s.epoch).collect();
+    // every 3 epochs, and the last step when all is unlocked
+    assert_eq!(epochs, vec![8, 9]);
+    assert_eq!(schedule[0].unlocked, 85);
+    assert_eq!(schedule[0].timestamp, 1_700_000_000 + 3 * day);
+    assert_eq!(schedule[1].locked, 0);
+    assert_eq!(schedule[1].date, "2023-11-18T22:13:20+00:00");
+
+    assert_eq!(project_unlocks(10, 100, 25, 5, 0, day, 1, 2).len(), 2);
+    assert!(project_unlocks(10, 100, 0, 5, 0, day, 1, 12).is_empty());
+}
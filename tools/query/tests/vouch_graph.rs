@@ -0,0 +1,98 @@
+use diem_sdk::types::account_address::AccountAddress;
+use libra_query::vouch_graph::{VouchGraph, VouchNode, MAX_PATH_DEPTH, MAX_VOUCH_SCORE};
+
+fn addr(n: u8) -> AccountAddress {
+    AccountAddress::from_hex_literal(&format!("0x{:x}", n)).unwrap()
+}
+
+/// add a vouch from `from` to `to`
+fn vouch(g: &mut VouchGraph, from: AccountAddress, to: AccountAddress) {
+    for a in [from, to] {
+        g.nodes.entry(a).or_insert(VouchNode {
+            is_init: true,
+            ..Default::default()
+        });
+    }
+    g.nodes.get_mut(&from).unwrap().given.push(to);
+    g.nodes.get_mut(&to).unwrap().received.push(from);
+}
+
+/// same network as page_rank_lazy::setup_mock_trust_network
+fn mock_network() -> VouchGraph {
+    let (root, user1, user2, user3) = (addr(10), addr(11), addr(12), addr(13));
+    let mut g = VouchGraph {
+        roots: vec![root],
+        ..Default::default()
+    };
+    vouch(&mut g, root, user1);
+    vouch(&mut g, root, user2);
+    vouch(&mut g, user2, user3);
+    g
+}
+
+#[test]
+fn offline_scores_decay_by_distance() {
+    let g = mock_network();
+    let user1 = g.score(&addr(11), MAX_PATH_DEPTH).unwrap();
+    assert_eq!(user1.score, MAX_VOUCH_SCORE);
+    assert_eq!(user1.max_depth_reached, 1);
+    assert_eq!(user1.accounts_processed, 1);
+
+    let user3 = g.score(&addr(13), MAX_PATH_DEPTH).unwrap();
+    assert_eq!(user3.score, MAX_VOUCH_SCORE / 2);
+    assert_eq!(user3.max_depth_reached, 2);
+
+    // the root itself has no one vouching for it
+    assert_eq!(g.score(&addr(10), MAX_PATH_DEPTH).unwrap().score, 0);
+    // too far from the root
+    assert_eq!(g.score(&addr(13), 2).unwrap().score, 0);
+}
+
+#[test]
+fn diamonds_add_up_and_cycles_end() {
+    let mut g = mock_network();
+    // user1 also vouches for user3, a second path from the root
+    vouch(&mut g, addr(11), addr(13));
+    // and a cycle between user3 and user1
+    vouch(&mut g, addr(13), addr(11));
+    let user3 = g.score(&addr(13), MAX_PATH_DEPTH).unwrap();
+    assert_eq!(user3.score, MAX_VOUCH_SCORE);
+}
+
+#[test]
+fn report_flags_stale_cache() {
+    let mut g = mock_network();
+    g.nodes.get_mut(&addr(11)).unwrap().cached_score = Some(MAX_VOUCH_SCORE);
+    g.nodes.get_mut(&addr(13)).unwrap().cached_score = Some(0);
+    let report = g.report();
+    assert_eq!(report.stale, vec![addr(13)]);
+
+    let graphml = report.to_graphml();
+    assert!(graphml.contains("<edge source=\"0xc\" target=\"0xd\"/>"));
+    assert!(graphml.contains("<data key=\"stale\">true</data>"));
+}
+
+#[test]
+fn truncated_graph_only_flags_walks_inside_it() {
+    let mut g = mock_network();
+    // user4 is vouched for by user3 and by an account the crawl didn't reach
+    let (user3, user4, outside) = (addr(13), addr(14), addr(20));
+    vouch(&mut g, user3, user4);
+    g.nodes.get_mut(&user4).unwrap().received.push(outside);
+    g.nodes.get_mut(&user3).unwrap().cached_score = Some(0);
+    // on chain user4 also gets the score through `outside`
+    g.nodes.get_mut(&user4).unwrap().cached_score = Some(MAX_VOUCH_SCORE);
+
+    let user4_score = g.score(&user4, MAX_PATH_DEPTH).unwrap();
+    assert!(user4_score.left_graph);
+    assert!(!g.score(&user3, MAX_PATH_DEPTH).unwrap().left_graph);
+
+    // a complete graph: the missing account is a dead end on chain too
+    assert_eq!(g.report().stale, vec![user3, user4]);
+
+    g.truncated = true;
+    let report = g.report();
+    assert!(report.truncated);
+    // user3's walk stayed in the graph, so it is still stale
+    assert_eq!(report.stale, vec![user3]);
+}
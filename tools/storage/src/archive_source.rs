@@ -0,0 +1,595 @@
+//! Where to get epoch archive backups from.
+//! The canonical archive is a GitHub repo, but any mirror with the same
+//! layout (a `snapshots/` directory with the backup folders) can be used:
+//! a plain HTTP directory index, an S3-compatible bucket, or a local path.
+use anyhow::{bail, Context, Result};
+use async_trait::async_trait;
+use glob::glob;
+use reqwest::{header::RANGE, Client, StatusCode};
+use serde::Deserialize;
+use std::{
+    fs,
+    io::{self, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+};
+
+/// The directory in the archive which contains the backup folders
+pub const SNAPSHOTS_DIR: &str = "snapshots";
+
+const USER_AGENT: &str = "libra-framework-downloader";
+
+#[async_trait]
+pub trait ArchiveSource: Send + Sync {
+    /// human readable description for logs
+    fn describe(&self) -> String;
+    /// names of the backup folders in `snapshots/`
+    async fn list_folders(&self) -> Result<Vec<String>>;
+    /// paths of all the files in a backup folder, relative to the folder
+    async fn list_files(&self, folder: &str) -> Result<Vec<String>>;
+    /// read a whole (small) file into memory
+    async fn fetch(&self, folder: &str, file: &str) -> Result<Vec<u8>>;
+    /// append a file to `out` as it arrives, skipping the first `offset`
+    /// bytes to resume a partial download. If the source can't resume, `out`
+    /// is truncated and the file written from the start.
+    async fn fetch_to(
+        &self,
+        folder: &str,
+        file: &str,
+        offset: u64,
+        out: &mut fs::File,
+    ) -> Result<()>;
+}
+
+/// Pick the source from the --mirror argument:
+/// `https://...` a plain HTTP directory index,
+/// `s3://bucket/prefix` an S3-compatible bucket (needs `s3_endpoint`),
+/// anything else is a path on the local filesystem.
+/// Without a mirror the GitHub repo is used.
+pub fn source_from_mirror(
+    mirror: Option<&str>,
+    s3_endpoint: Option<&str>,
+    owner: &str,
+    repo: &str,
+    branch: &str,
+) -> Result<Box<dyn ArchiveSource>> {
+    let Some(m) = mirror else {
+        return Ok(Box::new(GitHubSource::new(owner, repo, branch)));
+    };
+
+    if m.starts_with("http://") || m.starts_with("https://") {
+        Ok(Box::new(HttpIndexSource::new(m)))
+    } else if let Some(rest) = m.strip_prefix("s3://") {
+        let endpoint = s3_endpoint.context("an s3:// mirror needs --s3-endpoint")?;
+        let (bucket, prefix) = rest.split_once('/').unwrap_or((rest, ""));
+        Ok(Box::new(S3Source::new(endpoint, bucket, prefix)))
+    } else {
+        let path = m.strip_prefix("file://").unwrap_or(m);
+        Ok(Box::new(LocalSource::new(PathBuf::from(path))?))
+    }
+}
+
+/// GET a whole url into memory
+async fn http_get(client: &Client, url: &str) -> Result<Vec<u8>> {
+    let res = client
+        .get(url)
+        .header("User-Agent", USER_AGENT)
+        .send()
+        .await
+        .with_context(|| format!("failed to request: {}", url))?;
+    let status = res.status();
+    if !status.is_success() {
+        bail!("request to {} failed with status {}", url, status);
+    }
+    Ok(res
+        .bytes()
+        .await
+        .with_context(|| format!("failed to read bytes from: {}", url))?
+        .to_vec())
+}
+
+/// GET a url, with a Range header if resuming, and write each chunk to `out`
+/// as it arrives, so an interrupted transfer leaves what it got on disk.
+/// A 416 means there is nothing after `offset`, the file is already complete.
+/// Servers which ignore the Range header send the full file, so we start over.
+async fn http_get_to(client: &Client, url: &str, offset: u64, out: &mut fs::File) -> Result<()> {
+    let mut req = client.get(url).header("User-Agent", USER_AGENT);
+    if offset > 0 {
+        req = req.header(RANGE, format!("bytes={}-", offset));
+    }
+    let mut res = req
+        .send()
+        .await
+        .with_context(|| format!("failed to request: {}", url))?;
+    let status = res.status();
+    if offset > 0 && status == StatusCode::RANGE_NOT_SATISFIABLE {
+        return Ok(());
+    }
+    if !status.is_success() {
+        bail!("request to {} failed with status {}", url, status);
+    }
+    if offset > 0 && status != StatusCode::PARTIAL_CONTENT {
+        out.set_len(0)?;
+        out.seek(SeekFrom::Start(0))?;
+    }
+
+    while let Some(chunk) = res
+        .chunk()
+        .await
+        .with_context(|| format!("failed to read bytes from: {}", url))?
+    {
+        out.write_all(&chunk)?;
+    }
+    out.flush()?;
+    Ok(())
+}
+
+/// the canonical epoch archive on GitHub
+pub struct GitHubSource {
+    client: Client,
+    owner: String,
+    repo: String,
+    branch: String,
+}
+
+#[derive(Deserialize, Debug)]
+struct TreeItem {
+    path: String,
+    #[serde(rename = "type")]
+    item_type: String,
+}
+
+#[derive(Deserialize, Debug)]
+struct TreeResponse {
+    tree: Vec<TreeItem>,
+    truncated: bool,
+}
+
+impl GitHubSource {
+    pub fn new(owner: &str, repo: &str, branch: &str) -> Self {
+        Self {
+            client: Client::new(),
+            owner: owner.to_owned(),
+            repo: repo.to_owned(),
+            branch: branch.to_owned(),
+        }
+    }
+
+    async fn tree(&self, path: &str, recursive: bool) -> Result<Vec<TreeItem>> {
+        let api_url = format!(
+            "https://api.github.com/repos/{}/{}/git/trees/{}:{}{}",
+            self.owner,
+            self.repo,
+            self.branch,
+            path,
+            if recursive { "?recursive=1" } else { "" }
+        );
+        let res: TreeResponse = self
+            .client
+            .get(&api_url)
+            .header("User-Agent", USER_AGENT)
+            .send()
+            .await
+            .context("Failed to send tree request")?
+            .json()
+            .await
+            .context("Failed to parse JSON tree response")?;
+
+        if res.truncated {
+            println!(
+                "Warning: GitHub Tree API response is truncated, some files might be missing."
+            );
+        }
+        Ok(res.tree)
+    }
+
+    fn file_url(&self, folder: &str, file: &str) -> String {
+        format!(
+            "https://raw.githubusercontent.com/{}/{}/{}/{}/{}/{}",
+            self.owner, self.repo, self.branch, SNAPSHOTS_DIR, folder, file
+        )
+    }
+}
+
+#[async_trait]
+impl ArchiveSource for GitHubSource {
+    fn describe(&self) -> String {
+        format!("github {}/{}@{}", self.owner, self.repo, self.branch)
+    }
+
+    async fn list_folders(&self) -> Result<Vec<String>> {
+        Ok(self
+            .tree(SNAPSHOTS_DIR, false)
+            .await?
+            .into_iter()
+            .filter(|i| i.item_type == "tree")
+            .map(|i| i.path)
+            .collect())
+    }
+
+    async fn list_files(&self, folder: &str) -> Result<Vec<String>> {
+        Ok(self
+            .tree(&format!("{}/{}", SNAPSHOTS_DIR, folder), true)
+            .await?
+            .into_iter()
+            .filter(|i| i.item_type == "blob")
+            .map(|i| i.path)
+            .collect())
+    }
+
+    async fn fetch(&self, folder: &str, file: &str) -> Result<Vec<u8>> {
+        http_get(&self.client, &self.file_url(folder, file)).await
+    }
+
+    async fn fetch_to(
+        &self,
+        folder: &str,
+        file: &str,
+        offset: u64,
+        out: &mut fs::File,
+    ) -> Result<()> {
+        http_get_to(&self.client, &self.file_url(folder, file), offset, out).await
+    }
+}
+
+/// A mirror served as a plain HTTP directory index
+/// (e.g. nginx autoindex, or `python -m http.server`)
+pub struct HttpIndexSource {
+    client: Client,
+    base_url: String,
+}
+
+impl HttpIndexSource {
+    pub fn new(base_url: &str) -> Self {
+        Self {
+            client: Client::new(),
+            base_url: base_url.trim_end_matches('/').to_owned(),
+        }
+    }
+
+    /// list the entries of a directory index page, directories end with '/'
+    async fn index(&self, path: &str) -> Result<Vec<String>> {
+        let url = format!("{}/{}/", self.base_url, path);
+        let html = String::from_utf8(http_get(&self.client, &url).await?)?;
+        Ok(parse_index_links(&html))
+    }
+
+    fn file_url(&self, folder: &str, file: &str) -> String {
+        format!("{}/{}/{}/{}", self.base_url, SNAPSHOTS_DIR, folder, file)
+    }
+}
+
+/// Get the relative links from a directory index page.
+/// Skips parent, absolute and sorting links.
+pub fn parse_index_links(html: &str) -> Vec<String> {
+    html.split("href=\"")
+        .skip(1)
+        .filter_map(|s| s.split('"').next())
+        .filter(|l| {
+            !l.is_empty()
+                && !l.starts_with('?')
+                && !l.starts_with('/')
+                && !l.starts_with("..")
+                && !l.contains("://")
+        })
+        .map(|l| l.trim_start_matches("./").to_owned())
+        .collect()
+}
+
+#[async_trait]
+impl ArchiveSource for HttpIndexSource {
+    fn describe(&self) -> String {
+        format!("http index {}", self.base_url)
+    }
+
+    async fn list_folders(&self) -> Result<Vec<String>> {
+        Ok(self
+            .index(SNAPSHOTS_DIR)
+            .await?
+            .into_iter()
+            .filter_map(|l| l.strip_suffix('/').map(|s| s.to_owned()))
+            .collect())
+    }
+
+    async fn list_files(&self, folder: &str) -> Result<Vec<String>> {
+        let mut files = vec![];
+        let mut dirs = vec![String::new()];
+        while let Some(sub) = dirs.pop() {
+            let path = format!("{}/{}/{}", SNAPSHOTS_DIR, folder, sub);
+            for l in self.index(path.trim_end_matches('/')).await? {
+                if l.ends_with('/') {
+                    dirs.push(format!("{}{}", sub, l));
+                } else {
+                    files.push(format!("{}{}", sub, l));
+                }
+            }
+        }
+        Ok(files)
+    }
+
+    async fn fetch(&self, folder: &str, file: &str) -> Result<Vec<u8>> {
+        http_get(&self.client, &self.file_url(folder, file)).await
+    }
+
+    async fn fetch_to(
+        &self,
+        folder: &str,
+        file: &str,
+        offset: u64,
+        out: &mut fs::File,
+    ) -> Result<()> {
+        http_get_to(&self.client, &self.file_url(folder, file), offset, out).await
+    }
+}
+
+/// A public S3-compatible bucket, using path-style requests.
+/// NOTE: only anonymous read access is supported
+pub struct S3Source {
+    client: Client,
+    endpoint: String,
+    bucket: String,
+    prefix: String,
+}
+
+impl S3Source {
+    pub fn new(endpoint: &str, bucket: &str, prefix: &str) -> Self {
+        let prefix = prefix.trim_matches('/');
+        Self {
+            client: Client::new(),
+            endpoint: endpoint.trim_end_matches('/').to_owned(),
+            bucket: bucket.to_owned(),
+            prefix: if prefix.is_empty() {
+                String::new()
+            } else {
+                format!("{}/", prefix)
+            },
+        }
+    }
+
+    /// ListObjectsV2, following continuation tokens.
+    /// Returns (keys, common prefixes)
+    async fn list(&self, prefix: &str, delimiter: bool) -> Result<(Vec<String>, Vec<String>)> {
+        let mut keys = vec![];
+        let mut prefixes = vec![];
+        let mut token: Option<String> = None;
+        loop {
+            let mut req = self
+                .client
+                .get(format!("{}/{}", self.endpoint, self.bucket))
+                .header("User-Agent", USER_AGENT)
+                .query(&[("list-type", "2"), ("prefix", prefix)]);
+            if delimiter {
+                req = req.query(&[("delimiter", "/")]);
+            }
+            if let Some(t) = &token {
+                req = req.query(&[("continuation-token", t.as_str())]);
+            }
+            let xml = req
+                .send()
+                .await
+                .context("failed to list bucket")?
+                .error_for_status()?
+                .text()
+                .await?;
+
+            keys.extend(xml_values(&xml, "Key"));
+            // the request prefix is also in the response, skip it
+            prefixes.extend(
+                xml_values(&xml, "Prefix")
+                    .into_iter()
+                    .filter(|p| p != prefix),
+            );
+
+            token = xml_values(&xml, "NextContinuationToken").into_iter().next();
+            if token.is_none() {
+                break;
+            }
+        }
+        Ok((keys, prefixes))
+    }
+
+    fn file_url(&self, folder: &str, file: &str) -> String {
+        format!(
+            "{}/{}/{}{}/{}/{}",
+            self.endpoint, self.bucket, self.prefix, SNAPSHOTS_DIR, folder, file
+        )
+    }
+}
+
+/// all the values of a tag in a (simple) XML document
+pub fn xml_values(xml: &str, tag: &str) -> Vec<String> {
+    let open = format!("<{}>", tag);
+    let close = format!("</{}>", tag);
+    xml.split(&open)
+        .skip(1)
+        .filter_map(|s| s.split(&close).next())
+        .map(|s| s.replace("&amp;", "&"))
+        .collect()
+}
+
+#[async_trait]
+impl ArchiveSource for S3Source {
+    fn describe(&self) -> String {
+        format!("s3 {}/{}/{}", self.endpoint, self.bucket, self.prefix)
+    }
+
+    async fn list_folders(&self) -> Result<Vec<String>> {
+        let root = format!("{}{}/", self.prefix, SNAPSHOTS_DIR);
+        let (_, prefixes) = self.list(&root, true).await?;
+        Ok(prefixes
+            .iter()
+            .filter_map(|p| p.strip_prefix(&root))
+            .map(|p| p.trim_end_matches('/').to_owned())
+            .collect())
+    }
+
+    async fn list_files(&self, folder: &str) -> Result<Vec<String>> {
+        let root = format!("{}{}/{}/", self.prefix, SNAPSHOTS_DIR, folder);
+        let (keys, _) = self.list(&root, false).await?;
+        Ok(keys
+            .iter()
+            .filter_map(|k| k.strip_prefix(&root))
+            .filter(|k| !k.is_empty())
+            .map(|k| k.to_owned())
+            .collect())
+    }
+
+    async fn fetch(&self, folder: &str, file: &str) -> Result<Vec<u8>> {
+        http_get(&self.client, &self.file_url(folder, file)).await
+    }
+
+    async fn fetch_to(
+        &self,
+        folder: &str,
+        file: &str,
+        offset: u64,
+        out: &mut fs::File,
+    ) -> Result<()> {
+        http_get_to(&self.client, &self.file_url(folder, file), offset, out).await
+    }
+}
+
+/// A copy of the archive on the local filesystem (or a network mount)
+pub struct LocalSource {
+    root: PathBuf,
+}
+
+impl LocalSource {
+    pub fn new(root: PathBuf) -> Result<Self> {
+        if !root.join(SNAPSHOTS_DIR).exists() {
+            bail!(
+                "local mirror has no {} directory: {}",
+                SNAPSHOTS_DIR,
+                root.display()
+            );
+        }
+        Ok(Self { root })
+    }
+
+    fn folder_path(&self, folder: &str) -> PathBuf {
+        self.root.join(SNAPSHOTS_DIR).join(folder)
+    }
+}
+
+#[async_trait]
+impl ArchiveSource for LocalSource {
+    fn describe(&self) -> String {
+        format!("local {}", self.root.display())
+    }
+
+    async fn list_folders(&self) -> Result<Vec<String>> {
+        let mut folders = vec![];
+        for entry in fs::read_dir(self.root.join(SNAPSHOTS_DIR))? {
+            let entry = entry?;
+            if entry.file_type()?.is_dir() {
+                folders.push(entry.file_name().to_string_lossy().to_string());
+            }
+        }
+        Ok(folders)
+    }
+
+    async fn list_files(&self, folder: &str) -> Result<Vec<String>> {
+        let base = self.folder_path(folder);
+        let pattern = format!("{}/**/*", base.display());
+        let mut files = vec![];
+        for entry in glob(&pattern)? {
+            let path = entry?;
+            if path.is_file() {
+                files.push(relative_str(&path, &base)?);
+            }
+        }
+        Ok(files)
+    }
+
+    async fn fetch(&self, folder: &str, file: &str) -> Result<Vec<u8>> {
+        Ok(fs::read(self.folder_path(folder).join(file))?)
+    }
+
+    async fn fetch_to(
+        &self,
+        folder: &str,
+        file: &str,
+        offset: u64,
+        out: &mut fs::File,
+    ) -> Result<()> {
+        let mut f = fs::File::open(self.folder_path(folder).join(file))?;
+        f.seek(SeekFrom::Start(offset))?;
+        io::copy(&mut f, out)?;
+        Ok(())
+    }
+}
+
+fn relative_str(path: &Path, base: &Path) -> Result<String> {
+    Ok(path
+        .strip_prefix(base)?
+        .to_string_lossy()
+        .trim_start_matches('/')
+        .to_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_index_links() {
+        let html = r#"<html><body><h1>Index of /snapshots/</h1>
+<a href="../">../</a>
+<a href="?C=N;O=D">Name</a>
+<a href="epoch_ending_116-.be9b/">epoch_ending_116-.be9b/</a>
+<a href="./transaction.manifest">transaction.manifest</a>
+<a href="https://example.com">elsewhere</a>
+</body></html>"#;
+        assert_eq!(
+            parse_index_links(html),
+            vec!["epoch_ending_116-.be9b/", "transaction.manifest"]
+        );
+    }
+
+    #[test]
+    fn test_xml_values() {
+        let xml = "<ListBucketResult><Prefix>snapshots/</Prefix>\
+<CommonPrefixes><Prefix>snapshots/epoch_ending_1-.aa/</Prefix></CommonPrefixes>\
+<Contents><Key>snapshots/a&amp;b</Key></Contents></ListBucketResult>";
+        assert_eq!(
+            xml_values(xml, "Prefix"),
+            vec!["snapshots/", "snapshots/epoch_ending_1-.aa/"]
+        );
+        assert_eq!(xml_values(xml, "Key"), vec!["snapshots/a&b"]);
+    }
+
+    #[tokio::test]
+    async fn test_local_source_lists_fixtures() {
+        let dir = diem_temppath::TempPath::new();
+        dir.create_as_dir().unwrap();
+        let snapshots = dir.path().join(SNAPSHOTS_DIR);
+        fs::create_dir_all(&snapshots).unwrap();
+        fs_extra::dir::copy(
+            "fixtures/v7/epoch_ending_116-.be9b",
+            &snapshots,
+            &fs_extra::dir::CopyOptions::new(),
+        )
+        .unwrap();
+
+        let source = LocalSource::new(dir.path().to_path_buf()).unwrap();
+        let folders = source.list_folders().await.unwrap();
+        assert_eq!(folders, vec!["epoch_ending_116-.be9b"]);
+
+        let files = source.list_files(&folders[0]).await.unwrap();
+        assert!(files.contains(&"epoch_ending.manifest".to_string()));
+
+        let full = source.fetch(&folders[0], &files[0]).await.unwrap();
+
+        // resume after the first byte
+        let part_path = dir.path().join("resumed.part");
+        fs::write(&part_path, &full[..1]).unwrap();
+        let mut out = fs::OpenOptions::new()
+            .append(true)
+            .open(&part_path)
+            .unwrap();
+        source
+            .fetch_to(&folders[0], &files[0], 1, &mut out)
+            .await
+            .unwrap();
+        assert_eq!(fs::read(&part_path).unwrap(), full);
+    }
+}
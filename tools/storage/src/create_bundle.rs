@@ -0,0 +1,354 @@
+//! Produce an epoch restore bundle from a local DB, so that archive operators
+//! can publish new epochs without a separate backup coordinator.
+//! The output has the same layout as the `epoch-archive` `snapshots/` folders:
+//! `epoch_ending_N-.xxxx`, `state_epoch_N_ver_V.xxxx` and `transaction_S-.xxxx`,
+//! with every file gzipped and a SHA256SUMS file per folder.
+
+use crate::{dbtool_init::get_backup_storage, download_bundle::CHECKSUM_FILE};
+use anyhow::{bail, Context, Result};
+use diem_backup_cli::{
+    backup_types::{
+        epoch_ending::{
+            backup::{EpochEndingBackupController, EpochEndingBackupOpt},
+            manifest::EpochEndingBackup,
+        },
+        state_snapshot::backup::{StateSnapshotBackupController, StateSnapshotBackupOpt},
+        transaction::backup::{TransactionBackupController, TransactionBackupOpt},
+    },
+    utils::{backup_service_client::BackupServiceClient, GlobalBackupOpt},
+};
+use diem_backup_service::start_backup_service;
+use diem_config::{
+    config::{
+        RocksdbConfigs, BUFFERED_STATE_TARGET_ITEMS, DEFAULT_MAX_NUM_NODES_PER_LRU_CACHE_SHARD,
+        NO_OP_STORAGE_PRUNER_CONFIG,
+    },
+    utils::get_available_port,
+};
+use diem_db::DiemDB;
+use flate2::{write::GzEncoder, Compression};
+use sha2::{Digest, Sha256};
+use std::{
+    fs::{self, File},
+    io::copy,
+    net::{IpAddr, Ipv4Addr, SocketAddr},
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+/// the archive splits transaction backups in folders of this many versions
+pub const DEFAULT_TRANSACTION_SPAN: u64 = 100_000;
+
+/// the backup is written here inside the out dir, then its folders are moved
+/// out. It also holds the metadata cache of the controllers, which is not
+/// part of a bundle.
+const STAGING_DIR: &str = ".create_bundle_staging";
+
+pub struct CreateBundleOpts {
+    /// versions per transaction backup folder
+    pub transaction_span: u64,
+    /// gzip all the files, like the archive does
+    pub compress: bool,
+}
+
+impl Default for CreateBundleOpts {
+    fn default() -> Self {
+        Self {
+            transaction_span: DEFAULT_TRANSACTION_SPAN,
+            compress: true,
+        }
+    }
+}
+
+/// Back up the epoch ending, state snapshot and transactions needed to
+/// restore `epoch`, from the DB at `db_path` into `out_dir`.
+/// The DB is opened read only, so a node can be stopped and its DB used.
+pub async fn create_epoch_bundle(
+    db_path: &Path,
+    epoch: u64,
+    out_dir: &Path,
+    opts: &CreateBundleOpts,
+) -> Result<()> {
+    if opts.transaction_span == 0 {
+        bail!("transaction span must be greater than zero");
+    }
+    fs::create_dir_all(out_dir)?;
+    // the out dir may hold published folders, so back up into a fresh dir
+    let staging = out_dir.join(STAGING_DIR);
+    if staging.exists() {
+        fs::remove_dir_all(&staging)?;
+    }
+    fs::create_dir_all(&staging)?;
+
+    let db = Arc::new(
+        DiemDB::open(
+            db_path,
+            true,                        /* read_only */
+            NO_OP_STORAGE_PRUNER_CONFIG, /* pruner config */
+            RocksdbConfigs::default(),
+            false,
+            BUFFERED_STATE_TARGET_ITEMS,
+            DEFAULT_MAX_NUM_NODES_PER_LRU_CACHE_SHARD,
+        )
+        .context(format!("cannot open db at {}", db_path.display()))?,
+    );
+
+    // the backup controllers only talk to a backup service, so serve this db locally
+    let addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), get_available_port());
+    let rt = start_backup_service(addr, db);
+    let client = Arc::new(BackupServiceClient::new(format!("http://{}", addr)));
+    let storage = get_backup_storage(staging.clone())?;
+    let global_opt = GlobalBackupOpt {
+        max_chunk_size: 128 * 1024 * 1024,
+        concurrent_data_requests: 8,
+    };
+
+    let res = async {
+        println!("backing up epoch ending: {}", epoch);
+        let epoch_manifest = EpochEndingBackupController::new(
+            EpochEndingBackupOpt {
+                start_epoch: epoch,
+                end_epoch: epoch + 1,
+            },
+            global_opt.clone(),
+            Arc::clone(&client),
+            Arc::clone(&storage),
+        )
+        .run()
+        .await?;
+
+        // the restore version is the one of the waypoint ending the epoch
+        let content = fs::read_to_string(staging.join(&epoch_manifest))?;
+        let manifest: EpochEndingBackup = serde_json::from_str(&content)?;
+        let version = manifest
+            .waypoints
+            .first()
+            .context("epoch ending manifest has no waypoint")?
+            .version();
+
+        println!(
+            "backing up state snapshot: epoch {}, version {}",
+            epoch, version
+        );
+        let state_manifest = StateSnapshotBackupController::new(
+            StateSnapshotBackupOpt { epoch },
+            global_opt.clone(),
+            Arc::clone(&client),
+            Arc::clone(&storage),
+        )
+        .run()
+        .await?;
+
+        let (start_version, num_transactions) =
+            transaction_backup_range(version, opts.transaction_span);
+        println!("backing up transactions: {} to {}", start_version, version);
+        let transaction_manifest = TransactionBackupController::new(
+            TransactionBackupOpt {
+                start_version,
+                num_transactions: num_transactions as usize,
+            },
+            global_opt,
+            client,
+            storage,
+        )
+        .run()
+        .await?;
+
+        anyhow::Ok([epoch_manifest, state_manifest, transaction_manifest])
+    }
+    .await;
+
+    // the service runtime can't be dropped from an async context
+    rt.shutdown_background();
+    let manifests = res?;
+
+    let folders = manifests
+        .iter()
+        .map(|m| manifest_folder(m))
+        .collect::<Result<Vec<_>>>()?;
+    finish_bundle(&staging, &folders, out_dir, opts.compress)?;
+    fs::remove_dir_all(&staging)?;
+
+    println!(
+        "SUCCESS: bundle for epoch {} in {}",
+        epoch,
+        out_dir.display()
+    );
+    Ok(())
+}
+
+/// the backup folder of a manifest handle, e.g. `epoch_ending_116-.be9b`
+fn manifest_folder(handle: &str) -> Result<String> {
+    Path::new(handle)
+        .components()
+        .next()
+        .and_then(|c| c.as_os_str().to_str())
+        .map(str::to_owned)
+        .context(format!("no backup folder in manifest handle {}", handle))
+}
+
+/// Compress and checksum the backup folders in `staging`, then move them
+/// into `out_dir`. Nothing else in `out_dir` is touched.
+pub fn finish_bundle(
+    staging: &Path,
+    folders: &[String],
+    out_dir: &Path,
+    compress: bool,
+) -> Result<()> {
+    for name in folders {
+        let dest = out_dir.join(name);
+        if dest.exists() {
+            bail!("{} already exists, not overwriting it", dest.display());
+        }
+        let folder = staging.join(name);
+        if compress {
+            compress_folder(&folder)?;
+        }
+        write_checksums(&folder)?;
+        fs::rename(&folder, &dest)?;
+    }
+    Ok(())
+}
+
+/// The transaction backup starts at the beginning of the span which holds
+/// `version`, like the archive folders (e.g. transaction_38100001-),
+/// and ends at `version`. Returns the start and the number of transactions.
+pub fn transaction_backup_range(version: u64, span: u64) -> (u64, u64) {
+    let start = if version == 0 {
+        0
+    } else {
+        ((version - 1) / span) * span + 1
+    };
+    (start, version - start + 1)
+}
+
+/// gzip every file in the folder, removing the originals.
+/// NOTE: manifests keep pointing to the uncompressed names, restore
+/// decompresses everything before reading them.
+pub fn compress_folder(folder: &Path) -> Result<()> {
+    for path in list_files(folder)? {
+        if path.extension().map(|e| e == "gz").unwrap_or(false) {
+            continue;
+        }
+        let mut gz_name = path.clone().into_os_string();
+        gz_name.push(".gz");
+
+        let mut encoder = GzEncoder::new(File::create(&gz_name)?, Compression::default());
+        copy(&mut File::open(&path)?, &mut encoder)?;
+        encoder.finish()?;
+        fs::remove_file(&path)?;
+    }
+    Ok(())
+}
+
+/// write a `sha256sum` style file for all files of the folder, which
+/// `download-restore-bundle` uses to verify the chunks
+pub fn write_checksums(folder: &Path) -> Result<()> {
+    let mut lines = vec![];
+    for path in list_files(folder)? {
+        let name = path
+            .strip_prefix(folder)?
+            .to_str()
+            .context("invalid file name")?
+            .to_owned();
+        if name == CHECKSUM_FILE {
+            continue;
+        }
+        let mut hasher = Sha256::new();
+        hasher.update(fs::read(&path)?);
+        lines.push(format!("{}  {}", hex::encode(hasher.finalize()), name));
+    }
+    lines.sort();
+    fs::write(folder.join(CHECKSUM_FILE), lines.join("\n") + "\n")?;
+    Ok(())
+}
+
+fn list_files(folder: &Path) -> Result<Vec<PathBuf>> {
+    let mut files = vec![];
+    for entry in fs::read_dir(folder)? {
+        let path = entry?.path();
+        if path.is_file() {
+            files.push(path);
+        }
+    }
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{download_bundle::parse_checksums, restore::maybe_decompress_gz_files};
+
+    #[test]
+    fn test_transaction_backup_range() {
+        // same as the archive folder transaction_38100001-
+        assert_eq!(
+            transaction_backup_range(38180075, DEFAULT_TRANSACTION_SPAN),
+            (38100001, 80075)
+        );
+        assert_eq!(transaction_backup_range(100_000, 100_000), (1, 100_000));
+        assert_eq!(transaction_backup_range(100_001, 100_000), (100_001, 1));
+        assert_eq!(transaction_backup_range(0, 100_000), (0, 1));
+    }
+
+    #[tokio::test]
+    async fn test_compress_and_checksum_round_trip() -> Result<()> {
+        let temp = diem_temppath::TempPath::new();
+        temp.create_as_dir()?;
+        let folder = temp.path().join("epoch_ending_116-.be9b");
+        fs::create_dir_all(&folder)?;
+        fs::write(folder.join("epoch_ending.manifest"), "{}")?;
+        fs::write(folder.join("116-.chunk"), "chunk")?;
+
+        compress_folder(&folder)?;
+        write_checksums(&folder)?;
+
+        assert!(folder.join("epoch_ending.manifest.gz").exists());
+        assert!(!folder.join("116-.chunk").exists());
+
+        let sums = parse_checksums(&fs::read_to_string(folder.join(CHECKSUM_FILE))?);
+        assert_eq!(sums.len(), 2);
+        assert!(sums.contains_key("116-.chunk.gz"));
+
+        maybe_decompress_gz_files(temp.path()).await?;
+        assert_eq!(fs::read_to_string(folder.join("116-.chunk"))?, "chunk");
+        Ok(())
+    }
+
+    #[test]
+    fn test_finish_bundle_leaves_other_folders() -> Result<()> {
+        let temp = diem_temppath::TempPath::new();
+        temp.create_as_dir()?;
+        let out_dir = temp.path();
+        // an already published folder, and the metadata of another tool
+        let published = out_dir.join("epoch_ending_100-.aaaa");
+        fs::create_dir_all(&published)?;
+        fs::write(published.join("100-.chunk"), "old")?;
+        fs::create_dir_all(out_dir.join("metadata"))?;
+
+        let staging = out_dir.join(STAGING_DIR);
+        let new = "epoch_ending_116-.be9b";
+        fs::create_dir_all(staging.join(new))?;
+        fs::write(staging.join(new).join("116-.chunk"), "chunk")?;
+        assert_eq!(
+            manifest_folder(&format!("{}/epoch_ending.manifest", new))?,
+            new
+        );
+
+        finish_bundle(&staging, &[new.to_string()], out_dir, true)?;
+
+        assert!(out_dir.join(new).join("116-.chunk.gz").exists());
+        assert!(out_dir.join(new).join(CHECKSUM_FILE).exists());
+        assert!(!staging.join(new).exists());
+
+        assert_eq!(fs::read_to_string(published.join("100-.chunk"))?, "old");
+        assert!(!published.join(CHECKSUM_FILE).exists());
+        assert!(!published.join("100-.chunk.gz").exists());
+        assert!(out_dir.join("metadata").exists());
+
+        // a second run can't overwrite a published folder
+        fs::create_dir_all(staging.join(new))?;
+        assert!(finish_bundle(&staging, &[new.to_string()], out_dir, true).is_err());
+        Ok(())
+    }
+}
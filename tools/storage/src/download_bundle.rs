@@ -1,41 +1,43 @@
-use crate::parse_folder_names::{
-    parse_epoch_ending_number, parse_state_epoch_info, parse_transaction_number,
+use crate::{
+    archive_source::{source_from_mirror, ArchiveSource},
+    parse_folder_names::{
+        parse_epoch_ending_number, parse_state_epoch_info, parse_transaction_number,
+    },
 };
 use anyhow::bail;
 use anyhow::{Context, Result};
 use diem_logger::info;
-use reqwest::Client;
-use serde::Deserialize;
+use futures::{stream, StreamExt, TryStreamExt};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
 use std::fs;
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
 
-// Update GitHubContent structure for contents API
-#[derive(Deserialize, Debug)]
-#[allow(dead_code)]
-struct GitHubContent {
-    download_url: Option<String>,
-    #[serde(rename = "type")]
-    content_type: String,
-    name: String,
+/// Optional checksum file in each backup folder, in `sha256sum` format
+pub const CHECKSUM_FILE: &str = "SHA256SUMS";
+/// Marker written in a cached bundle once all files are downloaded and verified
+const CACHE_COMPLETE_MARKER: &str = ".bundle_complete";
+
+/// Options for fetching a restore bundle from any archive source
+#[derive(Debug, Clone)]
+pub struct DownloadOpts {
+    /// number of files to download at the same time
+    pub jobs: usize,
+    /// keep bundles here, keyed by epoch, and reuse them on the next run
+    pub cache_dir: Option<PathBuf>,
+    /// fail if a folder does not have a SHA256SUMS file
+    pub require_checksums: bool,
 }
 
-// Add TreeResponse structures for the tree API
-#[derive(Deserialize, Debug)]
-#[allow(dead_code)]
-struct TreeItem {
-    path: String,
-    mode: String,
-    #[serde(rename = "type")]
-    item_type: String,
-    sha: String,
-    url: Option<String>,
-}
-
-#[derive(Deserialize, Debug)]
-struct TreeResponse {
-    tree: Vec<TreeItem>,
-    truncated: bool,
+impl Default for DownloadOpts {
+    fn default() -> Self {
+        Self {
+            jobs: 4,
+            cache_dir: None,
+            require_checksums: false,
+        }
+    }
 }
 
 // Rest of the original data structures
@@ -99,47 +101,27 @@ fn find_closest_transaction_folder(
 }
 
 pub async fn find_closest_epoch_folder(
-    client: &Client,
-    owner: &str,
-    repo: &str,
-    branch: &str,
+    source: &dyn ArchiveSource,
     target_epoch: u64,
 ) -> Result<EpochFolders> {
-    // Update to use the Git Tree API instead of Contents API
-    let api_url = format!(
-        "https://api.github.com/repos/{}/{}/git/trees/{}:snapshots",
-        owner, repo, branch
-    );
-
-    let response = client
-        .get(&api_url)
-        .header("User-Agent", "libra-framework-downloader")
-        .send()
+    let folders = source
+        .list_folders()
         .await
         .context("Failed to list snapshots directory")?;
+    select_epoch_folders(&folders, target_epoch)
+}
 
-    let tree_response: TreeResponse = response
-        .json()
-        .await
-        .context("Failed to parse snapshots directory contents")?;
-
-    if tree_response.truncated {
-        info!("Warning: GitHub Tree API response is truncated. Some folders might be missing.");
-    }
-
+/// Given the names of all the backup folders in the archive,
+/// pick the three needed to restore the target epoch
+pub fn select_epoch_folders(folder_names: &[String], target_epoch: u64) -> Result<EpochFolders> {
     // Separate folders by type
     let mut epoch_ending_folders: Vec<(u64, String)> = Vec::new();
     let mut state_epoch_folders: Vec<(u64, String)> = Vec::new();
     let mut transaction_folders: Vec<(u64, String)> = Vec::new();
 
-    for item in tree_response.tree {
-        // Only consider tree items (directories)
-        if item.item_type != "tree" {
-            continue;
-        }
-
+    for path in folder_names {
         // Extract just the folder name from the path
-        let folder_name = match Path::new(&item.path).file_name() {
+        let folder_name = match Path::new(path).file_name() {
             Some(name) => name.to_string_lossy().to_string(),
             None => continue,
         };
@@ -216,123 +198,196 @@ fn find_matching_state_epoch(folders: &[(u64, String)], target: u64) -> Result<S
     }
 }
 
-pub async fn download_github_folder(
-    owner: &str,
-    repo: &str,
-    path: &str,
-    branch: &str,
-    output_dir: &str,
-) -> Result<()> {
-    let client = reqwest::Client::new();
-
-    // Create the root output directory first
-    fs::create_dir_all(output_dir)?;
-
-    // Use the Git Tree API with recursive flag to get all contents at once
-    let api_url = format!(
-        "https://api.github.com/repos/{}/{}/git/trees/{}:{}?recursive=1",
-        owner, repo, branch, path
-    );
-
-    info!("Downloading tree from: {}", api_url);
+/// Parse a `sha256sum` style file: `<hex digest>  <path>` per line
+pub fn parse_checksums(content: &str) -> HashMap<String, String> {
+    content
+        .lines()
+        .filter_map(|l| {
+            let (hash, path) = l.trim().split_once(char::is_whitespace)?;
+            // binary mode is marked with a '*' before the path
+            let path = path.trim().trim_start_matches('*').trim_start_matches("./");
+            Some((path.to_owned(), hash.to_lowercase()))
+        })
+        .collect()
+}
 
-    let response = client
-        .get(&api_url)
-        .header("User-Agent", "libra-framework-downloader")
-        .send()
-        .await
-        .context("Failed to send tree request")?;
+fn sha256_file(path: &Path) -> Result<String> {
+    let mut hasher = Sha256::new();
+    hasher.update(fs::read(path)?);
+    Ok(hex::encode(hasher.finalize()))
+}
 
-    let tree_response: TreeResponse = response
-        .json()
-        .await
-        .context("Failed to parse JSON tree response")?;
+/// Download one file, resuming from a `.part` file if a previous run stopped.
+/// Files which are already complete (and match the checksum) are skipped.
+async fn download_file(
+    source: &dyn ArchiveSource,
+    folder: &str,
+    file: &str,
+    output_path: &Path,
+    expected_hash: Option<&String>,
+) -> Result<()> {
+    if output_path.exists() {
+        match expected_hash {
+            Some(h) if &sha256_file(output_path)? != h => {
+                println!("checksum mismatch, downloading again: {}", file);
+                fs::remove_file(output_path)?;
+            }
+            _ => return Ok(()),
+        }
+    }
 
-    if tree_response.truncated {
-        info!("Warning: Response was truncated, not all files will be downloaded");
+    if let Some(parent) = output_path.parent() {
+        fs::create_dir_all(parent)?;
     }
 
-    // Get the base path to properly handle nested directories
-    let base_path = Path::new(path)
-        .file_name()
-        .map_or(String::new(), |name| name.to_string_lossy().to_string());
-    let base_dir = Path::new(output_dir).join(base_path);
-    fs::create_dir_all(&base_dir)?;
+    let part_path = output_path.with_extension(match output_path.extension() {
+        Some(e) => format!("{}.part", e.to_string_lossy()),
+        None => "part".to_string(),
+    });
+    let offset = fs::metadata(&part_path).map(|m| m.len()).unwrap_or(0);
+    if offset > 0 {
+        info!("resuming {} from byte {}", file, offset);
+    }
 
-    // Process files from the tree
-    for item in tree_response.tree {
-        // Skip if not a blob (file)
-        if item.item_type != "blob" {
-            continue;
+    let mut f = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&part_path)
+        .with_context(|| format!("Failed to open file: {}", part_path.display()))?;
+    source
+        .fetch_to(folder, file, offset, &mut f)
+        .await
+        .with_context(|| format!("Failed to download file: {}", file))?;
+    drop(f);
+
+    if let Some(h) = expected_hash {
+        let actual = sha256_file(&part_path)?;
+        if &actual != h {
+            // a bad partial file would never recover, start over next time
+            fs::remove_file(&part_path)?;
+            bail!(
+                "checksum mismatch for {}/{}: expected {}, got {}",
+                folder,
+                file,
+                h,
+                actual
+            );
         }
+    }
 
-        // The path in tree response is relative to the requested path
-        let relative_path = item.path;
+    fs::rename(&part_path, output_path)?;
+    Ok(())
+}
 
-        // Compute where to save the file
-        let output_path = base_dir.join(&relative_path);
+/// Download all the files of a backup folder into `output_dir/<folder>`,
+/// verifying them against the folder's SHA256SUMS if there is one.
+pub async fn download_folder(
+    source: &dyn ArchiveSource,
+    folder: &str,
+    output_dir: &Path,
+    opts: &DownloadOpts,
+) -> Result<()> {
+    let base_dir = output_dir.join(folder);
+    fs::create_dir_all(&base_dir)?;
 
-        // Create parent directory if it doesn't exist
-        if let Some(parent) = output_path.parent() {
-            fs::create_dir_all(parent)?;
+    let files = source.list_files(folder).await?;
+
+    let checksums = if files.iter().any(|f| f == CHECKSUM_FILE) {
+        let content = source.fetch(folder, CHECKSUM_FILE).await?;
+        fs::write(base_dir.join(CHECKSUM_FILE), &content)?;
+        parse_checksums(&String::from_utf8(content)?)
+    } else if opts.require_checksums {
+        bail!("no {} found in folder {}", CHECKSUM_FILE, folder);
+    } else {
+        println!(
+            "Warning: no {} in {}, files will not be verified",
+            CHECKSUM_FILE, folder
+        );
+        HashMap::new()
+    };
+
+    let to_download: Vec<&String> = files.iter().filter(|f| *f != CHECKSUM_FILE).collect();
+    if !checksums.is_empty() {
+        if let Some(missing) = to_download
+            .iter()
+            .find(|f| !checksums.contains_key(f.as_str()))
+        {
+            bail!("{} has no checksum in {}", missing, CHECKSUM_FILE);
         }
+    }
 
-        // Download the file content using raw GitHub URL
-        let content_url = format!(
-            "https://raw.githubusercontent.com/{}/{}/{}/{}/{}",
-            owner, repo, branch, path, relative_path
-        );
+    println!(
+        "downloading {} files from {} ({} at a time)",
+        to_download.len(),
+        folder,
+        opts.jobs
+    );
 
-        let content = client
-            .get(&content_url)
-            .header("User-Agent", "libra-framework-downloader")
-            .send()
-            .await
-            .with_context(|| format!("Failed to download file: {}", relative_path))?
-            .bytes()
-            .await
-            .with_context(|| format!("Failed to read bytes from: {}", relative_path))?;
-
-        fs::write(&output_path, content)
-            .with_context(|| format!("Failed to write file: {}", relative_path))?;
-    }
+    stream::iter(to_download)
+        .map(|file| {
+            let output_path = base_dir.join(file);
+            let hash = checksums.get(file);
+            async move { download_file(source, folder, file, &output_path, hash).await }
+        })
+        .buffer_unordered(opts.jobs.max(1))
+        .try_collect::<Vec<()>>()
+        .await?;
 
     Ok(())
 }
 
-pub async fn download_restore_bundle(
-    owner: &str,
-    repo: &str,
-    branch: &str,
+/// Download the restore bundle for an epoch from any archive source.
+/// With a cache dir, bundles are kept there by epoch and copied to the
+/// destination, so a second run does not download again.
+pub async fn download_restore_bundle_from_source(
+    source: &dyn ArchiveSource,
     epoch_num: &u64,
     destination: &Path,
+    opts: &DownloadOpts,
 ) -> Result<PathBuf> {
     // Create the bundle-specific directory
-    let bundle_dir = destination.join(format!("epoch_{}_restore_bundle", epoch_num));
+    let bundle_name = format!("epoch_{}_restore_bundle", epoch_num);
+    let bundle_dir = destination.join(&bundle_name);
     if !bundle_dir.exists() {
         println!("creating directory: {}", bundle_dir.display());
         fs::create_dir_all(&bundle_dir)?;
     }
 
-    let client = reqwest::Client::new();
-
-    let folders = find_closest_epoch_folder(&client, owner, repo, branch, *epoch_num).await?;
-
-    // Download all three folders
-    for folder in [
-        &folders.epoch_ending,
-        &folders.state_epoch,
-        &folders.transaction,
-    ] {
-        let snapshot_path = format!("snapshots/{}", folder);
-        download_github_folder(
-            owner,
-            repo,
-            &snapshot_path,
-            branch,
-            bundle_dir.to_str().unwrap(),
-        )
-        .await?;
+    let work_dir = match &opts.cache_dir {
+        Some(c) => c.join(&bundle_name),
+        None => bundle_dir.clone(),
+    };
+
+    if work_dir.join(CACHE_COMPLETE_MARKER).exists() {
+        println!("using cached bundle at {}", work_dir.display());
+    } else {
+        println!("downloading from {}", source.describe());
+        let folders = find_closest_epoch_folder(source, *epoch_num).await?;
+
+        // Download all three folders
+        for folder in [
+            &folders.epoch_ending,
+            &folders.state_epoch,
+            &folders.transaction,
+        ] {
+            download_folder(source, folder, &work_dir, opts).await?;
+        }
+
+        if opts.cache_dir.is_some() {
+            fs::write(work_dir.join(CACHE_COMPLETE_MARKER), source.describe())?;
+        }
+    }
+
+    if work_dir != bundle_dir {
+        fs_extra::dir::copy(
+            &work_dir,
+            &bundle_dir,
+            &fs_extra::dir::CopyOptions::new()
+                .content_only(true)
+                .overwrite(true),
+        )?;
+        // the marker only belongs in the cache
+        let _ = fs::remove_file(bundle_dir.join(CACHE_COMPLETE_MARKER));
     }
 
     println!(
@@ -343,6 +398,24 @@ pub async fn download_restore_bundle(
     Ok(bundle_dir)
 }
 
+/// Download the restore bundle from the epoch archive on GitHub
+pub async fn download_restore_bundle(
+    owner: &str,
+    repo: &str,
+    branch: &str,
+    epoch_num: &u64,
+    destination: &Path,
+) -> Result<PathBuf> {
+    let source = source_from_mirror(None, None, owner, repo, branch)?;
+    download_restore_bundle_from_source(
+        source.as_ref(),
+        epoch_num,
+        destination,
+        &DownloadOpts::default(),
+    )
+    .await
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -415,4 +488,29 @@ mod tests {
             "transaction_33000000-.58b4"
         );
     }
+
+    #[test]
+    fn test_select_epoch_folders() {
+        let names: Vec<String> = [
+            "epoch_ending_116-.be9b",
+            "state_epoch_116_ver_38180075.05af",
+            "transaction_38100001-.541f",
+            "transaction_38200001-.aaaa",
+        ]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+
+        let f = select_epoch_folders(&names, 116).unwrap();
+        assert_eq!(f.epoch_ending, "epoch_ending_116-.be9b");
+        assert_eq!(f.state_epoch, "state_epoch_116_ver_38180075.05af");
+        assert_eq!(f.transaction, "transaction_38100001-.541f");
+    }
+
+    #[test]
+    fn test_parse_checksums() {
+        let c = parse_checksums("ABCD  116-.chunk\nef01 *./epoch_ending.manifest\n\n");
+        assert_eq!(c.get("116-.chunk").unwrap(), "abcd");
+        assert_eq!(c.get("epoch_ending.manifest").unwrap(), "ef01");
+    }
 }
@@ -1,4 +1,6 @@
+pub mod archive_source;
 pub mod bootstrap;
+pub mod create_bundle;
 pub mod dbtool_init;
 pub mod download_bundle;
 pub mod parse_folder_names;
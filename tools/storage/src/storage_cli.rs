@@ -7,7 +7,7 @@ use std::path::PathBuf;
 
 // Import the correct functions from libra-config
 
-use crate::{bootstrap, download_bundle, read_snapshot, restore};
+use crate::{archive_source, bootstrap, create_bundle, download_bundle, read_snapshot, restore};
 
 #[derive(Parser)]
 #[clap(author, version, about, long_about = None)]
@@ -37,7 +37,7 @@ pub enum Sub {
         prevent_bootstrap: bool,
     },
     /// downloads the stat, epoch, and transaction
-    /// restore files from the `epoch-archive` repo
+    /// restore files from the `epoch-archive` repo, or a mirror of it
     DownloadRestoreBundle {
         #[clap(long, default_value = "0LNetworkCommunity")]
         /// github organization or user, canonically: "0LNetworkCommunity"
@@ -54,6 +54,41 @@ pub enum Sub {
         #[clap(short, long)]
         /// required, the directory to download the restore bundle to
         destination: PathBuf,
+        #[clap(long)]
+        /// optional, use a mirror instead of github: an http(s):// directory index,
+        /// s3://bucket/prefix, or a local path. Must contain the `snapshots/` directory
+        mirror: Option<String>,
+        #[clap(long)]
+        /// endpoint of the S3-compatible service, for s3:// mirrors
+        s3_endpoint: Option<String>,
+        #[clap(long, default_value = "4")]
+        /// number of files to download in parallel
+        jobs: usize,
+        #[clap(long)]
+        /// optional, keep downloaded bundles here by epoch, and reuse them
+        cache_dir: Option<PathBuf>,
+        #[clap(long)]
+        /// fail if a folder has no SHA256SUMS file to verify the chunks
+        require_checksums: bool,
+    },
+    /// create a restore bundle for one epoch from a local db,
+    /// in the same layout as the `epoch-archive` repo
+    CreateBundle {
+        #[clap(short, long)]
+        /// required, the epoch to back up
+        epoch: u64,
+        #[clap(long)]
+        /// required, path of the db to back up, opened read only
+        db: PathBuf,
+        #[clap(short, long)]
+        /// required, the directory to write the bundle folders to
+        out_dir: PathBuf,
+        #[clap(long, default_value = "100000")]
+        /// versions per transaction backup folder
+        transaction_span: u64,
+        #[clap(long)]
+        /// don't gzip the backup files
+        no_compress: bool,
     },
     /// Read a snapshot, parse and export to JSON
     ExportSnapshot {
@@ -117,16 +152,45 @@ impl StorageCli {
                 branch,
                 epoch,
                 destination,
+                mirror,
+                s3_endpoint,
+                jobs,
+                cache_dir,
+                require_checksums,
             }) => {
-                download_bundle::download_restore_bundle(
+                let source = archive_source::source_from_mirror(
+                    mirror.as_deref(),
+                    s3_endpoint.as_deref(),
                     &owner,
                     &repo,
                     &branch,
+                )?;
+                let opts = download_bundle::DownloadOpts {
+                    jobs,
+                    cache_dir,
+                    require_checksums,
+                };
+                download_bundle::download_restore_bundle_from_source(
+                    source.as_ref(),
                     &epoch,
                     &destination,
+                    &opts,
                 )
                 .await?;
             }
+            Some(Sub::CreateBundle {
+                epoch,
+                db,
+                out_dir,
+                transaction_span,
+                no_compress,
+            }) => {
+                let opts = create_bundle::CreateBundleOpts {
+                    transaction_span,
+                    compress: !no_compress,
+                };
+                create_bundle::create_epoch_bundle(&db, epoch, &out_dir, &opts).await?;
+            }
             Some(Sub::Bootstrap {
                 db_path,
                 home_path,
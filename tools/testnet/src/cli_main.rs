@@ -1,6 +1,6 @@
 use crate::cli_output::TestInfo;
 use crate::restore_helper::one_step_restore_db;
-use crate::{cli_config::TestnetConfigOpts, cli_swarm::SwarmCliOpts};
+use crate::{cli_config::TestnetConfigOpts, cli_swarm::SwarmCliOpts, cli_twin::TwinCliOpts};
 use anyhow::Result;
 use async_trait::async_trait;
 use clap::Subcommand;
@@ -51,6 +51,8 @@ pub enum Sub {
     Configure(TestnetConfigOpts),
     /// start using Diem swarm
     Smoke(SwarmCliOpts),
+    /// twin from a reference db, use --headless to only create the node files
+    Twin(TwinCliOpts),
 }
 
 #[async_trait]
@@ -59,6 +61,7 @@ impl CliCommand<Vec<TestInfo>> for TestnetCli {
         match self.command {
             Sub::Configure(_) => "testnet-configure",
             Sub::Smoke(_) => "testnet-smoke",
+            Sub::Twin(_) => "testnet-twin",
         }
     }
 
@@ -95,6 +98,19 @@ impl CliCommand<Vec<TestInfo>> for TestnetCli {
                     )
                     .await?)
             }
+            Sub::Twin(twin) => {
+                if !twin.headless {
+                    check_bins_path()?;
+                }
+                Ok(twin
+                    .run(
+                        self.framework_mrb_path,
+                        reference_db,
+                        self.json,
+                        self.json_file,
+                    )
+                    .await?)
+            }
         }
     }
 }
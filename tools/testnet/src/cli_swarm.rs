@@ -1,4 +1,7 @@
-use crate::{cli_output::TestInfo, replace_validators_file::set_chain_id_in_app_cfg, twin_swarm};
+use crate::{
+    cli_output::TestInfo, replace_validators_file::set_chain_id_in_app_cfg, scenario::Scenario,
+    twin_swarm,
+};
 use clap::{self, Parser};
 use diem_framework::ReleaseBundle;
 use libra_smoke_tests::libra_smoke::LibraSmoke;
@@ -14,6 +17,10 @@ pub struct SwarmCliOpts {
     /// number of local validators to start
     #[clap(long, short)]
     pub count_vals: Option<u8>,
+    /// path to a scenario yaml file to run once the swarm is live.
+    /// The swarm exits after the scenario, with an error if any step fails
+    #[clap(long)]
+    pub scenario: Option<PathBuf>,
 }
 impl SwarmCliOpts {
     /// Runner for swarm
@@ -34,6 +41,12 @@ impl SwarmCliOpts {
 
         let num_validators = self.count_vals.unwrap_or(2);
 
+        // parse before starting the swarm, so we fail early on a bad file
+        let scenario = match &self.scenario {
+            Some(p) => Some(Scenario::read(p)?),
+            None => None,
+        };
+
         let bundle = if let Some(p) = framework_mrb_path.clone() {
             ReleaseBundle::read(p)?
         } else {
@@ -74,6 +87,11 @@ impl SwarmCliOpts {
             println!("{}", serde_json::to_string_pretty(&out)?);
         }
 
+        if let Some(s) = scenario {
+            s.run(&mut smoke).await?;
+            return Ok(out);
+        }
+
         // NOTE: all validators will stop when the LibraSmoke goes out of context. This is intentional
         // but since it's borrowed in this function you should assume it will continue until the caller goes out of scope.
         let _ = dialoguer::Input::<String>::new()
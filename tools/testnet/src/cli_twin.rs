@@ -0,0 +1,61 @@
+use crate::{cli_output::TestInfo, cli_swarm::SwarmCliOpts, twin_headless};
+use anyhow::bail;
+use diem_genesis::config::HostAndPort;
+use libra_types::global_config_dir;
+use std::path::PathBuf;
+
+#[derive(clap::Parser)]
+/// Twin of a network from a reference db.
+/// With --headless only the node files are created, nothing is started.
+/// Otherwise starts a twin using Diem swarm (same as `smoke`)
+pub struct TwinCliOpts {
+    /// don't start any nodes, just write validator.yaml, db and rescue blob for each node
+    #[clap(long)]
+    pub headless: bool,
+    /// headless: ordered list of dns/ip with port for alice..dave, use :6180 for production validator port
+    #[clap(long, requires = "headless")]
+    pub host: Vec<HostAndPort>,
+    /// headless: where to write the node directories, defaults to $HOME/.libra/twin
+    #[clap(short, long, requires = "headless")]
+    pub out_dir: Option<PathBuf>,
+    /// swarm: number of local validators to start
+    #[clap(long, short, conflicts_with = "headless")]
+    pub count_vals: Option<u8>,
+}
+
+impl TwinCliOpts {
+    pub async fn run(
+        &self,
+        framework_mrb_path: Option<PathBuf>,
+        twin_db: Option<PathBuf>,
+        json_output: bool,
+        json_file: Option<PathBuf>,
+    ) -> anyhow::Result<Vec<TestInfo>> {
+        let Some(reference_db) = twin_db else {
+            bail!("twin needs a reference db, use --twin-reference-db or --twin-epoch-restore");
+        };
+
+        if !self.headless {
+            let swarm = SwarmCliOpts {
+                count_vals: self.count_vals,
+                scenario: None,
+            };
+            return swarm
+                .run(
+                    framework_mrb_path,
+                    Some(reference_db),
+                    json_output,
+                    json_file,
+                )
+                .await;
+        }
+
+        let out_dir = self
+            .out_dir
+            .clone()
+            .unwrap_or_else(|| global_config_dir().join("twin"));
+
+        twin_headless::make_twin_headless(&reference_db, &self.host, &out_dir, framework_mrb_path)
+            .await
+    }
+}
@@ -3,7 +3,7 @@ use anyhow::bail;
 use diem_genesis::config::{HostAndPort, ValidatorConfiguration};
 use libra_backwards_compatibility::legacy_recovery_v6::LegacyRecoveryV6;
 use libra_config::validator_config;
-use libra_genesis_tools::{genesis_builder, parse_json};
+use libra_genesis_tools::{build_manifest::BuildInputs, genesis_builder, parse_json};
 use libra_types::{
     core_types::{app_cfg::CONFIG_FILE_NAME, fixtures::TestPersona},
     exports::{AccountAddress, AuthenticationKey, NamedChain},
@@ -82,7 +82,7 @@ pub async fn setup(
 
     // Determines the path for the recovery data.
     // NOTE: test fixtures located at ./tests/fixtures/sample_export_recovery.json
-    let mut recovery = if let Some(p) = legacy_data_path {
+    let mut recovery = if let Some(p) = legacy_data_path.clone() {
         parse_json::recovery_file_parse(p)?
     } else {
         // this is probably a testnet, we need to minimally start the infra escrow
@@ -92,14 +92,16 @@ pub async fn setup(
 
     // Builds the genesis block with the specified configurations.
     genesis_builder::build(
-        "none".to_string(), // we ignore ceremony coordination for testnet
-        "none".to_string(),
-        "none".to_string(),
+        None, // we ignore ceremony coordination for testnet
         data_dir.clone(),
         framework_mrb_path,
         &mut recovery,
         chain,
         Some(val_cfg),
+        BuildInputs {
+            recovery: legacy_data_path,
+            ..Default::default()
+        },
     )?;
 
     Ok(test_info)
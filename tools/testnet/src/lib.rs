@@ -2,9 +2,12 @@ pub mod cli_config;
 pub mod cli_main;
 pub mod cli_output;
 pub mod cli_swarm;
+pub mod cli_twin;
 pub mod config_twin;
 pub mod config_virgin;
 pub mod find_operators;
 pub mod replace_validators_file;
 pub mod restore_helper;
+pub mod scenario;
+pub mod twin_headless;
 pub mod twin_swarm;
@@ -0,0 +1,325 @@
+//! Scripted scenarios for a running smoke swarm.
+//! A scenario is a YAML file with an ordered list of actions (transactions
+//! sent by the swarm validators) and assertions (view functions and their
+//! expected results). Actions stop the run on the first error, assertions
+//! are all evaluated and reported at the end.
+//!
+//! Example:
+//! ```yaml
+//! name: vouch and transfer
+//! actions:
+//!   - fund: { to: "0x1234", amount: 100 }
+//!   - transfer: { from: 0, to: 1, amount: 10.5 }
+//!   - vouch: { from: 0, to: 1 }
+//!   - pof_bid: { from: 1, bid_pct: 1.0, epoch_expiry: 1000 }
+//!   - trigger_epoch
+//!   - kill: { node: 2 }
+//!   - wait: { secs: 30 }
+//!   - heal: {}
+//!   - assert_view:
+//!       function: 0x1::reconfiguration::get_current_epoch
+//!       expect: ["3"]
+//! ```
+//! Accounts are either the index of a swarm validator, or a quoted address.
+//! Only validators can sign transactions, since their keys are in the
+//! swarm's libra-cli-config.yaml files.
+use anyhow::{anyhow, bail, Context};
+use diem_forge::{Node, Swarm};
+use libra_cached_packages::libra_stdlib;
+use libra_query::query_view;
+use libra_smoke_tests::{helpers, libra_smoke::LibraSmoke};
+use libra_txs::{
+    submit_transaction::Sender, txs_cli_governance::GovernanceTxs, txs_cli_vals::ValidatorTxs,
+};
+use libra_types::{
+    core_types::app_cfg::{AppCfg, CONFIG_FILE_NAME},
+    exports::AccountAddress,
+    move_resource::gas_coin,
+};
+use serde::Deserialize;
+use serde_json::Value;
+use std::{fs, path::Path, path::PathBuf, time::Duration};
+
+/// A list of actions to run against a live swarm
+#[derive(Debug, Deserialize)]
+pub struct Scenario {
+    /// a name for the reports
+    #[serde(default)]
+    pub name: String,
+    /// ordered actions and assertions
+    pub actions: Vec<Action>,
+}
+
+/// Either a swarm validator by its index, or any address
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum AccountRef {
+    Validator(usize),
+    Address(AccountAddress),
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    /// mint coins from the testnet root account
+    Fund { to: AccountRef, amount: f64 },
+    /// transfer coins from a validator
+    Transfer {
+        from: usize,
+        to: AccountRef,
+        amount: f64,
+    },
+    /// vouch (or revoke a vouch) from a validator
+    Vouch {
+        from: usize,
+        to: AccountRef,
+        #[serde(default)]
+        revoke: bool,
+    },
+    /// place a proof-of-fee bid for a validator
+    PofBid {
+        from: usize,
+        bid_pct: f64,
+        epoch_expiry: u64,
+    },
+    /// enable the smoke test trigger and tickle the epoch boundary
+    TriggerEpoch,
+    /// submit a governance proposal from a compiled script directory
+    Propose {
+        from: usize,
+        proposal_script_dir: PathBuf,
+        metadata_url: String,
+    },
+    /// vote on a governance proposal
+    Vote {
+        from: usize,
+        proposal_id: u64,
+        #[serde(default)]
+        should_fail: bool,
+    },
+    /// resolve a governance proposal
+    Resolve {
+        from: usize,
+        proposal_id: u64,
+        proposal_script_dir: PathBuf,
+    },
+    /// pause, for example waiting for blocks
+    Wait { secs: u64 },
+    /// stop a validator process
+    Kill { node: usize },
+    /// start a validator process again
+    Restart { node: usize },
+    /// cut off validators from the network
+    Partition { nodes: Vec<usize> },
+    /// limit the validator network bandwidth of a node
+    Throttle { node: usize, bytes_per_sec: usize },
+    /// delay consensus messages on a node (needs a libra node binary built
+    /// with failpoints, otherwise it does nothing)
+    Lag { node: usize, delay_ms: u64 },
+    /// remove all faults from validators, or all of them if empty
+    Heal {
+        #[serde(default)]
+        nodes: Vec<usize>,
+    },
+    /// call a view function and compare the json result
+    AssertView {
+        function: String,
+        type_args: Option<String>,
+        args: Option<String>,
+        expect: Value,
+    },
+}
+
+impl Scenario {
+    pub fn read(path: &Path) -> anyhow::Result<Self> {
+        let yaml_str = fs::read_to_string(path)
+            .context(format!("cannot read scenario file {}", path.display()))?;
+        serde_yaml::from_str(&yaml_str).context("cannot parse scenario file")
+    }
+
+    /// Run all the actions in order. Returns an error if any action fails
+    /// or if any assertion does not match.
+    pub async fn run(&self, smoke: &mut LibraSmoke) -> anyhow::Result<()> {
+        println!("running scenario: {}", self.name);
+
+        // NOTE: the swarm stores validators in a map, so fix the order once
+        let val_dirs: Vec<PathBuf> = smoke
+            .swarm
+            .validators()
+            .map(|n| n.config_path().parent().unwrap().to_path_buf())
+            .collect();
+        let val_addrs: Vec<AccountAddress> =
+            smoke.swarm.validators().map(|n| n.peer_id()).collect();
+
+        let resolve = |a: &AccountRef| -> anyhow::Result<AccountAddress> {
+            match a {
+                AccountRef::Validator(i) => val_addrs
+                    .get(*i)
+                    .copied()
+                    .context(format!("no validator at index {i}")),
+                AccountRef::Address(addr) => Ok(*addr),
+            }
+        };
+
+        let mut failures: Vec<String> = vec![];
+
+        for (idx, action) in self.actions.iter().enumerate() {
+            println!("step {idx}: {:?}", action);
+            match action {
+                Action::Fund { to, amount } => {
+                    smoke
+                        .mint_and_unlock(resolve(to)?, gas_coin::cast_decimal_to_coin(*amount))
+                        .await?;
+                }
+                Action::Transfer { from, to, amount } => {
+                    let mut sender = validator_sender(&val_dirs, *from).await?;
+                    sender.transfer(resolve(to)?, *amount, false).await?;
+                    check_response(&sender)?;
+                }
+                Action::Vouch { from, to, revoke } => {
+                    let mut sender = validator_sender(&val_dirs, *from).await?;
+                    let payload = if *revoke {
+                        libra_stdlib::vouch_txs_revoke(resolve(to)?)
+                    } else {
+                        libra_stdlib::vouch_txs_vouch_for(resolve(to)?)
+                    };
+                    sender.sign_submit_wait(payload).await?;
+                    check_response(&sender)?;
+                }
+                Action::PofBid {
+                    from,
+                    bid_pct,
+                    epoch_expiry,
+                } => {
+                    let mut sender = validator_sender(&val_dirs, *from).await?;
+                    ValidatorTxs::Pof {
+                        bid_pct: Some(*bid_pct),
+                        epoch_expiry: *epoch_expiry,
+                        retract: false,
+                        net_reward: None,
+                    }
+                    .run(&mut sender)
+                    .await?;
+                    check_response(&sender)?;
+                }
+                Action::TriggerEpoch => {
+                    helpers::enable_epoch_trigger(&mut smoke.swarm.diem_public_info()).await?;
+                    let mut sender = validator_sender(&val_dirs, 0).await?;
+                    GovernanceTxs::EpochBoundary.run(&mut sender).await?;
+                    check_response(&sender)?;
+                }
+                Action::Propose {
+                    from,
+                    proposal_script_dir,
+                    metadata_url,
+                } => {
+                    let mut sender = validator_sender(&val_dirs, *from).await?;
+                    GovernanceTxs::Propose {
+                        proposal_script_dir: proposal_script_dir.to_owned(),
+                        metadata_url: metadata_url.to_owned(),
+                    }
+                    .run(&mut sender)
+                    .await?;
+                    check_response(&sender)?;
+                }
+                Action::Vote {
+                    from,
+                    proposal_id,
+                    should_fail,
+                } => {
+                    let mut sender = validator_sender(&val_dirs, *from).await?;
+                    GovernanceTxs::Vote {
+                        proposal_id: *proposal_id,
+                        should_fail: *should_fail,
+                    }
+                    .run(&mut sender)
+                    .await?;
+                    check_response(&sender)?;
+                }
+                Action::Resolve {
+                    from,
+                    proposal_id,
+                    proposal_script_dir,
+                } => {
+                    let mut sender = validator_sender(&val_dirs, *from).await?;
+                    GovernanceTxs::Resolve {
+                        proposal_id: *proposal_id,
+                        proposal_script_dir: proposal_script_dir.to_owned(),
+                    }
+                    .run(&mut sender)
+                    .await?;
+                    check_response(&sender)?;
+                }
+                Action::Wait { secs } => {
+                    tokio::time::sleep(Duration::from_secs(*secs)).await;
+                }
+                Action::Kill { node } => smoke.kill_validator(*node)?,
+                Action::Restart { node } => smoke.restart_validator(*node)?,
+                Action::Partition { nodes } => smoke.partition(nodes)?,
+                Action::Throttle {
+                    node,
+                    bytes_per_sec,
+                } => smoke.throttle(*node, *bytes_per_sec)?,
+                Action::Lag { node, delay_ms } => smoke.lag(*node, *delay_ms)?,
+                Action::Heal { nodes } => {
+                    if nodes.is_empty() {
+                        smoke.heal_all()?;
+                    } else {
+                        for n in nodes {
+                            smoke.heal(*n)?;
+                        }
+                    }
+                }
+                Action::AssertView {
+                    function,
+                    type_args,
+                    args,
+                    expect,
+                } => {
+                    let res = query_view::get_view(
+                        &smoke.client(),
+                        function,
+                        type_args.to_owned(),
+                        args.to_owned(),
+                    )
+                    .await?;
+                    if &res != expect {
+                        let msg = format!("step {idx}: {function} expected {expect}, got {res}");
+                        println!("FAIL: {msg}");
+                        failures.push(msg);
+                    } else {
+                        println!("PASS: {function} == {res}");
+                    }
+                }
+            }
+        }
+
+        if !failures.is_empty() {
+            bail!(
+                "scenario '{}' failed {} assertion(s):\n{}",
+                self.name,
+                failures.len(),
+                failures.join("\n")
+            );
+        }
+
+        println!("SUCCESS: scenario '{}' completed", self.name);
+        Ok(())
+    }
+}
+
+/// Each swarm validator has a libra-cli-config.yaml with its test private key
+async fn validator_sender(val_dirs: &[PathBuf], idx: usize) -> anyhow::Result<Sender> {
+    let dir = val_dirs
+        .get(idx)
+        .context(format!("no validator at index {idx}"))?;
+    let app_cfg = AppCfg::load(Some(dir.join(CONFIG_FILE_NAME)))?;
+    Sender::from_app_cfg(&app_cfg, None).await
+}
+
+fn check_response(sender: &Sender) -> anyhow::Result<()> {
+    sender
+        .eval_response()
+        .map_err(|status| anyhow!("transaction failed with status: {:?}", status))?;
+    Ok(())
+}
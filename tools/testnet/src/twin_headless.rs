@@ -0,0 +1,187 @@
+//! Prepare the files for a twin network without starting any nodes.
+//! Unlike `awake_frankenswarm` this does not need a Diem swarm or the
+//! DIEM_FORGE_NODE_BIN_PATH, so the output directory can be handed
+//! to systemd units or containers which start the `libra` nodes themselves.
+use crate::{cli_output::TestInfo, replace_validators_file::replace_validators_blob};
+use anyhow::{bail, Context};
+use diem_config::config::{
+    InitialSafetyRulesConfig, NodeConfig, PersistableConfig, SecureBackend, WaypointConfig,
+};
+use diem_genesis::config::HostAndPort;
+use diem_temppath::TempPath;
+use diem_types::waypoint::Waypoint;
+use fs_extra::dir;
+use libra_config::{
+    make_yaml_validator::NODE_YAML_FILE,
+    validator_config,
+    validator_registration::{registration_from_operator_yaml, ValCredentials},
+};
+use libra_rescue::{
+    cli_bootstrapper::one_step_apply_rescue_on_db, cli_main::REPLACE_VALIDATORS_BLOB,
+};
+use libra_types::{
+    core_types::{app_cfg::CONFIG_FILE_NAME, fixtures::TestPersona},
+    exports::NamedChain,
+};
+use libra_wallet::{account_keys, validator_files::OPERATOR_FILE};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Creates a directory per validator (alice, bob, ...) under `out_dir`
+/// with the validator.yaml, key files, a copy of the rescued db and
+/// the rescue blob which the node will use as its genesis.
+/// The reference db is never modified.
+///
+/// NOTE: every node gets the default ports from the validator.yaml template,
+/// so the nodes are expected to run on separate hosts or containers.
+pub async fn make_twin_headless(
+    reference_db: &Path,
+    host_list: &[HostAndPort],
+    out_dir: &Path,
+    framework_mrb_path: Option<PathBuf>,
+) -> anyhow::Result<Vec<TestInfo>> {
+    if host_list.is_empty() {
+        bail!("need at least one --host to create a twin validator set. Exiting.")
+    }
+    if host_list.len() > 4 {
+        bail!("too many hosts provided, there are only four test personas for a twin. Exiting.")
+    }
+    if !reference_db.exists() {
+        bail!(
+            "twin reference db path does not exist: {}",
+            reference_db.display()
+        );
+    }
+
+    fs::create_dir_all(out_dir)?;
+
+    // Step 1: generate the test keys and node configs for each persona
+    println!("Creating validator files for {} nodes", host_list.len());
+    let mut node_dirs: Vec<PathBuf> = vec![];
+    let mut creds: Vec<ValCredentials> = vec![];
+    let mut test_info: Vec<TestInfo> = vec![];
+
+    for (idx, host) in host_list.iter().enumerate() {
+        let p = TestPersona::from(idx)?;
+        let mnem = p.get_persona_mnem();
+        let node_dir = out_dir.join(p.to_string());
+
+        let (_, mut app_cfg) = validator_config::initialize_validator_files(
+            Some(node_dir.clone()),
+            Some(&p.to_string()),
+            host.clone(),
+            Some(mnem.clone()),
+            false,
+            Some(NamedChain::TESTNET),
+        )
+        .await?;
+
+        // DANGER: this is only for testnet
+        let w = account_keys::get_keys_from_mnem(mnem)?;
+        app_cfg
+            .get_profile_mut(None)?
+            .set_private_key(&w.child_0_owner.pri_key);
+        app_cfg.save_file()?;
+
+        creds.push(registration_from_operator_yaml(Some(
+            node_dir.join(OPERATOR_FILE),
+        ))?);
+
+        test_info.push(TestInfo {
+            validator_address: app_cfg.get_profile(None)?.account,
+            val_set_index: idx,
+            data_dir: node_dir.clone(),
+            api_endpoint: host.to_owned(),
+            app_cfg_path: node_dir.join(CONFIG_FILE_NAME),
+        });
+
+        node_dirs.push(node_dir);
+    }
+
+    // Step 2: rescue a scratch copy of the reference db with the new validator set
+    let temp = TempPath::new();
+    temp.create_as_dir()?;
+    let temp_db_path = temp.path();
+
+    println!("Copying reference db to: {}", temp_db_path.display());
+    dir::copy(
+        reference_db,
+        temp_db_path,
+        &dir::CopyOptions::new().content_only(true).overwrite(true),
+    )
+    .context("cannot copy reference db")?;
+
+    println!("Creating rescue blob from the reference db");
+    let rescue_blob_path =
+        replace_validators_blob(temp_db_path, creds, out_dir, framework_mrb_path).await?;
+
+    println!("Applying the rescue blob to the database & bootstrapping");
+    let wp = one_step_apply_rescue_on_db(temp_db_path, &rescue_blob_path)?;
+    println!("Writeset successful, waypoint: {}", wp);
+
+    // Step 3: every node gets the same db, rescue blob and waypoint
+    for node_dir in &node_dirs {
+        println!("Writing node artifacts to: {}", node_dir.display());
+        let destination_db = node_dir.join("data/db");
+        fs::create_dir_all(&destination_db)?;
+        dir::copy(
+            temp_db_path,
+            &destination_db,
+            &dir::CopyOptions::new().content_only(true).overwrite(true),
+        )
+        .context("cannot copy rescued db to node data dir")?;
+
+        let genesis_dir = node_dir.join("genesis");
+        fs::create_dir_all(&genesis_dir)?;
+        let node_blob = genesis_dir.join(REPLACE_VALIDATORS_BLOB);
+        fs::copy(&rescue_blob_path, &node_blob)?;
+        fs::write(genesis_dir.join("waypoint.txt"), wp.to_string())?;
+
+        update_headless_node_config(node_dir, wp, &node_blob)?;
+    }
+
+    println!("Twin artifacts ready at: {}", out_dir.display());
+    Ok(test_info)
+}
+
+/// Same changes as `post_rescue_node_file_updates`, but the node has never
+/// been started so the identity file is in the node home (not the data dir
+/// as in swarm), and the safety rules must be initialized from it.
+fn update_headless_node_config(
+    node_dir: &Path,
+    waypoint: Waypoint,
+    rescue_blob: &Path,
+) -> anyhow::Result<NodeConfig> {
+    let config_path = node_dir.join(NODE_YAML_FILE);
+    let mut node_config = NodeConfig::load_config(&config_path)?;
+
+    node_config.base.waypoint = WaypointConfig::FromConfig(waypoint);
+
+    let data_path = node_config.base.data_dir.clone();
+    if let SecureBackend::OnDiskStorage(_) = node_config.consensus.safety_rules.backend {
+        node_config.consensus.safety_rules.set_data_dir(data_path);
+    }
+
+    let validator_identity_file = node_dir.join("validator-identity.yaml");
+    if !validator_identity_file.exists() {
+        bail!(
+            "validator-identity.yaml not found at {}",
+            validator_identity_file.display()
+        );
+    }
+    node_config
+        .consensus
+        .safety_rules
+        .initial_safety_rules_config = InitialSafetyRulesConfig::from_file(
+        validator_identity_file,
+        WaypointConfig::FromConfig(waypoint),
+    );
+
+    node_config.execution.genesis = None;
+    node_config.execution.genesis_file_location = rescue_blob.to_path_buf();
+
+    node_config.save_to_path(&config_path)?;
+    Ok(node_config)
+}
@@ -0,0 +1,35 @@
+use libra_smoke_tests::libra_smoke::LibraSmoke;
+use libra_testnet::scenario::Scenario;
+
+/// Run a small scenario against a swarm: fund, transfer, new epoch,
+/// and check the epoch with a view function.
+#[tokio::test]
+async fn test_scenario_runs_actions_and_assertions() -> anyhow::Result<()> {
+    let mut smoke = LibraSmoke::new(Some(2), None).await?;
+
+    let yaml = r#"
+name: meta
+actions:
+  - fund: { to: 1, amount: 10 }
+  - transfer: { from: 0, to: "0x1234", amount: 1.5 }
+  - trigger_epoch
+  - assert_view:
+      function: 0x1::reconfiguration::get_current_epoch
+      expect: ["3"]
+"#;
+    let s: Scenario = serde_yaml::from_str(yaml)?;
+    s.run(&mut smoke).await?;
+
+    // a wrong expectation must fail the scenario
+    let yaml = r#"
+name: should fail
+actions:
+  - assert_view:
+      function: 0x1::reconfiguration::get_current_epoch
+      expect: ["100"]
+"#;
+    let s: Scenario = serde_yaml::from_str(yaml)?;
+    assert!(s.run(&mut smoke).await.is_err());
+
+    Ok(())
+}
@@ -0,0 +1,53 @@
+use diem_forge::Node;
+use diem_genesis::config::HostAndPort;
+use diem_temppath::TempPath;
+use libra_rescue::cli_main::REPLACE_VALIDATORS_BLOB;
+use libra_smoke_tests::libra_smoke::LibraSmoke;
+use libra_testnet::twin_headless;
+use std::str::FromStr;
+
+/// Start a swarm to produce a db, then use that db as the reference
+/// for a headless twin. Nothing gets started, we only check the files.
+#[tokio::test]
+async fn test_twin_headless_creates_node_files() -> anyhow::Result<()> {
+    let mut smoke = LibraSmoke::new(Some(1), None).await?;
+    let reference_db = smoke
+        .swarm
+        .validators()
+        .next()
+        .unwrap()
+        .config()
+        .storage
+        .dir();
+    for n in smoke.swarm.validators_mut() {
+        n.stop();
+    }
+
+    let out = TempPath::new();
+    out.create_as_dir()?;
+
+    let hosts = vec![
+        HostAndPort::from_str("127.0.0.1:6180")?,
+        HostAndPort::from_str("127.0.0.2:6180")?,
+        HostAndPort::from_str("127.0.0.3:6180")?,
+    ];
+
+    let info = twin_headless::make_twin_headless(&reference_db, &hosts, out.path(), None).await?;
+    assert_eq!(info.len(), 3);
+
+    for i in info {
+        assert!(i.data_dir.join("validator.yaml").exists());
+        assert!(i.data_dir.join("data/db").exists());
+        assert!(i.data_dir.join("genesis/waypoint.txt").exists());
+        assert!(i
+            .data_dir
+            .join("genesis")
+            .join(REPLACE_VALIDATORS_BLOB)
+            .exists());
+        assert!(i.app_cfg_path.exists());
+    }
+
+    // the reference db should never be touched
+    assert!(reference_db.exists());
+    Ok(())
+}
@@ -10,3 +10,4 @@ pub mod txs_cli_governance;
 pub mod txs_cli_stream;
 pub mod txs_cli_user;
 pub mod txs_cli_vals;
+pub mod validator_rotation;
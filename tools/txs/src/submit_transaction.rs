@@ -24,6 +24,7 @@ use libra_types::{
     type_extensions::{
         cli_config_ext::CliConfigExt,
         client_ext::{ClientExt, DEFAULT_TIMEOUT_SECS},
+        failover_client::FailoverClient,
     },
 };
 use std::{
@@ -40,6 +41,8 @@ pub struct Sender {
     client: Client,
     chain_id: ChainId,
     pub response: Option<TransactionOnChainData>,
+    /// other nodes to resubmit to, if the client's node fails
+    failover: Option<FailoverClient>,
 }
 
 impl Sender {
@@ -81,9 +84,16 @@ impl Sender {
             local_account,
             chain_id,
             response: None,
+            failover: None,
         })
     }
 
+    /// Submit transactions to the other nodes of the playlist when the
+    /// current one doesn't accept them.
+    pub fn set_failover(&mut self, failover: FailoverClient) {
+        self.failover = Some(failover);
+    }
+
     /// Sets the transaction cost for the `Sender`.
     pub fn set_tx_cost(&mut self, cost: &TxCost) {
         cost.clone_into(&mut self.tx_cost);
@@ -105,8 +115,9 @@ impl Sender {
 
         // Create authentication key and initialize client with URL
         let auth_key = AuthenticationKey::ed25519(&key.public_key());
-        let url = &app_cfg.pick_url(None)?;
-        let client = Client::new(url.clone());
+        let failover = FailoverClient::from_app_cfg(app_cfg, None)?;
+        let (client, _) = failover.connect().await?;
+        let url = failover.current_url().to_owned();
         let address = client
             .lookup_originating_address(auth_key)
             .await
@@ -128,13 +139,15 @@ impl Sender {
             Err(_) => bail!("cannot connect to client at {:?}", &url),
         };
 
-        let s = Sender {
+        let mut s = Sender {
             client,
             tx_cost: app_cfg.tx_configs.get_cost(None),
             local_account,
             chain_id,
             response: None,
+            failover: None,
         };
+        s.set_failover(failover);
 
         Ok(s)
     }
@@ -190,6 +203,7 @@ impl Sender {
                 local_account,
                 chain_id,
                 response: None,
+                failover: None,
             };
             return Ok(s);
         }
@@ -256,17 +270,30 @@ impl Sender {
         &mut self,
         signed_trans: &SignedTransaction,
     ) -> anyhow::Result<TransactionOnChainData> {
-        let pending_trans = self.client.submit(signed_trans).await?.into_inner();
-
-        info!("pending tx hash: {}", &pending_trans.hash.to_string());
-
-        let res = self
-            .client
-            .wait_for_transaction_bcs(&pending_trans)
-            .await?
-            .into_inner();
-
-        Ok(res)
+        match &self.failover {
+            // only fail over until a node accepts the transaction. After that
+            // it's only waited for on that node, a resubmission could fail
+            // after the first one executed.
+            Some(f) => {
+                let pending = f
+                    .run(|c| async move { Ok(c.submit(signed_trans).await?.into_inner()) })
+                    .await?;
+                self.client = f.client();
+                info!("pending tx hash: {}", &pending.hash.to_string());
+                let res = self
+                    .client
+                    .wait_for_transaction_bcs(&pending)
+                    .await
+                    .context(format!(
+                        "transaction {} was submitted to {}, but not confirmed",
+                        pending.hash,
+                        f.current_url()
+                    ))?
+                    .into_inner();
+                Ok(res)
+            }
+            None => submit_and_wait(&self.client, signed_trans).await,
+        }
     }
 
     /// Evaluates the response of the last submitted transaction.
@@ -312,3 +339,19 @@ impl Sender {
         &self.client
     }
 }
+
+async fn submit_and_wait(
+    client: &Client,
+    signed_trans: &SignedTransaction,
+) -> anyhow::Result<TransactionOnChainData> {
+    let pending_trans = client.submit(signed_trans).await?.into_inner();
+
+    info!("pending tx hash: {}", &pending_trans.hash.to_string());
+
+    let res = client
+        .wait_for_transaction_bcs(&pending_trans)
+        .await?
+        .into_inner();
+
+    Ok(res)
+}
@@ -15,6 +15,7 @@ use indoc::indoc;
 use libra_types::{
     core_types::app_cfg::{AppCfg, TxCost, TxType},
     exports::{ChainId, NamedChain},
+    type_extensions::failover_client::FailoverClient,
 };
 use libra_wallet::account_keys::{get_keys_from_mnem, get_keys_from_prompt};
 use std::path::PathBuf;
@@ -144,7 +145,10 @@ impl TxsCli {
     /// Executes the transaction CLI command based on parsed arguments.
     pub async fn run(&self) -> Result<()> {
         // Load application configuration
-        let app_cfg = AppCfg::load(self.config_path.clone())?;
+        let (app_cfg, notes) = AppCfg::load_with_notes(self.config_path.clone())?;
+        if let Some(n) = notes {
+            println!("{}", n);
+        }
         let profile = app_cfg.get_profile(None)?;
 
         // Determine private key based on CLI options or prompts
@@ -168,15 +172,15 @@ impl TxsCli {
             .chain_name
             .unwrap_or(app_cfg.workspace.default_chain_id);
 
-        let url = if let Some(u) = self.url.as_ref() {
-            u.to_owned()
+        // Initialize client, with failover to the playlist unless a url is set
+        let (client, failover) = if let Some(u) = self.url.as_ref() {
+            (Client::new(u.to_owned()), None)
         } else {
-            app_cfg.pick_url(Some(chain_name))?
+            let failover = FailoverClient::from_app_cfg(&app_cfg, Some(chain_name))?;
+            let (client, _) = failover.connect().await?;
+            (client, Some(failover))
         };
 
-        // Initialize client
-        let client = Client::new(url);
-
         // Initialize sender
         let mut send = Sender::new(
             AccountKey::from_private_key(pri_key),
@@ -185,6 +189,9 @@ impl TxsCli {
             self.legacy_address,
         )
         .await?;
+        if let Some(f) = failover {
+            send.set_failover(f);
+        }
 
         // Handle mutually exclusive options for transaction cost
         if self.tx_cost.is_some() && self.tx_profile.is_some() {
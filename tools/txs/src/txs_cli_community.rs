@@ -4,7 +4,7 @@ use crate::submit_transaction::Sender;
 use diem_logger::error;
 use diem_types::account_address::AccountAddress;
 use libra_cached_packages::libra_stdlib;
-use libra_query::{account_queries, query_view};
+use libra_query::{account_queries, donor_voice_queries, query_view};
 use libra_types::{exports::Client, move_resource::gas_coin};
 use serde::{Deserialize, Serialize};
 use std::{collections::HashMap, fs, path::PathBuf};
@@ -30,6 +30,8 @@ pub enum CommunityTxs {
     Veto(VetoTx),
     /// Donor can vote in reauthorization poll
     Reauthorize(ReauthVoteTx),
+    /// Donors can propose and vote to liquidate a Donor Voice account
+    Liquidate(LiquidateTx),
 }
 
 impl CommunityTxs {
@@ -63,6 +65,10 @@ impl CommunityTxs {
             CommunityTxs::Reauthorize(tx) => {
                 tx.run(sender).await.map(|_| "reauthorize vote submitted")
             }
+            CommunityTxs::Liquidate(tx) => tx.run(sender).await.map(|_| match tx.action {
+                LiquidateAction::Propose { .. } => "liquidation proposed",
+                LiquidateAction::Vote { .. } => "liquidation vote submitted",
+            }),
         };
 
         match result {
@@ -517,6 +523,92 @@ impl ReauthVoteTx {
     }
 }
 
+#[derive(clap::Args)]
+pub struct LiquidateTx {
+    #[clap(subcommand)]
+    pub action: LiquidateAction,
+}
+
+#[derive(clap::Subcommand)]
+pub enum LiquidateAction {
+    /// Propose to liquidate the account, donors then vote on the ballot
+    Propose {
+        #[clap(short, long)]
+        /// The Donor Voice account to liquidate
+        community_wallet: AccountAddress,
+    },
+    /// Vote on a liquidation which was already proposed
+    Vote {
+        #[clap(short, long)]
+        /// The Donor Voice account to liquidate
+        community_wallet: AccountAddress,
+    },
+}
+
+impl LiquidateTx {
+    pub async fn run(&self, sender: &mut Sender) -> anyhow::Result<()> {
+        let (payload, community_wallet) = match &self.action {
+            LiquidateAction::Propose { community_wallet } => (
+                libra_stdlib::donor_voice_txs_propose_liquidate_tx(*community_wallet),
+                community_wallet,
+            ),
+            LiquidateAction::Vote { community_wallet } => (
+                libra_stdlib::donor_voice_txs_vote_liquidation_tx(*community_wallet),
+                community_wallet,
+            ),
+        };
+        sender.sign_submit_wait(payload).await?;
+
+        let report =
+            donor_voice_queries::community_wallet_liquidation(sender.client(), *community_wallet)
+                .await?;
+        display_liquidation_report(&report);
+        Ok(())
+    }
+}
+
+/// Displays the liquidation tally and the refunds in a readable format
+fn display_liquidation_report(report: &donor_voice_queries::LiquidationReport) {
+    match &report.tally {
+        Some(t) => {
+            println!("\nLiquidation Poll Status (ballot {}):", t.ballot_id);
+            println!("------------------------------");
+            println!("Approval Rate:       {:.2}%", t.approval_pct as f64 / 100.0);
+            println!("Voter Turnout:       {:.2}%", t.turnout_pct as f64 / 100.0);
+            println!(
+                "Approval Threshold:  {:.2}%",
+                t.threshold_pct as f64 / 100.0
+            );
+            println!(
+                "Minimum Turnout:     {:.2}%",
+                t.minimum_turnout_pct as f64 / 100.0
+            );
+            println!("Epoch Deadline:      {}", t.epoch_deadline);
+            println!(
+                "Poll Complete:       {}",
+                if t.is_complete { "Yes" } else { "No" }
+            );
+        }
+        None => println!("\nNo pending liquidation ballot"),
+    }
+
+    if !report.refunds_to_donors {
+        println!("\nIf liquidated, the funds go to the match index, not back to donors");
+        return;
+    }
+    println!(
+        "\nPro-rata refunds of the balance {:.2}, if liquidated now:",
+        gas_coin::cast_coin_to_decimal(report.balance)
+    );
+    for r in &report.refunds {
+        println!(
+            "  {}: {:.2}",
+            r.donor,
+            gas_coin::cast_coin_to_decimal(r.refund)
+        );
+    }
+}
+
 /// Fetches pending reauthorization ballot IDs for a community wallet
 async fn fetch_pending_reauth_ballots(
     client: &Client,
@@ -5,16 +5,20 @@ use dialoguer::Confirm;
 use diem::common::types::RotationProofChallenge;
 use diem_sdk::{
     crypto::{ed25519::Ed25519PublicKey, PrivateKey, SigningKey, ValidCryptoMaterialStringExt},
+    rest_client::Client,
     types::LocalAccount,
 };
 use diem_types::{
     account_address::AccountAddress, account_config::CORE_CODE_ADDRESS,
     transaction::TransactionPayload,
 };
-use libra_cached_packages::libra_stdlib;
+use libra_cached_packages::{libra_framework_views, libra_stdlib};
 use libra_query;
+use libra_query::pledge_queries::pledges_report;
 use libra_types::{
+    core_types::ol_features,
     exports::{AuthenticationKey, Ed25519PrivateKey},
+    move_resource::gas_coin,
     type_extensions::client_ext::ClientExt,
 };
 use libra_wallet::account_keys::get_keys_from_prompt;
@@ -27,6 +31,10 @@ pub enum UserTxs {
     RotateKey(RotateKeyTx),
     ReJoin,
     Vouch(VouchTx),
+    RefreshTrust(RefreshTrustTx),
+    BurnPreference(BurnPreferenceTx),
+    Migrate(MigrateTx),
+    Pledge(PledgeTx),
 }
 
 impl UserTxs {
@@ -63,6 +71,21 @@ impl UserTxs {
             UserTxs::Vouch(vouch_tx) => {
                 vouch_tx.run(sender).await?;
             }
+            UserTxs::RefreshTrust(refresh) => {
+                refresh.run(sender).await?;
+            }
+            UserTxs::Pledge(pledge) => {
+                pledge.run(sender).await?;
+            }
+            UserTxs::Migrate(migrate) => {
+                migrate.run(sender).await?;
+            }
+            UserTxs::BurnPreference(pref) => match pref.run(sender).await {
+                Ok(_) => println!("SUCCESS: burn preference set"),
+                Err(e) => {
+                    println!("ERROR: could not set burn preference, message: {}", e);
+                }
+            },
         }
 
         Ok(())
@@ -84,6 +107,272 @@ impl SetSlowTx {
     }
 }
 
+/// Check if the account needs a v7 to v8 structural migration, and submit
+/// the migrations which apply.
+#[derive(clap::Args)]
+pub struct MigrateTx {
+    #[clap(long)]
+    /// Only inspect the account and simulate the migrations
+    dry_run: bool,
+    #[clap(short, long)]
+    /// Account to inspect, otherwise the sender's account. Only the sender's
+    /// account can be migrated.
+    account: Option<AccountAddress>,
+}
+
+/// an entry function which repairs or migrates an account
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Migration {
+    /// filo_migration::maybe_migrate, for accounts created before v8
+    Filo,
+    /// activity::maybe_fix_malformed, for timestamps recorded in microseconds
+    FixMalformedActivity,
+}
+
+impl Migration {
+    pub fn payload(&self) -> TransactionPayload {
+        match self {
+            Migration::Filo => libra_stdlib::filo_migration_maybe_migrate(),
+            Migration::FixMalformedActivity => libra_stdlib::activity_maybe_fix_malformed(),
+        }
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            Migration::Filo => "migrate the pre-v8 account (filo_migration::maybe_migrate)",
+            Migration::FixMalformedActivity => {
+                "fix the malformed activity timestamps (activity::maybe_fix_malformed)"
+            }
+        }
+    }
+}
+
+/// same threshold as activity::is_timestamp_secs
+const MALFORMED_TIMESTAMP: u64 = 1_000_000_000_000;
+
+/// The v8 state of an account, from the view functions
+#[derive(Debug, Default)]
+pub struct MigrationStatus {
+    pub account: AccountAddress,
+    pub is_donor_voice: bool,
+    pub activity_initialized: bool,
+    pub malformed_activity: bool,
+    pub is_founder: bool,
+    pub has_friends: bool,
+    pub v8_authorized: bool,
+}
+
+impl MigrationStatus {
+    pub async fn fetch(client: &Client, account: AccountAddress) -> anyhow::Result<Self> {
+        let mut status = MigrationStatus {
+            account,
+            is_donor_voice: libra_framework_views::donor_voice_is_donor_voice(client, account)
+                .await?,
+            activity_initialized: libra_framework_views::activity_is_initialized(client, account)
+                .await?,
+            is_founder: libra_framework_views::founder_is_founder(client, account).await?,
+            v8_authorized: libra_framework_views::reauthorization_is_v8_authorized(client, account)
+                .await?,
+            ..Default::default()
+        };
+        if status.activity_initialized {
+            let last_touch =
+                libra_framework_views::activity_get_last_touch_usecs(client, account).await?;
+            let onboarding =
+                libra_framework_views::activity_get_onboarding_usecs(client, account).await?;
+            status.malformed_activity =
+                last_touch > MALFORMED_TIMESTAMP || onboarding > MALFORMED_TIMESTAMP;
+        }
+        if status.is_founder {
+            status.has_friends =
+                libra_framework_views::founder_has_friends(client, account).await?;
+        }
+        Ok(status)
+    }
+
+    /// The migrations which apply, in the order they should be submitted
+    pub fn required(&self) -> Vec<Migration> {
+        // donor voice accounts are reauthorized by their donors instead
+        if self.is_donor_voice {
+            return vec![];
+        }
+        // NOTE: maybe_migrate resets the slow wallet, so never run it again
+        // on an account which already has a Founder record
+        if !self.v8_authorized && !self.is_founder {
+            // the filo migration also fixes malformed timestamps
+            return vec![Migration::Filo];
+        }
+        if self.activity_initialized && self.malformed_activity {
+            return vec![Migration::FixMalformedActivity];
+        }
+        vec![]
+    }
+
+    pub fn print(&self) {
+        println!("Account: {}", self.account);
+        println!("  • Donor Voice account: {}", self.is_donor_voice);
+        println!("  • Activity initialized: {}", self.activity_initialized);
+        println!(
+            "  • Malformed activity timestamps: {}",
+            self.malformed_activity
+        );
+        println!("  • Founder (pre-v8 account): {}", self.is_founder);
+        if self.is_founder {
+            println!("  • Founder has human friends: {}", self.has_friends);
+        }
+        println!("  • V8 authorized: {}", self.v8_authorized);
+    }
+}
+
+impl MigrateTx {
+    pub async fn run(&self, sender: &mut Sender) -> anyhow::Result<()> {
+        let sender_address = sender.local_account.address();
+        let account = self.account.unwrap_or(sender_address);
+        let status = MigrationStatus::fetch(sender.client(), account).await?;
+        status.print();
+
+        let migrations = status.required();
+        if migrations.is_empty() {
+            if status.is_donor_voice && !status.v8_authorized {
+                println!(
+                    "\nDonor Voice accounts are reauthorized by a vote of the donors, see `txs community reauthorize`"
+                );
+            } else if status.is_founder && !status.has_friends {
+                println!(
+                    "\nAccount is migrated, but needs vouches from trusted accounts to be authorized, see `txs user vouch`"
+                );
+            } else {
+                println!("\nNo migration needed");
+            }
+            return Ok(());
+        }
+
+        println!("\nMigrations required:");
+        for m in &migrations {
+            println!("  • {}", m.description());
+        }
+
+        if account != sender_address {
+            println!(
+                "\nonly the account owner can migrate {}, not simulating",
+                account
+            );
+            return Ok(());
+        }
+
+        // simulating signs with the next sequence number, reset it after each
+        let seq = sender.local_account.sequence_number();
+        let mut will_fail = false;
+        for m in &migrations {
+            let res = sender.estimate(m.payload()).await;
+            *sender.local_account.sequence_number_mut() = seq;
+            match res?.first() {
+                Some(tx) if tx.info.success => {
+                    println!(
+                        "simulated: {}, gas used: {}",
+                        m.description(),
+                        tx.info.gas_used
+                    )
+                }
+                Some(tx) => {
+                    println!(
+                        "simulation failed: {}, status: {}",
+                        m.description(),
+                        tx.info.vm_status
+                    );
+                    will_fail = true;
+                }
+                None => anyhow::bail!("no simulation result for: {}", m.description()),
+            }
+        }
+
+        if will_fail {
+            anyhow::bail!("migration simulation failed, no transactions were sent");
+        }
+        if self.dry_run {
+            return Ok(());
+        }
+
+        for m in &migrations {
+            println!("\nExecuting: {}", m.description());
+            sender.sign_submit_wait(m.payload()).await?;
+        }
+
+        println!("\n=== AFTER MIGRATION ===");
+        MigrationStatus::fetch(sender.client(), account)
+            .await?
+            .print();
+        Ok(())
+    }
+}
+
+/// Pledge unlocked coins to the infra escrow. The first pledge creates the
+/// pledge account, the next ones top it up.
+/// NOTE: the framework has no entry function to pledge to other
+/// beneficiaries, or for the pledger to withdraw. Pledged coins are only
+/// withdrawn by the beneficiary, or returned when the pledgers revoke the
+/// policy.
+#[derive(clap::Args)]
+pub struct PledgeTx {
+    #[clap(short, long)]
+    /// Amount of coins to pledge, in decimal
+    amount: f64,
+}
+
+impl PledgeTx {
+    pub async fn run(&self, sender: &mut Sender) -> anyhow::Result<()> {
+        if !libra_framework_views::features_is_enabled(
+            sender.client(),
+            ol_features::USER_PLEDGE_INFRA,
+        )
+        .await?
+        {
+            anyhow::bail!(
+                "the deployed framework doesn't allow user pledges to the infra escrow yet, the feature flag {} is not enabled",
+                ol_features::USER_PLEDGE_INFRA
+            );
+        }
+
+        let amount = gas_coin::cast_decimal_to_coin(self.amount);
+        let payload = libra_stdlib::infra_escrow_user_pledge_infra(amount);
+        sender.sign_submit_wait(payload).await?;
+
+        let report = pledges_report(sender.client(), sender.local_account.address()).await?;
+        if let Some(p) = report
+            .pledges
+            .iter()
+            .find(|p| p.beneficiary == AccountAddress::ONE)
+        {
+            println!(
+                "SUCCESS: pledged to the infra escrow: {}, lifetime: {}",
+                gas_coin::cast_coin_to_decimal(p.amount),
+                gas_coin::cast_coin_to_decimal(p.lifetime_pledged)
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Choose whether the fees attributed to the account are recycled to the
+/// community or burned, when they are not spent on subsidies.
+#[derive(clap::Args)]
+pub struct BurnPreferenceTx {
+    #[clap(long, required_unless_present = "burn", conflicts_with = "burn")]
+    /// Recycle the fees to the community wallets, through the match index
+    pub send_community: bool,
+    #[clap(long)]
+    /// Burn the fees, this is the default
+    pub burn: bool,
+}
+
+impl BurnPreferenceTx {
+    pub async fn run(&self, sender: &mut Sender) -> anyhow::Result<()> {
+        let payload = libra_stdlib::burn_set_send_community(self.send_community);
+        sender.sign_submit_wait(payload).await?;
+        Ok(())
+    }
+}
+
 /// Rotate an account's keys. Either you are a) rotating your existing account's
 /// keys, or b) claiming someone else's account.
 #[derive(clap::Args)]
@@ -337,12 +626,15 @@ pub fn revoke_rotation_capability(
 #[derive(clap::Args)]
 /// Vouch for accounts
 pub struct VouchTx {
-    #[clap(short, long)]
+    #[clap(short, long, required_unless_present = "clean_expired")]
     /// Vouch for another account, usually for validators
-    vouch_for: AccountAddress,
-    #[clap(short, long)]
+    vouch_for: Option<AccountAddress>,
+    #[clap(short, long, requires = "vouch_for")]
     /// Revoke a vouch for an account
     revoke: bool,
+    #[clap(long, conflicts_with = "vouch_for")]
+    /// Remove the expired vouches given and received by this account
+    clean_expired: bool,
 }
 
 impl VouchTx {
@@ -351,62 +643,109 @@ impl VouchTx {
 
         // Show vouch reports BEFORE the transaction
         println!("\n=== BEFORE TRANSACTION ===");
-        println!("\nSender account vouch report:");
-        if let Err(e) = libra_query::account_queries::account_vouch_report_console(
-            sender.client(),
-            sender_address,
-        )
-        .await
-        {
-            println!("Could not get sender vouch report: {}", e);
-        }
-
-        println!("\nTarget account vouch report:");
-        if let Err(e) = libra_query::account_queries::account_vouch_report_console(
-            sender.client(),
-            self.vouch_for,
-        )
-        .await
-        {
-            println!("Could not get target account vouch report: {}", e);
+        print_vouch_report(sender, "Sender account", sender_address).await;
+        if let Some(target) = self.vouch_for {
+            print_vouch_report(sender, "Target account", target).await;
         }
 
         // Execute the transaction
-        let action = if self.revoke { "revoke" } else { "vouch for" };
-        println!(
-            "\nExecuting transaction to {} address {}...",
-            action, self.vouch_for
-        );
-
-        let payload = if self.revoke {
-            libra_stdlib::vouch_txs_revoke(self.vouch_for)
-        } else {
-            libra_stdlib::vouch_txs_vouch_for(self.vouch_for)
+        let payload = match self.vouch_for {
+            Some(target) => {
+                let action = if self.revoke { "revoke" } else { "vouch for" };
+                println!(
+                    "\nExecuting transaction to {} address {}...",
+                    action, target
+                );
+                if self.revoke {
+                    libra_stdlib::vouch_txs_revoke(target)
+                } else {
+                    libra_stdlib::vouch_txs_vouch_for(target)
+                }
+            }
+            None => {
+                println!("\nExecuting transaction to clean expired vouches...");
+                libra_stdlib::vouch_txs_clean_expired()
+            }
         };
         sender.sign_submit_wait(payload).await?;
 
         // Show vouch reports AFTER the transaction
         println!("\n=== AFTER TRANSACTION ===");
-        println!("\nSender account vouch report:");
-        if let Err(e) = libra_query::account_queries::account_vouch_report_console(
-            sender.client(),
-            sender_address,
-        )
-        .await
-        {
-            println!("Could not get sender vouch report: {}", e);
+        print_vouch_report(sender, "Sender account", sender_address).await;
+        if let Some(target) = self.vouch_for {
+            print_vouch_report(sender, "Target account", target).await;
         }
 
-        println!("\nTarget account vouch report:");
-        if let Err(e) = libra_query::account_queries::account_vouch_report_console(
-            sender.client(),
-            self.vouch_for,
-        )
-        .await
-        {
-            println!("Could not get target account vouch report: {}", e);
+        Ok(())
+    }
+}
+
+#[derive(clap::Args)]
+/// Recalculate the cached trust score of accounts
+pub struct RefreshTrustTx {
+    #[clap(short, long, conflicts_with = "vouched")]
+    /// Account to refresh, otherwise the sender's account
+    account: Option<AccountAddress>,
+    #[clap(long)]
+    /// Refresh every account this account vouched for
+    vouched: bool,
+}
+
+impl RefreshTrustTx {
+    pub async fn run(&self, sender: &mut Sender) -> anyhow::Result<()> {
+        let sender_address = sender.local_account.address();
+        let accounts = if self.vouched {
+            let (given, _) =
+                libra_framework_views::vouch_get_given_vouches(sender.client(), sender_address)
+                    .await?;
+            if given.is_empty() {
+                println!("no vouches given by {}, nothing to refresh", sender_address);
+                return Ok(());
+            }
+            given
+        } else {
+            vec![self.account.unwrap_or(sender_address)]
+        };
+
+        // keep refreshing the others if one fails, the account may have no trust record
+        let mut failed = vec![];
+        for account in &accounts {
+            println!("\n=== BEFORE REFRESH ===");
+            print_vouch_report(sender, "Account", *account).await;
+
+            println!(
+                "\nExecuting transaction to refresh trust score of {}...",
+                account
+            );
+            let payload = libra_stdlib::page_rank_lazy_refresh_cache(*account);
+            if let Err(e) = sender.sign_submit_wait(payload).await {
+                println!("ERROR: could not refresh {}, message: {}", account, e);
+                failed.push(*account);
+                continue;
+            }
+
+            println!("\n=== AFTER REFRESH ===");
+            print_vouch_report(sender, "Account", *account).await;
         }
 
+        if !failed.is_empty() {
+            anyhow::bail!(
+                "could not refresh {} of {} accounts: {:?}",
+                failed.len(),
+                accounts.len(),
+                failed
+            );
+        }
         Ok(())
     }
 }
+
+/// print the vouch report of an account, or why it's not available
+async fn print_vouch_report(sender: &Sender, label: &str, account: AccountAddress) {
+    println!("\n{} vouch report:", label);
+    if let Err(e) =
+        libra_query::account_queries::account_vouch_report_console(sender.client(), account).await
+    {
+        println!("Could not get {} vouch report: {}", label.to_lowercase(), e);
+    }
+}
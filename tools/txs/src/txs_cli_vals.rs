@@ -1,6 +1,9 @@
 //! Validator subcommands
 
-use crate::submit_transaction::Sender;
+use crate::{
+    submit_transaction::Sender,
+    validator_rotation::{self, DEFAULT_EPOCH_WAIT_SECS},
+};
 use anyhow::{bail, Context};
 use diem_genesis::config::OperatorConfiguration;
 use diem_types::account_address::AccountAddress;
@@ -12,7 +15,7 @@ use libra_cached_packages::libra_stdlib::EntryFunctionCall::{
 use libra_config::validator_registration;
 use libra_types::global_config_dir;
 use libra_wallet::validator_files::OPERATOR_FILE;
-use std::{fs, path::PathBuf};
+use std::{fs, path::PathBuf, time::Duration};
 
 #[derive(clap::Subcommand)]
 pub enum ValidatorTxs {
@@ -54,10 +57,40 @@ pub enum ValidatorTxs {
         /// optional, Path to files with registration files
         operator_file: Option<PathBuf>,
     },
+    /// Rotate the consensus key and network identity of a running validator.
+    /// Stages new keys next to the old files (unless `libra wallet validator
+    /// rotate-keys` already did), sends them to the chain, and switches the
+    /// node files after the next epoch. Run again to resume.
+    RotateKeys {
+        /// validator home with operator.yaml, defaults to $HOME/.libra
+        #[clap(long)]
+        home_path: Option<PathBuf>,
+        /// how long to wait for the next epoch, in seconds
+        #[clap(long, default_value_t = DEFAULT_EPOCH_WAIT_SECS)]
+        max_wait_secs: u64,
+        /// send the keys from before the last rotation again, and restore
+        /// their files
+        #[clap(long)]
+        rollback: bool,
+    },
 }
 
 impl ValidatorTxs {
     pub async fn run(&self, sender: &mut Sender) -> anyhow::Result<()> {
+        if let ValidatorTxs::RotateKeys {
+            home_path,
+            max_wait_secs,
+            rollback,
+        } = self
+        {
+            let home = home_path.clone().unwrap_or_else(global_config_dir);
+            let max_wait = Duration::from_secs(*max_wait_secs);
+            return if *rollback {
+                validator_rotation::rollback(sender, &home, max_wait).await
+            } else {
+                validator_rotation::rotate_keys(sender, &home, max_wait).await
+            };
+        }
         let payload = self.make_payload()?;
         sender.sign_submit_wait(payload.encode()).await?;
         Ok(())
@@ -140,6 +173,9 @@ impl ValidatorTxs {
                     new_fullnode_addresses: bcs::to_bytes(&vec![vfn_fullnode_protocol])?,
                 }
             }
+            ValidatorTxs::RotateKeys { .. } => {
+                bail!("rotate-keys sends several transactions, it has no single payload")
+            }
         };
 
         Ok(p)
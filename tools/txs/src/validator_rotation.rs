@@ -0,0 +1,230 @@
+//! Send the keys of a validator key rotation to the chain.
+//! The files of a rotation are staged and switched by
+//! libra_wallet::validator_rotation, this sends the `stake` transactions and
+//! only switches the node files once the new validator set has the new keys.
+
+use crate::submit_transaction::Sender;
+use anyhow::{anyhow, bail, Context, Result};
+use diem_genesis::config::OperatorConfiguration;
+use diem_sdk::types::transaction::TransactionPayload;
+use diem_types::on_chain_config::ValidatorSet;
+use libra_cached_packages::{libra_framework_views as views, libra_stdlib};
+use libra_types::{core_types::ol_features, exports::AccountAddress};
+use libra_wallet::{
+    account_keys::get_keys_from_prompt,
+    utils::read_operator_file,
+    validator_files::OPERATOR_FILE,
+    validator_rotation::{
+        activate, restore_previous, rotation_dir, stage_next, RotationStage, RotationState,
+        PREVIOUS_DIR,
+    },
+};
+use std::{
+    path::Path,
+    time::{Duration, SystemTime},
+};
+
+/// how often to check for the new epoch
+const EPOCH_POLL_SECS: u64 = 30;
+/// by default wait a bit over a day, the longest epochs are 24 hours
+pub const DEFAULT_EPOCH_WAIT_SECS: u64 = 26 * 60 * 60;
+
+/// the transactions which set the keys and addresses of the operator file
+pub fn update_payloads(oc: &OperatorConfiguration) -> Result<Vec<TransactionPayload>> {
+    let validator_address: AccountAddress = oc.operator_account_address.into();
+    let val_addr = oc
+        .validator_host
+        .as_network_address(oc.validator_network_public_key)?;
+    let vfn_addr = oc
+        .full_node_host
+        .as_ref()
+        .context("cannot find fullnode host in operator config file")?
+        .as_network_address(
+            oc.full_node_network_public_key
+                .context("cannot find fullnode network public key in operator config file")?,
+        )?;
+
+    Ok(vec![
+        libra_stdlib::stake_rotate_consensus_key(
+            validator_address,
+            oc.consensus_public_key.to_bytes().to_vec(),
+            oc.consensus_proof_of_possession.to_bytes().to_vec(),
+        ),
+        libra_stdlib::stake_update_network_and_fullnode_addresses(
+            validator_address,
+            bcs::to_bytes(&vec![val_addr])?,
+            bcs::to_bytes(&vec![vfn_addr])?,
+        ),
+    ])
+}
+
+async fn epoch(sender: &Sender) -> Result<u64> {
+    Ok(sender.client().get_index().await?.into_inner().epoch.into())
+}
+
+/// send the keys and addresses of the operator file, returns the epoch
+async fn publish(sender: &mut Sender, oc: &OperatorConfiguration) -> Result<u64> {
+    if !views::features_is_enabled(sender.client(), ol_features::CONSENSUS_KEY_ROTATION).await? {
+        bail!("the deployed framework doesn't allow consensus key rotation yet, the feature flag {} is not enabled", ol_features::CONSENSUS_KEY_ROTATION);
+    }
+    let epoch = epoch(sender).await?;
+    for p in update_payloads(oc)? {
+        sender.sign_submit_wait(p).await?;
+        sender
+            .eval_response()
+            .map_err(|s| anyhow!("transaction failed: {:?}", s))?;
+    }
+    Ok(epoch)
+}
+
+/// Wait for an epoch after `submitted_epoch`, and check the active validator
+/// set has the consensus key of the operator file
+async fn wait_for_keys(
+    sender: &Sender,
+    oc: &OperatorConfiguration,
+    submitted_epoch: u64,
+    max_wait: Duration,
+) -> Result<bool> {
+    let account: AccountAddress = oc.operator_account_address.into();
+    let start = SystemTime::now();
+    loop {
+        let epoch = epoch(sender).await?;
+        if epoch > submitted_epoch {
+            let val_set = sender
+                .client()
+                .get_account_resource_bcs::<ValidatorSet>(
+                    AccountAddress::ONE,
+                    "0x1::stake::ValidatorSet",
+                )
+                .await?
+                .into_inner();
+            let active = val_set
+                .active_validators
+                .iter()
+                .find(|v| v.account_address() == &account)
+                .context(format!(
+                    "{} is not in the active validator set of epoch {}",
+                    account, epoch
+                ))?;
+            return Ok(active.consensus_public_key() == &oc.consensus_public_key);
+        }
+        if start.elapsed()? > max_wait {
+            return Ok(false);
+        }
+        println!(
+            "epoch {}, waiting for epoch {}...",
+            epoch,
+            submitted_epoch + 1
+        );
+        tokio::time::sleep(Duration::from_secs(EPOCH_POLL_SECS)).await;
+    }
+}
+
+/// Rotate the validator keys in `home`, or resume an interrupted rotation.
+/// Sends the new keys, waits for the next epoch, then switches the files.
+/// The new keys are derived from the mnemonic, which is only asked for when
+/// staging a new rotation.
+pub async fn rotate_keys(sender: &mut Sender, home: &Path, max_wait: Duration) -> Result<()> {
+    let mut state = match RotationState::load(home)? {
+        Some(s) if s.in_progress() => {
+            println!("resuming rotation {}", s.rotation);
+            s
+        }
+        _ => {
+            let keys = get_keys_from_prompt()?;
+            let s = stage_next(home, &keys)?;
+            println!(
+                "new keys of rotation {} staged at {}",
+                s.rotation,
+                rotation_dir(home, s.rotation).display()
+            );
+            s
+        }
+    };
+    if sender.local_account.address() != state.account {
+        bail!(
+            "the transactions are signed by {}, but the validator is {}",
+            sender.local_account.address(),
+            state.account
+        );
+    }
+
+    let dir = rotation_dir(home, state.rotation);
+    let new_oc = read_operator_file(&dir.join(OPERATOR_FILE))?;
+
+    let submitted_epoch = match state.submitted_epoch {
+        Some(e) => e,
+        None => {
+            let e = publish(sender, &new_oc).await?;
+            state.stage = RotationStage::Submitted;
+            state.submitted_epoch = Some(e);
+            state.save(home)?;
+            println!(
+                "new keys sent in epoch {}, they are used from the next epoch",
+                e
+            );
+            e
+        }
+    };
+
+    if !wait_for_keys(sender, &new_oc, submitted_epoch, max_wait).await? {
+        println!(
+            "\nthe validator set doesn't have the new keys yet. The node still uses the old keys in {}, nothing was switched.",
+            home.display()
+        );
+        println!("- to keep waiting, run `libra txs validator rotate-keys` again");
+        println!("- to cancel, run `libra txs validator rotate-keys --rollback`, which sends the old keys to the chain again");
+        bail!("rotation {} not active yet", state.rotation);
+    }
+
+    activate(home, state.rotation)?;
+    state.stage = RotationStage::Active;
+    state.save(home)?;
+    println!(
+        "\nrotation {} is active on chain, and the files in {} now have the new keys. The replaced files are in {}.",
+        state.rotation,
+        home.display(),
+        dir.join(PREVIOUS_DIR).display()
+    );
+    println!("restart the validator and its fullnode now, so they load the new identity");
+    println!(
+        "if the node doesn't join consensus, run `libra txs validator rotate-keys --rollback`"
+    );
+    Ok(())
+}
+
+/// Send the keys in use before the latest rotation to the chain again,
+/// and switch the files back once the validator set has them
+pub async fn rollback(sender: &mut Sender, home: &Path, max_wait: Duration) -> Result<()> {
+    let mut state = RotationState::load(home)?.context("no key rotation to roll back")?;
+    if state.stage == RotationStage::RolledBack {
+        bail!("rotation {} was already rolled back", state.rotation);
+    }
+
+    // before activation the home still has the old files
+    let was_active = state.stage == RotationStage::Active;
+    let old_dir = if was_active {
+        rotation_dir(home, state.rotation).join(PREVIOUS_DIR)
+    } else {
+        home.to_owned()
+    };
+    let old_oc = read_operator_file(&old_dir.join(OPERATOR_FILE))?;
+
+    let epoch = publish(sender, &old_oc).await?;
+    println!("old keys sent in epoch {}", epoch);
+
+    if !wait_for_keys(sender, &old_oc, epoch, max_wait).await? {
+        println!("the validator set doesn't have the old keys yet, the files were not changed");
+        println!("run `libra txs validator rotate-keys --rollback` again to resend them and wait");
+        bail!("rollback of rotation {} not active yet", state.rotation);
+    }
+
+    if was_active {
+        restore_previous(home, state.rotation)?;
+        println!("restart the validator and its fullnode now, so they load the old identity");
+    }
+    state.stage = RotationStage::RolledBack;
+    state.save(home)?;
+    println!("rotation {} rolled back", state.rotation);
+    Ok(())
+}
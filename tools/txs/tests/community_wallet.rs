@@ -2,10 +2,13 @@ use diem_sdk::crypto::ValidCryptoMaterialStringExt;
 use diem_sdk::types::LocalAccount;
 use diem_temppath::TempPath;
 use diem_types::account_address::AccountAddress;
-use libra_query::query_view;
+use libra_cached_packages::libra_framework_views as views;
+use libra_query::{donor_voice_queries, query_view};
 use libra_smoke_tests::{configure_validator, libra_smoke::LibraSmoke};
 use libra_txs::txs_cli::{TxsCli, TxsSub, TxsSub::Transfer};
-use libra_txs::txs_cli_community::{AdminTx, CageTx, ClaimTx, CommunityTxs, InitTx, OfferTx};
+use libra_txs::txs_cli_community::{
+    AdminTx, CageTx, ClaimTx, CommunityTxs, InitTx, LiquidateAction, LiquidateTx, OfferTx,
+};
 use libra_types::core_types::app_cfg::TxCost;
 use std::path::PathBuf;
 use url::Url;
@@ -1517,6 +1520,107 @@ async fn liquidate_community_wallet() {
 }
 */
 
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn liquidate_community_wallet_report() -> Result<(), anyhow::Error> {
+    // 1. Setup environment
+    let (mut smoke, dir, _account_address, comm_wallet_pk, comm_wallet_addr) =
+        setup_environment().await;
+    let config_path = dir.path().to_owned().join("libra-cli-config.yaml");
+    let api_endpoint = smoke.api_endpoint.clone();
+    let client = smoke.client();
+
+    // 2. Setup 5 funded accounts
+    let (signers, addresses) = smoke.create_accounts(5).await?;
+    for (signer_address, validator_private_key) in
+        addresses.iter().zip(smoke.validator_private_keys.iter())
+    {
+        run_cli_transfer(
+            *signer_address,
+            10.0,
+            validator_private_key.clone(),
+            smoke.api_endpoint.clone(),
+            config_path.clone(),
+        )
+        .await;
+    }
+
+    // 3. Setup community wallet caged with 3 authorities and 2 signitures
+    let initial_authorities: Vec<_> = signers.iter().take(3).collect();
+    setup_community_wallet_caged(
+        comm_wallet_pk.clone(),
+        comm_wallet_addr,
+        &initial_authorities,
+        2,
+        config_path.clone(),
+        api_endpoint.clone(),
+    )
+    .await;
+
+    // 4. The last account donates to the community wallet
+    let donor_address = addresses[4];
+    let donor_pk = signers[4]
+        .private_key()
+        .to_encoded_string()
+        .expect("cannot decode pri key");
+    run_cli_transfer(
+        comm_wallet_addr,
+        5.0,
+        donor_pk.clone(),
+        api_endpoint.clone(),
+        config_path.clone(),
+    )
+    .await;
+
+    // 5. The donor proposes to liquidate the community wallet
+    let cli_propose_liquidation = TxsCli {
+        subcommand: Some(TxsSub::Community(CommunityTxs::Liquidate(LiquidateTx {
+            action: LiquidateAction::Propose {
+                community_wallet: comm_wallet_addr,
+            },
+        }))),
+        mnemonic: None,
+        test_private_key: Some(donor_pk),
+        chain_name: None,
+        config_path: Some(config_path),
+        url: Some(api_endpoint),
+        tx_profile: None,
+        tx_cost: Some(TxCost::default_baseline_cost()),
+        estimate_only: false,
+        legacy_address: false,
+    };
+
+    cli_propose_liquidation
+        .run()
+        .await
+        .expect("CLI could not propose liquidation");
+
+    // 6. The report has the pending ballot and the pro rata refunds
+    let report = donor_voice_queries::community_wallet_liquidation(&client, comm_wallet_addr)
+        .await
+        .expect("Query failed: liquidation report");
+
+    let tally = report.tally.expect("liquidation ballot should be pending");
+    assert!(!tally.ballot_completed, "Ballot should not be completed");
+
+    let (donors, amounts) = views::donor_voice_txs_get_pro_rata(&client, comm_wallet_addr)
+        .await
+        .expect("Query failed: pro rata refunds");
+    assert_eq!(report.refunds.len(), donors.len());
+    for (refund, (donor, amount)) in report.refunds.iter().zip(donors.iter().zip(amounts)) {
+        assert_eq!(refund.donor, *donor);
+        assert_eq!(refund.refund, amount);
+    }
+
+    let donor_refund = report
+        .refunds
+        .iter()
+        .find(|r| r.donor == donor_address)
+        .expect("Donor should be in the refunds");
+    assert!(donor_refund.refund > 0, "Donor refund should be positive");
+
+    Ok(())
+}
+
 // UTILITY //
 
 async fn setup_environment() -> (LibraSmoke, TempPath, AccountAddress, String, AccountAddress) {
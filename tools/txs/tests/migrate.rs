@@ -0,0 +1,30 @@
+use libra_txs::txs_cli_user::{Migration, MigrationStatus};
+
+#[test]
+fn plans_account_migrations() {
+    // a v7 account which never migrated
+    let v7 = MigrationStatus::default();
+    assert_eq!(v7.required(), vec![Migration::Filo]);
+
+    // migrated founders are never migrated again, it would reset the slow wallet
+    let lonely_founder = MigrationStatus {
+        activity_initialized: true,
+        is_founder: true,
+        ..Default::default()
+    };
+    assert!(lonely_founder.required().is_empty());
+
+    let malformed = MigrationStatus {
+        activity_initialized: true,
+        malformed_activity: true,
+        v8_authorized: true,
+        ..Default::default()
+    };
+    assert_eq!(malformed.required(), vec![Migration::FixMalformedActivity]);
+
+    let community_wallet = MigrationStatus {
+        is_donor_voice: true,
+        ..Default::default()
+    };
+    assert!(community_wallet.required().is_empty());
+}
@@ -24,10 +24,10 @@ use diem_genesis::keys::{PrivateIdentity, PublicIdentity};
 use std::path::{Path, PathBuf};
 
 // These are consistent with Vendor
-const PRIVATE_KEYS_FILE: &str = "private-keys.yaml";
+pub const PRIVATE_KEYS_FILE: &str = "private-keys.yaml";
 pub const PUBLIC_KEYS_FILE: &str = "public-keys.yaml";
 pub const VALIDATOR_FILE: &str = "validator-identity.yaml";
-const VFN_FILE: &str = "validator-full-node-identity.yaml";
+pub const VFN_FILE: &str = "validator-full-node-identity.yaml";
 // This is Libra specific
 const USER_FILE: &str = "danger-user-private-keys.yaml";
 
@@ -143,7 +143,7 @@ fn create_non_signing_key() -> Ed25519PrivateKey {
     Ed25519PrivateKey::try_from(zero_bytes.as_ref()).unwrap()
 }
 
-fn save_val_files(
+pub(crate) fn save_val_files(
     output_opt: Option<PathBuf>,
     validator_blob: &mut IdentityBlob,
     vfn_blob: &mut IdentityBlob,
@@ -235,7 +235,7 @@ pub fn generate_key_objects_from_legacy(
 }
 
 /// Testing deterministic hkdf for bls
-fn bls_generate_key(ikm: &[u8]) -> anyhow::Result<bls12381::PrivateKey> {
+pub(crate) fn bls_generate_key(ikm: &[u8]) -> anyhow::Result<bls12381::PrivateKey> {
     let priv_key = blst::min_pk::SecretKey::key_gen(ikm, &[])
         .map_err(|e| anyhow!("blst key gen failed: {:?}", e))?;
 
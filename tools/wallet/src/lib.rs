@@ -8,5 +8,6 @@ pub mod keys;
 pub mod load_keys;
 pub mod utils;
 pub mod validator_files;
+pub mod validator_rotation;
 pub mod wallet_cli;
 pub mod whoami;
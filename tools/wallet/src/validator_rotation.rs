@@ -0,0 +1,353 @@
+//! Rotate the consensus key and network identity of a running validator.
+//! The new keys are derived from the mnemonic and a rotation number, so
+//! like the first keys they can always be recovered from the mnemonic.
+//! The new files are staged next to the old ones, and only replace them
+//! once the chain has the new keys in the validator set. The transactions
+//! are sent by `libra txs validator rotate-keys`.
+
+use crate::{
+    account_keys::KeyChain,
+    keys::{
+        bls_generate_key, generate_key_objects_from_legacy, save_val_files, PRIVATE_KEYS_FILE,
+        PUBLIC_KEYS_FILE, VALIDATOR_FILE, VFN_FILE,
+    },
+    utils::{read_operator_file, to_yaml, write_to_user_only_file},
+    validator_files::OPERATOR_FILE,
+};
+use anyhow::{bail, Context, Result};
+use diem_config::config::IdentityBlob;
+use diem_crypto::{bls12381, traits::PrivateKey, x25519};
+use diem_genesis::{
+    config::OperatorConfiguration,
+    keys::{PrivateIdentity, PublicIdentity},
+};
+use libra_types::exports::AccountAddress;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// where the staged keys of each rotation are kept, under the validator home
+pub const ROTATION_DIR: &str = "key-rotation";
+const ROTATION_STATE_FILE: &str = "rotation.yaml";
+/// the files replaced by a rotation are moved here, inside the rotation dir
+pub const PREVIOUS_DIR: &str = "previous";
+/// all the files a rotation replaces in the validator home
+const ROTATED_FILES: [&str; 5] = [
+    PRIVATE_KEYS_FILE,
+    PUBLIC_KEYS_FILE,
+    VALIDATOR_FILE,
+    VFN_FILE,
+    OPERATOR_FILE,
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RotationStage {
+    /// new files written, nothing sent to the chain
+    Staged,
+    /// the new keys were sent, waiting for the next epoch
+    Submitted,
+    /// the node files were switched to the new keys
+    Active,
+    /// the old keys were restored
+    RolledBack,
+}
+
+/// Progress of the latest rotation, so an interrupted one can be resumed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RotationState {
+    pub rotation: u64,
+    pub account: AccountAddress,
+    pub stage: RotationStage,
+    /// the epoch when the keys were sent, they are used from the next one
+    pub submitted_epoch: Option<u64>,
+}
+
+impl RotationState {
+    pub fn load(home: &Path) -> Result<Option<Self>> {
+        let path = home.join(ROTATION_DIR).join(ROTATION_STATE_FILE);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let s = fs::read_to_string(&path)?;
+        Ok(Some(serde_yaml::from_str(&s)?))
+    }
+
+    pub fn save(&self, home: &Path) -> Result<()> {
+        let dir = home.join(ROTATION_DIR);
+        fs::create_dir_all(&dir)?;
+        fs::write(dir.join(ROTATION_STATE_FILE), serde_yaml::to_string(self)?)?;
+        Ok(())
+    }
+
+    /// a rotation was started, but the node doesn't use the new keys yet
+    pub fn in_progress(&self) -> bool {
+        matches!(self.stage, RotationStage::Staged | RotationStage::Submitted)
+    }
+}
+
+/// the directory with the staged files of a rotation
+pub fn rotation_dir(home: &Path, rotation: u64) -> PathBuf {
+    home.join(ROTATION_DIR).join(rotation.to_string())
+}
+
+/// key material for a rotation, from the mnemonic seed
+fn rotation_ikm(seed: &[u8], rotation: u64, purpose: &[u8]) -> Vec<u8> {
+    let mut h = Sha256::new();
+    h.update(seed);
+    h.update(b"libra-key-rotation");
+    h.update(rotation.to_le_bytes());
+    h.update(purpose);
+    h.finalize().to_vec()
+}
+
+/// Same as the key objects of `generate_key_objects_from_legacy`, with the
+/// consensus and network keys of the rotation. The account key stays.
+pub fn rotated_key_objects(
+    keys: &KeyChain,
+    rotation: u64,
+) -> Result<(IdentityBlob, IdentityBlob, PrivateIdentity, PublicIdentity)> {
+    if rotation == 0 {
+        bail!("rotation 0 are the original keys, rotations start at 1");
+    }
+    let (mut validator_blob, mut vfn_blob, mut private_identity, mut public_identity) =
+        generate_key_objects_from_legacy(keys)?;
+
+    let consensus_key = bls_generate_key(&rotation_ikm(&keys.seed, rotation, b"consensus"))?;
+    let validator_network_key = x25519::PrivateKey::from_ed25519_private_bytes(&rotation_ikm(
+        &keys.seed,
+        rotation,
+        b"validator_network",
+    ))?;
+    let full_node_network_key = x25519::PrivateKey::from_ed25519_private_bytes(&rotation_ikm(
+        &keys.seed,
+        rotation,
+        b"fullnode_network",
+    ))?;
+
+    public_identity.consensus_public_key = Some(consensus_key.public_key());
+    public_identity.consensus_proof_of_possession =
+        Some(bls12381::ProofOfPossession::create(&consensus_key));
+    public_identity.validator_network_public_key = Some(validator_network_key.public_key());
+    public_identity.full_node_network_public_key = Some(full_node_network_key.public_key());
+
+    validator_blob.consensus_private_key = Some(consensus_key.clone());
+    validator_blob.network_private_key = validator_network_key.clone();
+    // the VFN address follows its network key, see generate_key_objects_from_legacy
+    vfn_blob.account_address = Some(full_node_network_key.public_key().to_string().parse()?);
+    vfn_blob.network_private_key = full_node_network_key.clone();
+
+    private_identity.consensus_private_key = consensus_key;
+    private_identity.validator_network_private_key = validator_network_key;
+    private_identity.full_node_network_private_key = full_node_network_key;
+
+    Ok((validator_blob, vfn_blob, private_identity, public_identity))
+}
+
+/// Write the files of the rotation to its directory, next to the files in
+/// use. The operator file keeps the hosts of the current one.
+pub fn stage(home: &Path, keys: &KeyChain, rotation: u64) -> Result<OperatorConfiguration> {
+    let current = read_operator_file(&home.join(OPERATOR_FILE))
+        .context("cannot read the operator file of the running validator")?;
+    let dir = rotation_dir(home, rotation);
+    if dir.join(OPERATOR_FILE).exists() {
+        bail!(
+            "rotation {} was already staged at {}, remove it to stage it again",
+            rotation,
+            dir.display()
+        );
+    }
+
+    let (mut validator_blob, mut vfn_blob, mut private_identity, public_identity) =
+        rotated_key_objects(keys, rotation)?;
+    if public_identity.account_address != AccountAddress::from(current.operator_account_address) {
+        bail!(
+            "the mnemonic is for account {}, but the validator is {}",
+            public_identity.account_address,
+            current.operator_account_address
+        );
+    }
+
+    save_val_files(
+        Some(dir.clone()),
+        &mut validator_blob,
+        &mut vfn_blob,
+        &mut private_identity,
+        &public_identity,
+    )?;
+
+    let oc = OperatorConfiguration {
+        consensus_public_key: private_identity.consensus_private_key.public_key(),
+        consensus_proof_of_possession: bls12381::ProofOfPossession::create(
+            &private_identity.consensus_private_key,
+        ),
+        validator_network_public_key: private_identity.validator_network_private_key.public_key(),
+        full_node_network_public_key: current
+            .full_node_host
+            .as_ref()
+            .map(|_| private_identity.full_node_network_private_key.public_key()),
+        ..current
+    };
+    write_to_user_only_file(
+        &dir.join(OPERATOR_FILE),
+        OPERATOR_FILE,
+        to_yaml(&oc)?.as_bytes(),
+    )?;
+    Ok(oc)
+}
+
+/// Stage the keys of the next rotation, and save its state so
+/// `libra txs validator rotate-keys` can send them.
+pub fn stage_next(home: &Path, keys: &KeyChain) -> Result<RotationState> {
+    let prev = RotationState::load(home)?;
+    if let Some(s) = prev.as_ref().filter(|s| s.in_progress()) {
+        bail!(
+            "rotation {} is already in progress ({:?}), finish it with `libra txs validator rotate-keys`",
+            s.rotation,
+            s.stage
+        );
+    }
+    let rotation = prev.map(|s| s.rotation + 1).unwrap_or(1);
+    let oc = stage(home, keys, rotation)?;
+    let state = RotationState {
+        rotation,
+        account: oc.operator_account_address.into(),
+        stage: RotationStage::Staged,
+        submitted_epoch: None,
+    };
+    state.save(home)?;
+    Ok(state)
+}
+
+/// Switch the validator home to the files of the rotation, moving the
+/// replaced ones to the `previous` directory of the rotation.
+pub fn activate(home: &Path, rotation: u64) -> Result<()> {
+    let dir = rotation_dir(home, rotation);
+    let previous = dir.join(PREVIOUS_DIR);
+    fs::create_dir_all(&previous)?;
+    for f in ROTATED_FILES {
+        let staged = dir.join(f);
+        if !staged.exists() {
+            continue;
+        }
+        if home.join(f).exists() {
+            fs::rename(home.join(f), previous.join(f))?;
+        }
+        fs::copy(&staged, home.join(f))?;
+    }
+    Ok(())
+}
+
+/// Put back the files `activate` replaced
+pub fn restore_previous(home: &Path, rotation: u64) -> Result<()> {
+    let previous = rotation_dir(home, rotation).join(PREVIOUS_DIR);
+    if !previous.exists() {
+        bail!("no previous files for rotation {}", rotation);
+    }
+    for f in ROTATED_FILES {
+        if previous.join(f).exists() {
+            fs::copy(previous.join(f), home.join(f))?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        account_keys::get_keys_from_mnem, keys::refresh_validator_files,
+        validator_files::SetValidatorConfiguration,
+    };
+
+    const ALICE_MNEM: &str = "talent sunset lizard pill fame nuclear spy noodle basket okay critic grow sleep legend hurry pitch blanket clerk impose rough degree sock insane purse";
+
+    fn validator_home() -> anyhow::Result<diem_temppath::TempPath> {
+        let temp = diem_temppath::TempPath::new();
+        temp.create_as_dir()?;
+        refresh_validator_files(
+            Some(ALICE_MNEM.to_owned()),
+            Some(temp.path().to_owned()),
+            false,
+        )?;
+        SetValidatorConfiguration::new(
+            Some(temp.path().to_owned()),
+            "alice".to_owned(),
+            "127.0.0.1:6180".parse()?,
+            None,
+        )
+        .set_config_files()?;
+        Ok(temp)
+    }
+
+    #[test]
+    fn rotated_keys_are_deterministic() -> anyhow::Result<()> {
+        let keys = get_keys_from_mnem(ALICE_MNEM.to_owned())?;
+        let (_, _, _, original) = generate_key_objects_from_legacy(&keys)?;
+        let (_, _, _, first) = rotated_key_objects(&keys, 1)?;
+        let (_, _, _, again) = rotated_key_objects(&keys, 1)?;
+        let (_, _, _, second) = rotated_key_objects(&keys, 2)?;
+
+        assert_eq!(first.consensus_public_key, again.consensus_public_key);
+        assert_ne!(first.consensus_public_key, original.consensus_public_key);
+        assert_ne!(first.consensus_public_key, second.consensus_public_key);
+        assert_ne!(
+            first.validator_network_public_key,
+            original.validator_network_public_key
+        );
+        // same account
+        assert_eq!(first.account_address, original.account_address);
+        assert_eq!(first.account_public_key, original.account_public_key);
+        Ok(())
+    }
+
+    #[test]
+    fn stage_activate_restore() -> anyhow::Result<()> {
+        let temp = validator_home()?;
+        let home = temp.path();
+        let keys = get_keys_from_mnem(ALICE_MNEM.to_owned())?;
+        let old = read_operator_file(&home.join(OPERATOR_FILE))?;
+
+        let new = stage(home, &keys, 1)?;
+        assert_ne!(new.consensus_public_key, old.consensus_public_key);
+        assert_eq!(new.validator_host, old.validator_host);
+        // the home is untouched until activation
+        let current = read_operator_file(&home.join(OPERATOR_FILE))?;
+        assert_eq!(current.consensus_public_key, old.consensus_public_key);
+        assert!(stage(home, &keys, 1).is_err());
+
+        activate(home, 1)?;
+        let current = read_operator_file(&home.join(OPERATOR_FILE))?;
+        assert_eq!(current.consensus_public_key, new.consensus_public_key);
+        let id = IdentityBlob::from_file(&home.join(VALIDATOR_FILE))?;
+        assert_eq!(
+            id.consensus_private_key.map(|k| k.public_key()),
+            Some(new.consensus_public_key.clone())
+        );
+
+        restore_previous(home, 1)?;
+        let current = read_operator_file(&home.join(OPERATOR_FILE))?;
+        assert_eq!(current.consensus_public_key, old.consensus_public_key);
+        Ok(())
+    }
+    #[test]
+    fn stage_next_numbers_rotations() -> anyhow::Result<()> {
+        let temp = validator_home()?;
+        let home = temp.path();
+        let keys = get_keys_from_mnem(ALICE_MNEM.to_owned())?;
+
+        let first = stage_next(home, &keys)?;
+        assert_eq!(first.rotation, 1);
+        assert_eq!(first.stage, RotationStage::Staged);
+        // can't stage another while this one is in progress
+        assert!(stage_next(home, &keys).is_err());
+
+        let mut done = RotationState::load(home)?.expect("state was saved");
+        done.stage = RotationStage::Active;
+        done.save(home)?;
+        assert_eq!(stage_next(home, &keys)?.rotation, 2);
+        Ok(())
+    }
+}
@@ -1,7 +1,12 @@
-use crate::{account_keys, whoami::who_am_i};
+use crate::{
+    account_keys,
+    validator_rotation::{self, RotationState},
+    whoami::who_am_i,
+};
 
 use anyhow::Result;
 use clap::{Args, Parser, Subcommand};
+use libra_types::global_config_dir;
 use std::path::PathBuf;
 
 #[derive(Parser)]
@@ -29,6 +34,21 @@ enum WalletSub {
     Legacy,
     /// use mnemonic to see what account keys are generated
     Whoami(WhoamiOpts),
+    /// Manage the keys of a validator
+    #[clap(subcommand)]
+    Validator(ValidatorSub),
+}
+
+#[derive(Subcommand)]
+enum ValidatorSub {
+    /// Stage new consensus and network keys of a running validator, next to
+    /// the files in use. Then `libra txs validator rotate-keys` sends them to
+    /// the chain, and switches the node files after the next epoch.
+    RotateKeys {
+        /// validator home with operator.yaml, defaults to $HOME/.libra
+        #[clap(long)]
+        home_path: Option<PathBuf>,
+    },
 }
 
 #[derive(Args, Debug)]
@@ -73,6 +93,24 @@ impl WalletCli {
                     .await?
                 );
             }
+            WalletSub::Validator(ValidatorSub::RotateKeys { home_path }) => {
+                let home = home_path.clone().unwrap_or_else(global_config_dir);
+                if let Some(s) = RotationState::load(&home)?.filter(|s| s.in_progress()) {
+                    println!(
+                        "rotation {} is already in progress ({:?})",
+                        s.rotation, s.stage
+                    );
+                } else {
+                    let keys = account_keys::get_keys_from_prompt()?;
+                    let s = validator_rotation::stage_next(&home, &keys)?;
+                    println!(
+                        "new keys of rotation {} staged at {}",
+                        s.rotation,
+                        validator_rotation::rotation_dir(&home, s.rotation).display()
+                    );
+                }
+                println!("next, run `libra txs validator rotate-keys` to send the keys to the chain and switch the files after the next epoch");
+            }
         }
         Ok(())
     }
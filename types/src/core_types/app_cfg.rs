@@ -15,6 +15,7 @@ use url::Url;
 use std::{fs, io::Write, path::PathBuf, str::FromStr};
 
 use super::{
+    config_migrations::{self, MigrationNotes, CONFIG_VERSION},
     mode_ol::MODE_0L,
     network_playlist::{self, NetworkPlaylist},
     pledge::Pledge,
@@ -27,13 +28,19 @@ pub const CONFIG_FILE_NAME: &str = "libra-cli-config.yaml";
 /// MinerApp Configuration
 #[derive(Debug, Deserialize, Serialize)]
 pub struct AppCfg {
+    /// Schema version of the file, older files are migrated on load
+    #[serde(default)]
+    pub version: u64,
     /// Workspace config
     pub workspace: Workspace,
     /// A user may have multiple profiles for different accounts or networks
+    #[serde(default)]
     pub user_profiles: Vec<Profile>,
     /// Network profile
+    #[serde(default)]
     pub network_playlist: Vec<NetworkPlaylist>,
     /// Transaction configurations
+    #[serde(default)]
     pub tx_configs: TxConfigs,
 }
 
@@ -119,6 +126,13 @@ impl AppCfg {
     }
     /// load from default path
     pub fn load(file: Option<PathBuf>) -> anyhow::Result<Self> {
+        Ok(Self::load_with_notes(file)?.0)
+    }
+    /// load from default path, with the notes of the migration if the file
+    /// was an older version
+    pub fn load_with_notes(
+        file: Option<PathBuf>,
+    ) -> anyhow::Result<(Self, Option<MigrationNotes>)> {
         let path = file.unwrap_or_else(default_file_path);
         if !path.exists() {
             bail!(format!(
@@ -126,12 +140,13 @@ impl AppCfg {
                 path.to_str().unwrap()
             ))
         }
+        let notes = config_migrations::migrate_file(&path)?;
         let s = fs::read_to_string(&path)?;
         let de: AppCfg = serde_yaml::from_str(&s).context(format!(
             "could not read {:?} file into an AppCfg. Is there an issue with the file?",
             &path
         ))?;
-        Ok(de)
+        Ok((de, notes))
     }
     /// save the config file to 0L.toml to the workspace home path
     pub fn save_file(&self) -> anyhow::Result<PathBuf> {
@@ -315,12 +330,147 @@ impl AppCfg {
         let np = self.get_network_profile(chain_id)?;
         np.pick_one()
     }
+
+    // Non-interactive management, for scripts. None of these touch stdin.
+
+    /// find a profile by exact nickname or address, unlike `get_profile`
+    /// which matches fragments
+    pub fn find_profile_idx(&self, id: &str) -> Option<usize> {
+        self.user_profiles.iter().position(|p| {
+            p.nickname == id || p.account.to_hex_literal() == id || p.account.to_string() == id
+        })
+    }
+
+    fn is_default_profile(&self, p: &Profile) -> bool {
+        self.workspace
+            .default_profile
+            .as_ref()
+            .map(|d| d == &p.account.to_hex_literal() || d == &p.nickname)
+            .unwrap_or(false)
+    }
+
+    /// add a profile, or replace the one with the same account
+    pub fn add_profile(
+        &mut self,
+        authkey: AuthenticationKey,
+        account: AccountAddress,
+        nickname: Option<String>,
+        set_default: bool,
+    ) -> anyhow::Result<()> {
+        let mut profile = Profile::new(authkey, account);
+        if let Some(n) = nickname {
+            profile.nickname = n;
+        }
+        self.maybe_add_profile(profile)?;
+        if set_default || self.user_profiles.len() == 1 {
+            self.workspace.set_default(account.to_hex_literal());
+        }
+        Ok(())
+    }
+
+    /// Remove a profile by exact nickname or address. If it was the
+    /// default, the first remaining profile becomes the default.
+    pub fn remove_profile(&mut self, id: &str) -> anyhow::Result<Profile> {
+        let idx = self
+            .find_profile_idx(id)
+            .context(format!("no profile found matching {}", id))?;
+        let was_default = self.is_default_profile(&self.user_profiles[idx]);
+        let removed = self.user_profiles.remove(idx);
+
+        if was_default {
+            self.workspace.default_profile = self
+                .user_profiles
+                .first()
+                .map(|p| p.account.to_hex_literal());
+        }
+        Ok(removed)
+    }
+
+    pub fn set_default_profile(&mut self, id: &str) -> anyhow::Result<()> {
+        let idx = self
+            .find_profile_idx(id)
+            .context(format!("no profile found matching {}", id))?;
+        let account = self.user_profiles[idx].account.to_hex_literal();
+        self.workspace.set_default(account);
+        Ok(())
+    }
+
+    pub fn rename_profile(&mut self, id: &str, nickname: &str) -> anyhow::Result<()> {
+        let idx = self
+            .find_profile_idx(id)
+            .context(format!("no profile found matching {}", id))?;
+        if self.is_default_profile(&self.user_profiles[idx]) {
+            // the default may be set by nickname
+            self.workspace
+                .set_default(self.user_profiles[idx].account.to_hex_literal());
+        }
+        self.user_profiles[idx].nickname = nickname.to_owned();
+        Ok(())
+    }
+
+    /// the default chain must have a network playlist
+    pub fn set_default_chain(&mut self, chain_id: NamedChain) -> anyhow::Result<()> {
+        if !self
+            .network_playlist
+            .iter()
+            .any(|np| np.chain_name == chain_id)
+        {
+            bail!("no network configured for chain {}", chain_id);
+        }
+        self.set_chain_id(chain_id);
+        Ok(())
+    }
+
+    /// add a fullnode url to the playlist of the chain, creating the playlist
+    /// if the chain has none
+    pub fn add_fullnode_url(
+        &mut self,
+        chain_id: Option<NamedChain>,
+        url: Url,
+    ) -> anyhow::Result<()> {
+        let chain_id = chain_id.unwrap_or(self.workspace.default_chain_id);
+        match self
+            .network_playlist
+            .iter_mut()
+            .find(|np| np.chain_name == chain_id)
+        {
+            Some(np) => {
+                if !np.nodes.iter().any(|n| n.url == url) {
+                    np.add_url(url);
+                }
+            }
+            None => self
+                .network_playlist
+                .push(NetworkPlaylist::new(Some(url), Some(chain_id))),
+        }
+        Ok(())
+    }
+
+    pub fn remove_fullnode_url(
+        &mut self,
+        chain_id: Option<NamedChain>,
+        url: &Url,
+    ) -> anyhow::Result<()> {
+        let np = self.get_network_profile_mut(chain_id)?;
+        let before = np.nodes.len();
+        np.nodes.retain(|n| &n.url != url);
+        if np.nodes.len() == before {
+            bail!("{} is not in the {} playlist", url, np.chain_name);
+        }
+        Ok(())
+    }
+
+    pub fn remove_all_fullnodes(&mut self, chain_id: Option<NamedChain>) -> anyhow::Result<()> {
+        self.get_network_profile_mut(chain_id)?.nodes.clear();
+        Ok(())
+    }
 }
 
 /// Default configuration settings.
 impl Default for AppCfg {
     fn default() -> Self {
         Self {
+            version: CONFIG_VERSION,
             workspace: Workspace::default(),
             user_profiles: vec![],
             network_playlist: vec![],
@@ -530,16 +680,16 @@ pub struct TxConfigs {
     #[serde(default = "TxCost::default_baseline_cost")]
     pub baseline_cost: TxCost,
     /// critical transactions cost
-    // #[serde(default = "None")]
+    #[serde(default = "TxConfigs::default_critical_txs_cost")]
     pub critical_txs_cost: Option<TxCost>,
     /// management transactions cost
-    // #[serde(default = "TxCost::default_management_txs_cost")]
+    #[serde(default = "TxConfigs::default_management_txs_cost")]
     pub management_txs_cost: Option<TxCost>,
     /// Miner transactions cost
-    // #[serde(default = "TxCost::default_miner_txs_cost")]
+    #[serde(default = "TxConfigs::default_miner_txs_cost")]
     pub miner_txs_cost: Option<TxCost>,
     /// Cheap or test transaction costs
-    // #[serde(default = "TxCost::default_cheap_txs_cost")]
+    #[serde(default = "TxConfigs::default_cheap_txs_cost")]
     pub cheap_txs_cost: Option<TxCost>,
 }
 
@@ -556,6 +706,19 @@ impl TxConfigs {
 
         cost.unwrap_or_default()
     }
+
+    fn default_critical_txs_cost() -> Option<TxCost> {
+        Some(TxCost::default_critical_txs_cost())
+    }
+    fn default_management_txs_cost() -> Option<TxCost> {
+        Some(TxCost::default_management_txs_cost())
+    }
+    fn default_miner_txs_cost() -> Option<TxCost> {
+        Some(TxCost::default_miner_txs_cost())
+    }
+    fn default_cheap_txs_cost() -> Option<TxCost> {
+        Some(TxCost::default_cheap_txs_cost())
+    }
 }
 
 /// Transaction preferences for a given type of transaction
@@ -624,10 +787,10 @@ impl Default for TxConfigs {
     fn default() -> Self {
         Self {
             baseline_cost: TxCost::default_baseline_cost(),
-            critical_txs_cost: Some(TxCost::default_critical_txs_cost()),
-            management_txs_cost: Some(TxCost::default_management_txs_cost()),
-            miner_txs_cost: Some(TxCost::default_miner_txs_cost()),
-            cheap_txs_cost: Some(TxCost::default_cheap_txs_cost()),
+            critical_txs_cost: Self::default_critical_txs_cost(),
+            management_txs_cost: Self::default_management_txs_cost(),
+            miner_txs_cost: Self::default_miner_txs_cost(),
+            cheap_txs_cost: Self::default_cheap_txs_cost(),
         }
     }
 }
@@ -771,3 +934,50 @@ tx_configs:
     let url = cfg.pick_url(None).unwrap();
     assert!(url.host_str().unwrap().contains("localhost"));
 }
+
+#[test]
+fn manage_profiles_and_urls() {
+    let a = AuthenticationKey::from_str(
+        "8603ba96e87b810cebbec1a0fd7ea06285f9eb352a3eabde992a5594fe80af40",
+    )
+    .unwrap();
+    let b = AuthenticationKey::from_str(
+        "052dea65ac80cd4b2b1318a9420dc1568819882769346d9acffdc0d731504c66",
+    )
+    .unwrap();
+
+    let mut cfg = AppCfg::default();
+    cfg.add_profile(a, a.derived_address(), None, false)
+        .unwrap();
+    cfg.add_profile(b, b.derived_address(), Some("bob".to_string()), true)
+        .unwrap();
+    assert_eq!(
+        cfg.workspace.default_profile,
+        Some(b.derived_address().to_hex_literal())
+    );
+
+    // removing the default moves it to the remaining profile
+    cfg.remove_profile("bob").unwrap();
+    assert_eq!(
+        cfg.workspace.default_profile,
+        Some(a.derived_address().to_hex_literal())
+    );
+    assert!(cfg.remove_profile("bob").is_err());
+
+    let url: Url = "http://localhost:1".parse().unwrap();
+    cfg.add_fullnode_url(Some(NamedChain::TESTNET), url.clone())
+        .unwrap();
+    cfg.add_fullnode_url(Some(NamedChain::TESTNET), url.clone())
+        .unwrap();
+    assert_eq!(
+        cfg.get_network_profile(Some(NamedChain::TESTNET))
+            .unwrap()
+            .nodes
+            .len(),
+        1
+    );
+    cfg.set_default_chain(NamedChain::TESTNET).unwrap();
+    assert!(cfg.set_default_chain(NamedChain::DEVNET).is_err());
+    cfg.remove_fullnode_url(None, &url).unwrap();
+    assert!(cfg.remove_fullnode_url(None, &url).is_err());
+}
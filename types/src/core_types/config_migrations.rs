@@ -0,0 +1,325 @@
+//! Versions of the libra-cli-config.yaml schema, and the migrations which
+//! upgrade older files. Files from before versioning are version 0.
+//! Migrations work on the raw YAML, since old files may not parse into
+//! the current `AppCfg`.
+
+use super::app_cfg::{AppCfg, TxConfigs};
+use anyhow::{bail, Context};
+use serde::{Deserialize, Serialize};
+use serde_yaml::{Mapping, Value};
+use std::{
+    fmt, fs,
+    path::{Path, PathBuf},
+};
+
+/// the schema version this build writes
+pub const CONFIG_VERSION: u64 = 1;
+
+type Migration = fn(&mut Mapping) -> anyhow::Result<()>;
+
+/// Ordered migrations, the one at index N upgrades from version N to N + 1
+const MIGRATIONS: &[(&str, Migration)] = &[(
+    "add missing sections and transaction costs, drop profile upstream_nodes",
+    v0_to_v1,
+)];
+
+/// the version of a raw config, 0 if it has none
+pub fn version_of(cfg: &Value) -> u64 {
+    cfg.get("version").and_then(Value::as_u64).unwrap_or(0)
+}
+
+/// Upgrade a raw config to the current version. Returns the description
+/// of every migration applied.
+pub fn migrate_value(cfg: &mut Value) -> anyhow::Result<Vec<String>> {
+    let from = version_of(cfg);
+    if from > CONFIG_VERSION {
+        bail!(
+            "config file is version {}, this tool only knows up to version {}. Update your tools.",
+            from,
+            CONFIG_VERSION
+        );
+    }
+
+    let map = cfg
+        .as_mapping_mut()
+        .context("config file is not a YAML mapping")?;
+    let mut applied = vec![];
+    for (i, (description, migration)) in MIGRATIONS.iter().enumerate().skip(from as usize) {
+        migration(map).context(format!("migration to version {} failed", i + 1))?;
+        map.insert("version".into(), (i as u64 + 1).into());
+        applied.push(format!("v{} -> v{}: {}", i, i + 1, description));
+    }
+    Ok(applied)
+}
+
+/// What `migrate_file` did to an older config file
+#[derive(Debug, Clone)]
+pub struct MigrationNotes {
+    pub path: PathBuf,
+    /// the copy of the original file
+    pub backup: PathBuf,
+    /// description of every migration applied
+    pub applied: Vec<String>,
+}
+
+impl fmt::Display for MigrationNotes {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "migrated {} to version {}, backup at {}",
+            self.path.display(),
+            CONFIG_VERSION,
+            self.backup.display()
+        )?;
+        for a in &self.applied {
+            write!(f, "\n  {}", a)?;
+        }
+        Ok(())
+    }
+}
+
+/// Upgrade the config file in place if it's an older version, keeping a
+/// copy of the original next to it. Returns what was done, if the file
+/// was migrated.
+pub fn migrate_file(path: &Path) -> anyhow::Result<Option<MigrationNotes>> {
+    let s = fs::read_to_string(path).context(format!("cannot read {}", path.display()))?;
+    let mut cfg: Value = serde_yaml::from_str(&s)?;
+    let from = version_of(&cfg);
+    if from == CONFIG_VERSION {
+        return Ok(None);
+    }
+    let applied = migrate_value(&mut cfg)?;
+
+    let mut backup = path.as_os_str().to_owned();
+    backup.push(format!(".v{}.bak", from));
+    let backup = PathBuf::from(backup);
+    fs::copy(path, &backup)?;
+    fs::write(path, serde_yaml::to_string(&cfg)?)?;
+
+    Ok(Some(MigrationNotes {
+        path: path.to_owned(),
+        backup,
+        applied,
+    }))
+}
+
+fn v0_to_v1(cfg: &mut Mapping) -> anyhow::Result<()> {
+    for key in ["user_profiles", "network_playlist"] {
+        if !cfg.contains_key(&key.into()) {
+            cfg.insert(key.into(), Value::Sequence(vec![]));
+        }
+    }
+
+    // costs missing from old files silently fell back to the baseline
+    let defaults = serde_yaml::to_value(TxConfigs::default())?;
+    let key: Value = "tx_configs".into();
+    if !cfg.contains_key(&key) {
+        cfg.insert(key.clone(), defaults.clone());
+    }
+    if let (Some(costs), Some(default_costs)) = (
+        cfg.get_mut(&key).and_then(Value::as_mapping_mut),
+        defaults.as_mapping(),
+    ) {
+        for (k, v) in default_costs {
+            if !costs.contains_key(k) {
+                costs.insert(k.clone(), v.clone());
+            }
+        }
+    }
+
+    if let Some(profiles) = cfg
+        .get_mut(&"user_profiles".into())
+        .and_then(Value::as_sequence_mut)
+    {
+        for p in profiles.iter_mut().filter_map(Value::as_mapping_mut) {
+            p.remove(&"upstream_nodes".into());
+        }
+    }
+    Ok(())
+}
+
+/// What `config validate` found
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ValidationReport {
+    pub path: PathBuf,
+    pub version: u64,
+    /// migrations which would be applied on the next load
+    pub pending_migrations: Vec<String>,
+    /// fields the current schema doesn't know, which are ignored
+    pub unknown_fields: Vec<String>,
+    /// fields which can't be read, the file can't be used
+    pub errors: Vec<String>,
+    /// the file can be read, but probably isn't what was meant
+    pub warnings: Vec<String>,
+}
+
+impl ValidationReport {
+    pub fn is_valid(&self) -> bool {
+        self.errors.is_empty()
+    }
+}
+
+/// Check a config file without changing it: pending migrations,
+/// unknown fields, and fields which don't parse.
+pub fn validate_file(path: &Path) -> anyhow::Result<ValidationReport> {
+    let s = fs::read_to_string(path).context(format!("cannot read {}", path.display()))?;
+    let mut report = ValidationReport {
+        path: path.to_owned(),
+        ..Default::default()
+    };
+
+    let mut raw: Value = match serde_yaml::from_str(&s) {
+        Ok(v) => v,
+        Err(e) => {
+            report.errors.push(format!("not valid YAML: {}", e));
+            return Ok(report);
+        }
+    };
+    report.version = version_of(&raw);
+    match migrate_value(&mut raw) {
+        Ok(applied) => report.pending_migrations = applied,
+        Err(e) => {
+            report.errors.push(format!("{:#}", e));
+            return Ok(report);
+        }
+    }
+
+    let cfg: AppCfg = match serde_yaml::from_value(raw.clone()) {
+        Ok(c) => c,
+        Err(e) => {
+            report.errors.push(e.to_string());
+            return Ok(report);
+        }
+    };
+
+    // anything serde skipped won't be written back
+    let round_trip = serde_yaml::to_value(&cfg)?;
+    unknown_fields(&raw, &round_trip, "", &mut report.unknown_fields);
+
+    if let Some(d) = &cfg.workspace.default_profile {
+        if cfg.get_profile(Some(d.to_owned())).is_err() {
+            report
+                .warnings
+                .push(format!("default profile {} matches no profile", d));
+        }
+    }
+    match cfg.get_network_profile(None) {
+        Ok(np) if np.nodes.is_empty() => report.warnings.push(format!(
+            "the default chain {} has no fullnode urls",
+            np.chain_name
+        )),
+        Ok(_) => {}
+        Err(_) => report.warnings.push(format!(
+            "the default chain {} has no network playlist",
+            cfg.workspace.default_chain_id
+        )),
+    }
+
+    Ok(report)
+}
+
+/// the paths of keys in `raw` which are not in `known`
+fn unknown_fields(raw: &Value, known: &Value, path: &str, out: &mut Vec<String>) {
+    match (raw, known) {
+        (Value::Mapping(r), Value::Mapping(k)) => {
+            for (key, v) in r {
+                let name = key
+                    .as_str()
+                    .map(str::to_owned)
+                    .unwrap_or_else(|| format!("{:?}", key));
+                let child = if path.is_empty() {
+                    name
+                } else {
+                    format!("{}.{}", path, name)
+                };
+                match k.get(key) {
+                    Some(kv) => unknown_fields(v, kv, &child, out),
+                    None => out.push(child),
+                }
+            }
+        }
+        (Value::Sequence(r), Value::Sequence(k)) => {
+            for (i, (rv, kv)) in r.iter().zip(k).enumerate() {
+                unknown_fields(rv, kv, &format!("{}[{}]", path, i), out);
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const V0: &str = r"
+workspace:
+  default_profile: '636'
+  default_chain_id: TESTING
+  node_home: /tmp/.libra
+user_profiles:
+- account: 63609dfa4c8786bef29b201500064b2864689de724ca134f4e975784e3642776
+  auth_key: 0x63609dfa4c8786bef29b201500064b2864689de724ca134f4e975784e3642776
+  test_private_key: null
+  nickname: '636'
+  on_chain: false
+  balance:
+    unlocked: 0
+    total: 0
+  locale: null
+  statement: Protests rage across the nation
+  upstream_nodes: null
+  pledges: null
+tx_configs:
+  baseline_cost:
+    max_gas_unit_for_tx: 10000
+    coin_price_per_unit: 100
+    user_tx_timeout: 5000
+";
+
+    #[test]
+    fn migrates_v0() -> anyhow::Result<()> {
+        let mut raw: Value = serde_yaml::from_str(V0)?;
+        assert_eq!(version_of(&raw), 0);
+        let applied = migrate_value(&mut raw)?;
+        assert_eq!(applied.len(), MIGRATIONS.len());
+        assert_eq!(version_of(&raw), CONFIG_VERSION);
+
+        let cfg: AppCfg = serde_yaml::from_value(raw)?;
+        assert!(cfg.network_playlist.is_empty());
+        // the missing costs are the defaults, not the baseline
+        assert!(cfg.tx_configs.critical_txs_cost.is_some());
+        assert_eq!(cfg.tx_configs.baseline_cost.coin_price_per_unit, 100);
+        Ok(())
+    }
+
+    #[test]
+    fn refuses_newer_versions() {
+        let mut raw: Value = serde_yaml::from_str("version: 999\n").unwrap();
+        assert!(migrate_value(&mut raw).is_err());
+    }
+
+    #[test]
+    fn finds_unknown_fields() -> anyhow::Result<()> {
+        let temp = diem_temppath::TempPath::new();
+        temp.create_as_dir()?;
+        let path = temp.path().join("libra-cli-config.yaml");
+        fs::write(&path, format!("{}favorite_color: blue\n", V0))?;
+
+        let report = validate_file(&path)?;
+        assert!(report.is_valid());
+        assert_eq!(report.version, 0);
+        assert_eq!(report.unknown_fields, vec!["favorite_color".to_string()]);
+
+        let notes = migrate_file(&path)?.expect("v0 file should be migrated");
+        assert_eq!(notes.applied.len(), MIGRATIONS.len());
+        assert_eq!(validate_file(&path)?.version, CONFIG_VERSION);
+        assert!(notes.backup.exists());
+        assert_eq!(
+            notes.backup,
+            temp.path().join("libra-cli-config.yaml.v0.bak")
+        );
+        // already current, nothing to do
+        assert!(migrate_file(&path)?.is_none());
+        Ok(())
+    }
+}
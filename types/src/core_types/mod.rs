@@ -1,7 +1,9 @@
 pub mod app_cfg;
 pub mod block;
+pub mod config_migrations;
 pub mod fixtures;
 pub mod legacy_currency_info;
 pub mod mode_ol;
 pub mod network_playlist;
+pub mod ol_features;
 pub mod pledge;
@@ -1,11 +1,22 @@
 //! network configs
 use crate::exports::{Client, NamedChain};
+use crate::type_extensions::failover_client::DEFAULT_MAX_LAG;
 use anyhow::{bail, Context};
-use futures::{stream::FuturesUnordered, StreamExt};
+use futures::{future::join_all, stream::FuturesUnordered, StreamExt};
 use rand::{seq::SliceRandom, thread_rng};
 use serde_with::{serde_as, DisplayFromStr};
+use std::time::{Duration, Instant};
 use url::Url;
 
+/// weight of the newest probe in the rolling averages
+pub const SCORE_SMOOTHING: f64 = 0.2;
+/// a node this many versions behind the best one loses half its score
+pub const SCORE_LAG_SCALE: f64 = 1_000.0;
+/// a node this slow loses half its score
+pub const SCORE_LATENCY_SCALE_MS: f64 = 1_000.0;
+/// probes taking longer count as errors
+pub const PROBE_TIMEOUT_SECS: u64 = 10;
+
 #[derive(serde::Deserialize, serde::Serialize, Debug, Clone)]
 pub struct HostProfile {
     pub url: Url,
@@ -16,6 +27,52 @@ pub struct HostProfile {
     pub is_api: bool,
     #[serde(default)]
     pub is_sync: bool,
+    /// measured by `config network monitor`, none if never probed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub score: Option<NodeScore>,
+}
+
+/// Rolling health measurements of a node
+#[derive(serde::Deserialize, serde::Serialize, Debug, Clone, Default, PartialEq)]
+pub struct NodeScore {
+    /// average response time in milliseconds
+    pub latency_ms: f64,
+    /// average number of versions behind the most synced node
+    pub lag: f64,
+    /// average share of failed probes, from 0 to 1
+    pub error_rate: f64,
+    /// number of probes so far
+    pub probes: u64,
+    /// from 0 to 100, higher is better
+    pub score: f64,
+}
+
+impl NodeScore {
+    /// add the result of a probe to the averages: the latency and lag,
+    /// or None if the probe failed
+    pub fn update(&mut self, probe: Option<(f64, u64)>) {
+        let first = self.probes == 0;
+        let avg = |old: f64, new: f64| {
+            if first {
+                new
+            } else {
+                old * (1.0 - SCORE_SMOOTHING) + new * SCORE_SMOOTHING
+            }
+        };
+        match probe {
+            Some((latency_ms, lag)) => {
+                self.latency_ms = avg(self.latency_ms, latency_ms);
+                self.lag = avg(self.lag, lag as f64);
+                self.error_rate = avg(self.error_rate, 0.0);
+            }
+            None => self.error_rate = avg(self.error_rate, 1.0),
+        }
+        self.probes += 1;
+        self.score = 100.0
+            * (1.0 - self.error_rate)
+            * (1.0 / (1.0 + self.latency_ms / SCORE_LATENCY_SCALE_MS))
+            * (1.0 / (1.0 + self.lag / SCORE_LAG_SCALE));
+    }
 }
 /// from the list of seed_peers find the best peer to connect to.
 /// First does a light port check on all peers, and eliminated unresponsive
@@ -31,6 +88,7 @@ impl Default for HostProfile {
             is_api: false,
             is_sync: false,
             note: "default".to_string(),
+            score: None,
         }
     }
 }
@@ -63,6 +121,23 @@ impl HostProfile {
 
         Ok(self)
     }
+
+    /// Time a request for the ledger version. None if it fails or times out.
+    async fn probe(&self) -> Option<(f64, u64)> {
+        let client = Client::new(self.url.clone());
+        let start = Instant::now();
+        let res = tokio::time::timeout(Duration::from_secs(PROBE_TIMEOUT_SECS), client.get_index())
+            .await
+            .ok()?
+            .ok()?;
+        let latency_ms = start.elapsed().as_secs_f64() * 1000.0;
+        Some((latency_ms, res.into_inner().ledger_version.into()))
+    }
+
+    /// the score, zero if never probed
+    pub fn score(&self) -> f64 {
+        self.score.as_ref().map(|s| s.score).unwrap_or(0.0)
+    }
 }
 #[serde_as]
 #[derive(serde::Deserialize, serde::Serialize, Debug, Clone)]
@@ -123,6 +198,7 @@ impl NetworkPlaylist {
                 version: 0,
                 is_api: true,
                 is_sync: true,
+                score: None,
             }],
         }
     }
@@ -166,16 +242,29 @@ impl NetworkPlaylist {
         urls_list.shuffle(&mut rng);
     }
 
+    /// The nodes by score, best first. Nodes which were never probed keep
+    /// the playlist order, after the scored ones.
+    pub fn ranked(&self) -> Vec<&HostProfile> {
+        let mut list: Vec<&HostProfile> = self.nodes.iter().collect();
+        list.sort_by(|a, b| match (&a.score, &b.score) {
+            (Some(x), Some(y)) => y.score.total_cmp(&x.score),
+            (Some(_), None) => std::cmp::Ordering::Less,
+            (None, Some(_)) => std::cmp::Ordering::Greater,
+            (None, None) => std::cmp::Ordering::Equal,
+        });
+        list
+    }
+
     pub fn all_urls(&self) -> anyhow::Result<Vec<Url>> {
-        let list_urls: Vec<Url> = self.nodes.iter().map(|e| e.url.to_owned()).collect();
+        let list_urls: Vec<Url> = self.ranked().iter().map(|e| e.url.to_owned()).collect();
 
         Ok(list_urls)
     }
 
     pub fn the_good_ones(&self) -> anyhow::Result<Vec<Url>> {
         let list_urls: Vec<Url> = self
-            .nodes
-            .iter()
+            .ranked()
+            .into_iter()
             .filter_map(|e| {
                 if e.is_sync && e.is_api {
                     Some(e.url.to_owned())
@@ -277,4 +366,70 @@ impl NetworkPlaylist {
         self.nodes = checked;
         Ok(self)
     }
+
+    /// Probe every node once, and update their rolling scores.
+    /// Unlike `refresh_sync_status`, nodes which fail are kept, with a
+    /// lower score.
+    pub async fn probe_and_score(&mut self) -> anyhow::Result<()> {
+        let probes = join_all(self.nodes.iter().map(|n| n.probe())).await;
+        let highest = probes.iter().flatten().map(|(_, v)| *v).max();
+
+        for (node, probe) in self.nodes.iter_mut().zip(probes) {
+            let measured = match (probe, highest) {
+                (Some((latency_ms, version)), Some(h)) => {
+                    node.version = version;
+                    node.is_api = true;
+                    node.is_sync = h - version <= DEFAULT_MAX_LAG;
+                    Some((latency_ms, h - version))
+                }
+                _ => {
+                    node.is_api = false;
+                    node.is_sync = false;
+                    None
+                }
+            };
+            node.score
+                .get_or_insert_with(NodeScore::default)
+                .update(measured);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn score_rolls() {
+        let mut s = NodeScore::default();
+        s.update(Some((0.0, 0)));
+        assert_eq!(s.score, 100.0);
+        // a failure lowers the score, but doesn't zero it
+        s.update(None);
+        assert!(s.score > 0.0 && s.score < 100.0);
+        assert_eq!(s.probes, 2);
+        // a slow lagging node scores less than a fast synced one
+        let mut slow = NodeScore::default();
+        slow.update(Some((SCORE_LATENCY_SCALE_MS, SCORE_LAG_SCALE as u64)));
+        assert_eq!(slow.score, 25.0);
+    }
+
+    #[test]
+    fn ranked_by_score() -> anyhow::Result<()> {
+        let mut np = NetworkPlaylist::new(Some("http://localhost:1".parse()?), None);
+        np.add_url("http://localhost:2".parse()?);
+        np.add_url("http://localhost:3".parse()?);
+        np.nodes[1].score = Some(NodeScore {
+            score: 10.0,
+            ..Default::default()
+        });
+        np.nodes[2].score = Some(NodeScore {
+            score: 90.0,
+            ..Default::default()
+        });
+        let ports: Vec<_> = np.all_urls()?.iter().map(|u| u.port()).collect();
+        assert_eq!(ports, vec![Some(3), Some(2), Some(1)]);
+        Ok(())
+    }
 }
@@ -0,0 +1,7 @@
+//! The ol feature flags checked by the tools, see ol_features_constants in
+//! ol_features.move
+
+/// CONSENSUS_KEY_ROTATION_ENABLED, validators can rotate their consensus key
+pub const CONSENSUS_KEY_ROTATION: u64 = 26;
+/// USER_PLEDGE_INFRA_ENABLED, users can pledge to the infra escrow
+pub const USER_PLEDGE_INFRA: u64 = 27;
@@ -1,62 +1,40 @@
-use move_core_types::{
-    ident_str,
-    identifier::IdentStr,
-    move_resource::{MoveResource, MoveStructType},
-};
+use diem_api_types::{HexEncodedBytes, U64};
+use move_core_types::account_address::AccountAddress;
 use serde::{Deserialize, Serialize};
 
-/// Struct that represents a transaction schedule.
+/// Struct that represents a transaction schedule, as the JSON of the
+/// REST API returns it. For the BCS resource see donor_voice_txs::TxScheduleResource.
 #[derive(Debug, Serialize, Deserialize)]
 pub struct TxSchedule {
-    scheduled: Vec<TimedTransfer>,
-    veto: Vec<TimedTransfer>,
-    paid: Vec<TimedTransfer>,
-    guid_capability: GUIDCapability, // we need this for the MultiSig
+    pub scheduled: Vec<TimedTransfer>,
+    pub veto: Vec<TimedTransfer>,
+    pub paid: Vec<TimedTransfer>,
+    pub guid_capability: GUIDCapability, // we need this for the MultiSig
 }
 
-impl MoveStructType for TxSchedule {
-    const MODULE_NAME: &'static IdentStr = ident_str!("donor_voice");
-    const STRUCT_NAME: &'static IdentStr = ident_str!("TxSchedule");
-}
-
-impl MoveResource for TxSchedule {}
-
 #[derive(Debug, Serialize, Deserialize)]
 pub struct TimedTransfer {
-    uid: diem_api_types::U128, // copy of ID generated by MultiSig for the transaction
-    deadline: diem_api_types::U64, // what epoch does the transaction execute
-    tx: Payment,               // The transaction properties
-    epoch_latest_veto_received: u64, // This is to check if we need to extend the deadline
+    pub uid: GuidId,   // copy of ID generated by MultiSig for the transaction
+    pub deadline: U64, // what epoch does the transaction execute
+    pub tx: Payment,   // The transaction properties
+    pub epoch_latest_veto_received: U64, // This is to check if we need to extend the deadline
 }
 
-impl MoveStructType for TimedTransfer {
-    const MODULE_NAME: &'static IdentStr = ident_str!("donor_voice");
-    const STRUCT_NAME: &'static IdentStr = ident_str!("TimedTransfer");
+/// guid::ID, the creation number is the transaction id
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GuidId {
+    pub creation_num: U64,
+    pub addr: AccountAddress,
 }
 
-impl MoveResource for TimedTransfer {}
-
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Payment {
-    payee: diem_api_types::U128,
-    value: diem_api_types::U64,
-    description: Vec<u8>,
+    pub payee: AccountAddress,
+    pub value: U64,
+    pub description: HexEncodedBytes,
 }
 
-impl MoveStructType for Payment {
-    const MODULE_NAME: &'static IdentStr = ident_str!("donor_voice");
-    const STRUCT_NAME: &'static IdentStr = ident_str!("Payment");
-}
-
-impl MoveResource for Payment {}
-
 #[derive(Debug, Serialize, Deserialize)]
 pub struct GUIDCapability {
-    addr: diem_api_types::U128,
+    pub addr: AccountAddress,
 }
-impl MoveStructType for GUIDCapability {
-    const MODULE_NAME: &'static IdentStr = ident_str!("account");
-    const STRUCT_NAME: &'static IdentStr = ident_str!("GUIDCapability");
-}
-
-impl MoveResource for GUIDCapability {}
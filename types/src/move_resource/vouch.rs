@@ -26,3 +26,64 @@ impl MoveStructType for MyVouchesResource {
 }
 
 impl MoveResource for MyVouchesResource {}
+
+/// the incoming vouches of an account
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ReceivedVouchesResource {
+    pub incoming_vouches: Vec<AccountAddress>,
+    /// the epoch when each vouch was received
+    pub epoch_vouched: Vec<u64>,
+}
+
+impl MoveStructType for ReceivedVouchesResource {
+    const MODULE_NAME: &'static IdentStr = ident_str!("vouch");
+    const STRUCT_NAME: &'static IdentStr = ident_str!("ReceivedVouches");
+}
+
+impl MoveResource for ReceivedVouchesResource {}
+
+/// the outgoing vouches of an account
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct GivenVouchesResource {
+    pub outgoing_vouches: Vec<AccountAddress>,
+    /// the epoch when each vouch was given
+    pub epoch_vouched: Vec<u64>,
+}
+
+impl MoveStructType for GivenVouchesResource {
+    const MODULE_NAME: &'static IdentStr = ident_str!("vouch");
+    const STRUCT_NAME: &'static IdentStr = ident_str!("GivenVouches");
+}
+
+impl MoveResource for GivenVouchesResource {}
+
+/// the cached trust score of an account, see page_rank_lazy.move
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct UserTrustRecordResource {
+    pub cached_score: u64,
+    pub score_computed_at_timestamp: u64,
+    pub is_stale: bool,
+}
+
+impl MoveStructType for UserTrustRecordResource {
+    const MODULE_NAME: &'static IdentStr = ident_str!("page_rank_lazy");
+    const STRUCT_NAME: &'static IdentStr = ident_str!("UserTrustRecord");
+}
+
+impl MoveResource for UserTrustRecordResource {}
+
+/// the registry of the roots of trust, see root_of_trust.move
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RootOfTrustResource {
+    pub roots: Vec<AccountAddress>,
+    pub last_updated_secs: u64,
+    pub minimum_cohort: u64,
+    pub rotate_window_days: u64,
+}
+
+impl MoveStructType for RootOfTrustResource {
+    const MODULE_NAME: &'static IdentStr = ident_str!("root_of_trust");
+    const STRUCT_NAME: &'static IdentStr = ident_str!("RootOfTrust");
+}
+
+impl MoveResource for RootOfTrustResource {}
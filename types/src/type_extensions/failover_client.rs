@@ -0,0 +1,398 @@
+//! A client over all the nodes of a network playlist, instead of a single URL.
+//! Requests which fail, time out, or land on a node lagging behind the
+//! others are retried on the next healthy node. Nodes are only probed when
+//! they are needed.
+//! Reads can also be sent to several nodes at once, to check they agree.
+
+use crate::{
+    core_types::{app_cfg::AppCfg, network_playlist::NetworkPlaylist},
+    type_extensions::client_ext::DEFAULT_TIMEOUT_SECS,
+};
+use anyhow::{bail, Context};
+use diem_sdk::{
+    rest_client::Client,
+    types::chain_id::{ChainId, NamedChain},
+};
+use futures::future::join_all;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{
+    future::Future,
+    sync::atomic::{AtomicU64, AtomicUsize, Ordering},
+    time::Duration,
+};
+use url::Url;
+
+/// a node more than this many versions behind the highest one seen is lagging
+pub const DEFAULT_MAX_LAG: u64 = 1_000;
+/// how many nodes a request is tried on before giving up
+pub const DEFAULT_MAX_ATTEMPTS: usize = 3;
+
+#[derive(Debug)]
+pub struct FailoverClient {
+    urls: Vec<Url>,
+    /// index of the node the last successful request went to
+    current: AtomicUsize,
+    /// highest ledger version seen on any node
+    highest_version: AtomicU64,
+    timeout: Duration,
+    max_lag: u64,
+    max_attempts: usize,
+}
+
+impl FailoverClient {
+    pub fn new(urls: Vec<Url>) -> anyhow::Result<Self> {
+        if urls.is_empty() {
+            bail!("no node urls to connect to");
+        }
+        Ok(Self {
+            urls,
+            current: AtomicUsize::new(0),
+            highest_version: AtomicU64::new(0),
+            timeout: Duration::from_secs(DEFAULT_TIMEOUT_SECS),
+            max_lag: DEFAULT_MAX_LAG,
+            max_attempts: DEFAULT_MAX_ATTEMPTS,
+        })
+    }
+
+    /// nodes verified as synced come first, then the rest of the playlist
+    pub fn from_playlist(np: &NetworkPlaylist) -> anyhow::Result<Self> {
+        let mut urls = np.the_good_ones().unwrap_or_default();
+        for u in np.all_urls()? {
+            if !urls.contains(&u) {
+                urls.push(u);
+            }
+        }
+        Self::new(urls)
+    }
+
+    /// use the playlist of the chain in the config, or the default chain
+    pub fn from_app_cfg(app_cfg: &AppCfg, chain_id: Option<NamedChain>) -> anyhow::Result<Self> {
+        Self::from_playlist(&app_cfg.get_network_profile(chain_id)?)
+    }
+
+    /// timeout of each attempt
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    pub fn with_max_lag(mut self, max_lag: u64) -> Self {
+        self.max_lag = max_lag;
+        self
+    }
+
+    pub fn with_max_attempts(mut self, max_attempts: usize) -> Self {
+        self.max_attempts = max_attempts.max(1);
+        self
+    }
+
+    pub fn urls(&self) -> &[Url] {
+        &self.urls
+    }
+
+    pub fn current_url(&self) -> &Url {
+        &self.urls[self.current.load(Ordering::Relaxed) % self.urls.len()]
+    }
+
+    /// a client for the node currently in use
+    pub fn client(&self) -> Client {
+        Client::new(self.current_url().to_owned())
+    }
+
+    /// Check the node answers in time and is not lagging behind the highest
+    /// version seen so far. Returns its ledger version.
+    pub async fn check_node(&self, url: &Url) -> anyhow::Result<u64> {
+        let res = tokio::time::timeout(self.timeout, Client::new(url.to_owned()).get_index())
+            .await
+            .context(format!("{} timed out", url))??;
+        let version: u64 = res.into_inner().ledger_version.into();
+
+        let highest = self
+            .highest_version
+            .fetch_max(version, Ordering::Relaxed)
+            .max(version);
+        if highest - version > self.max_lag {
+            bail!(
+                "{} is lagging: version {}, highest seen {}",
+                url,
+                version,
+                highest
+            );
+        }
+        Ok(version)
+    }
+
+    /// Switch to the first node of the playlist which answers. Nodes are
+    /// only probed until one does, the others are checked when failing over.
+    pub async fn connect(&self) -> anyhow::Result<(Client, ChainId)> {
+        let mut errs = vec![];
+        for (i, url) in self.urls.iter().enumerate() {
+            let index = tokio::time::timeout(self.timeout, Client::new(url.to_owned()).get_index())
+                .await
+                .context("timed out")
+                .and_then(|r| Ok(r?.into_inner()));
+            match index {
+                Ok(index) => {
+                    self.highest_version
+                        .fetch_max(index.ledger_version.into(), Ordering::Relaxed);
+                    self.current.store(i, Ordering::Relaxed);
+                    return Ok((self.client(), ChainId::new(index.chain_id)));
+                }
+                Err(e) => errs.push(format!("{}: {:#}", url, e)),
+            }
+        }
+        bail!(
+            "none of the nodes in the playlist answered:\n{}",
+            errs.join("\n")
+        )
+    }
+
+    /// Run a request, starting with the current node. If it fails or
+    /// times out, move on to the next node which passes `check_node`.
+    pub async fn run<T, F, Fut>(&self, f: F) -> anyhow::Result<T>
+    where
+        F: Fn(Client) -> Fut,
+        Fut: Future<Output = anyhow::Result<T>>,
+    {
+        let start = self.current.load(Ordering::Relaxed);
+        let mut errs = vec![];
+        let mut attempts = 0;
+
+        for i in 0..self.urls.len() {
+            if attempts >= self.max_attempts {
+                break;
+            }
+            let idx = (start + i) % self.urls.len();
+            let url = &self.urls[idx];
+
+            // the current node was fine on the last request, check the others first
+            if i > 0 {
+                if let Err(e) = self.check_node(url).await {
+                    errs.push(e.to_string());
+                    continue;
+                }
+            }
+
+            attempts += 1;
+            match tokio::time::timeout(self.timeout, f(Client::new(url.to_owned()))).await {
+                Ok(Ok(res)) => {
+                    self.current.store(idx, Ordering::Relaxed);
+                    return Ok(res);
+                }
+                Ok(Err(e)) => errs.push(format!("{}: {:#}", url, e)),
+                Err(_) => errs.push(format!("{} timed out", url)),
+            }
+        }
+        bail!("request failed on every node tried:\n{}", errs.join("\n"))
+    }
+
+    /// Send a read to the first `n` nodes, and compare the answers.
+    pub async fn quorum_read<F, Fut>(&self, n: usize, f: F) -> anyhow::Result<QuorumReport>
+    where
+        F: Fn(Client) -> Fut,
+        Fut: Future<Output = anyhow::Result<Value>>,
+    {
+        let responses = join_all(self.urls.iter().take(n.max(1)).map(|url| {
+            let client = Client::new(url.to_owned());
+            let fetch = f(client.clone());
+            async move {
+                let read = async {
+                    // the version first, so a node can't look ahead of its answer
+                    let version: u64 = client.get_index().await?.into_inner().ledger_version.into();
+                    let value = fetch.await?;
+                    anyhow::Ok((version, value))
+                };
+                match tokio::time::timeout(self.timeout, read).await {
+                    Ok(Ok((version, value))) => NodeResponse {
+                        url: url.to_owned(),
+                        ledger_version: Some(version),
+                        value: Some(value),
+                        error: None,
+                    },
+                    Ok(Err(e)) => NodeResponse {
+                        url: url.to_owned(),
+                        ledger_version: None,
+                        value: None,
+                        error: Some(format!("{:#}", e)),
+                    },
+                    Err(_) => NodeResponse {
+                        url: url.to_owned(),
+                        ledger_version: None,
+                        value: None,
+                        error: Some("timed out".to_string()),
+                    },
+                }
+            }
+        }))
+        .await;
+
+        Ok(QuorumReport::new(responses, self.max_lag))
+    }
+}
+
+/// What one node answered to a quorum read
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NodeResponse {
+    pub url: Url,
+    pub ledger_version: Option<u64>,
+    pub value: Option<Value>,
+    pub error: Option<String>,
+}
+
+/// The answers of all the nodes of a quorum read, and where they disagree
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuorumReport {
+    pub responses: Vec<NodeResponse>,
+    /// the value most nodes answered, if more than half of them did
+    pub majority: Option<Value>,
+    /// everything that looks wrong: errors, lagging versions, different values
+    pub disagreements: Vec<String>,
+}
+
+impl QuorumReport {
+    pub fn new(responses: Vec<NodeResponse>, max_lag: u64) -> Self {
+        let mut disagreements = vec![];
+
+        for r in &responses {
+            if let Some(e) = &r.error {
+                disagreements.push(format!("{} failed: {}", r.url, e));
+            }
+        }
+
+        let versions: Vec<u64> = responses.iter().filter_map(|r| r.ledger_version).collect();
+        if let (Some(min), Some(max)) = (versions.iter().min(), versions.iter().max()) {
+            if max - min > max_lag {
+                disagreements.push(format!(
+                    "ledger versions differ by {}: from {} to {}",
+                    max - min,
+                    min,
+                    max
+                ));
+            }
+        }
+
+        // count the distinct values
+        let mut tally: Vec<(&Value, usize)> = vec![];
+        for v in responses.iter().filter_map(|r| r.value.as_ref()) {
+            match tally.iter_mut().find(|(t, _)| *t == v) {
+                Some((_, count)) => *count += 1,
+                None => tally.push((v, 1)),
+            }
+        }
+        tally.sort_by(|a, b| b.1.cmp(&a.1));
+
+        let majority = tally
+            .first()
+            .filter(|(_, count)| *count * 2 > responses.len())
+            .map(|(v, _)| (*v).to_owned());
+
+        if tally.len() > 1 {
+            for r in &responses {
+                if r.value.is_some() && r.value != majority {
+                    disagreements.push(format!(
+                        "{} answered a different value at version {}",
+                        r.url,
+                        r.ledger_version.unwrap_or_default()
+                    ));
+                }
+            }
+        }
+        if majority.is_none() {
+            disagreements.push("no value was answered by a majority of the nodes".to_string());
+        }
+
+        Self {
+            responses,
+            majority,
+            disagreements,
+        }
+    }
+
+    pub fn is_consistent(&self) -> bool {
+        self.disagreements.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn response(port: u16, version: u64, value: Value) -> NodeResponse {
+        NodeResponse {
+            url: format!("http://localhost:{}", port).parse().unwrap(),
+            ledger_version: Some(version),
+            value: Some(value),
+            error: None,
+        }
+    }
+
+    #[test]
+    fn quorum_agrees() {
+        let report = QuorumReport::new(
+            vec![response(1, 100, json!(5)), response(2, 101, json!(5))],
+            DEFAULT_MAX_LAG,
+        );
+        assert!(report.is_consistent());
+        assert_eq!(report.majority, Some(json!(5)));
+    }
+
+    #[test]
+    fn quorum_flags_disagreements() {
+        let report = QuorumReport::new(
+            vec![
+                response(1, 100, json!(5)),
+                response(2, 100, json!(5)),
+                response(3, 100 + DEFAULT_MAX_LAG + 1, json!(6)),
+            ],
+            DEFAULT_MAX_LAG,
+        );
+        assert_eq!(report.majority, Some(json!(5)));
+        // one for the versions, one for the value
+        assert_eq!(report.disagreements.len(), 2);
+    }
+
+    #[test]
+    fn quorum_without_majority() {
+        let report = QuorumReport::new(
+            vec![response(1, 100, json!(5)), response(2, 100, json!(6))],
+            DEFAULT_MAX_LAG,
+        );
+        assert!(report.majority.is_none());
+    }
+
+    #[test]
+    fn playlist_good_nodes_first() -> anyhow::Result<()> {
+        let mut np = NetworkPlaylist::new(Some("http://localhost:1".parse()?), None);
+        np.add_url("http://localhost:2".parse()?);
+        np.nodes[1].is_api = true;
+        np.nodes[1].is_sync = true;
+
+        let fc = FailoverClient::from_playlist(&np)?;
+        assert_eq!(fc.urls().len(), 2);
+        assert_eq!(fc.current_url().port(), Some(2));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn run_fails_when_no_node_answers() -> anyhow::Result<()> {
+        let fc = FailoverClient::new(vec!["http://localhost:1".parse()?])?;
+        let res = fc
+            .run(|c| async move { c.get_index().await.map_err(anyhow::Error::from) })
+            .await;
+        assert!(res.is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn connect_fails_when_no_node_answers() -> anyhow::Result<()> {
+        let fc = FailoverClient::new(vec![
+            "http://localhost:1".parse()?,
+            "http://localhost:2".parse()?,
+        ])?;
+        let err = fc.connect().await.unwrap_err().to_string();
+        assert!(err.contains("localhost:1") && err.contains("localhost:2"));
+        Ok(())
+    }
+}
@@ -1,4 +1,5 @@
 pub mod cli_config_ext;
 pub mod client_ext;
 pub mod ed25519_private_key_ext;
+pub mod failover_client;
 pub mod global_config_ext;